@@ -0,0 +1,81 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Compares looking up learned values keyed on full states to looking them up keyed on ids.
+
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rurel::mdp::State;
+use rurel::table::{IdTable, QTable};
+
+const STATES: u64 = 10_000;
+const CELLS: usize = 16;
+
+// A board of 16 cells, each holding one of 4 pieces.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Board {
+    cells: Vec<u8>,
+}
+
+impl Board {
+    fn new(index: u64) -> Board {
+        Board {
+            cells: (0..CELLS)
+                .map(|cell| (index >> (2 * cell) & 3) as u8)
+                .collect(),
+        }
+    }
+}
+
+impl State for Board {
+    type A = u8;
+
+    fn reward(&self) -> f64 {
+        0.
+    }
+
+    fn actions(&self) -> Vec<u8> {
+        (0..4).collect()
+    }
+
+    fn id(&self) -> Option<u64> {
+        Some(
+            self.cells
+                .iter()
+                .rev()
+                .fold(0, |id, &piece| id << 2 | piece as u64),
+        )
+    }
+}
+
+fn filled<T: QTable<Board>>(mut table: T, boards: &[Board]) -> T {
+    for board in boards {
+        for action in board.actions() {
+            table.insert(board.clone(), action, action as f64);
+        }
+    }
+    table
+}
+
+fn lookup<T: QTable<Board>>(table: &T, boards: &[Board]) -> f64 {
+    boards
+        .iter()
+        .map(|board| table.get(board, &1).unwrap() + table.best(board).unwrap().1)
+        .sum()
+}
+
+fn table(c: &mut Criterion) {
+    let boards: Vec<Board> = (0..STATES).map(|i| Board::new(i * 7919)).collect();
+    let states = filled(HashMap::new(), &boards);
+    let ids = filled(IdTable::new(), &boards);
+
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("state", |b| b.iter(|| lookup(black_box(&states), &boards)));
+    group.bench_function("id", |b| b.iter(|| lookup(black_box(&ids), &boards)));
+    group.finish();
+}
+
+criterion_group!(benches, table);
+criterion_main!(benches);
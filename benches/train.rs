@@ -0,0 +1,109 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Compares training with trait objects to training with the monomorphized loop.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rurel::mdp::{Agent, State};
+use rurel::strategy::explore::RandomExploration;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::AgentTrainer;
+
+const STEPS: u32 = 10_000;
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct MyState {
+    x: i32,
+    y: i32,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+enum MyAction {
+    Move { dx: i32, dy: i32 },
+}
+
+impl State for MyState {
+    type A = MyAction;
+
+    fn reward(&self) -> f64 {
+        let (tx, ty) = (10, 10);
+        let d = (((tx - self.x).pow(2) + (ty - self.y).pow(2)) as f64).sqrt();
+        -d
+    }
+
+    fn actions(&self) -> Vec<MyAction> {
+        vec![
+            MyAction::Move { dx: 0, dy: -1 },
+            MyAction::Move { dx: 0, dy: 1 },
+            MyAction::Move { dx: -1, dy: 0 },
+            MyAction::Move { dx: 1, dy: 0 },
+        ]
+    }
+}
+
+struct MyAgent {
+    state: MyState,
+}
+
+impl Agent<MyState> for MyAgent {
+    fn current_state(&self) -> &MyState {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &MyAction) {
+        match action {
+            &MyAction::Move { dx, dy } => {
+                self.state = MyState {
+                    x: (((self.state.x + dx) % 21) + 21) % 21,
+                    y: (((self.state.y + dy) % 21) + 21) % 21,
+                }
+            }
+        }
+    }
+}
+
+fn agent() -> MyAgent {
+    MyAgent {
+        state: MyState { x: 0, y: 0 },
+    }
+}
+
+fn train(c: &mut Criterion) {
+    let mut group = c.benchmark_group("train");
+    group.bench_function("dyn", |b| {
+        b.iter_batched(
+            || (AgentTrainer::new(), agent()),
+            |(mut trainer, mut agent)| {
+                trainer.train(
+                    &mut agent,
+                    &QLearning::new(0.2, 0.01, 2.),
+                    &mut FixedIterations::new(STEPS),
+                    &RandomExploration::with_seed(0),
+                );
+                trainer
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("static", |b| {
+        b.iter_batched(
+            || (AgentTrainer::new(), agent()),
+            |(mut trainer, mut agent)| {
+                trainer.train_static(
+                    &mut agent,
+                    &QLearning::new(0.2, 0.01, 2.),
+                    &mut FixedIterations::new(STEPS),
+                    &RandomExploration::with_seed(0),
+                );
+                trainer
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, train);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the training configuration.
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::{ExplorationStrategy, RandomExploration};
+use crate::strategy::learn::{LearningStrategy, QLearning};
+use crate::strategy::terminate::{FixedIterations, TerminationStrategy};
+use crate::table::QTable;
+use crate::AgentTrainer;
+
+/// A `TrainConfig` configures a training run of an [AgentTrainer] with named methods, as an
+/// alternative to passing all strategies to [AgentTrainer::train()]. It is created using
+/// [AgentTrainer::configure()].
+///
+/// Strategies that are not set default to [QLearning] with learning rate 0.2, discount factor 0.01
+/// and initial value 2.0, [FixedIterations] with 100000 iterations, and [RandomExploration].
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+/// trainer
+///     .configure()
+///     .learning_strategy(QLearning::new(0.2, 0.01, 2.))
+///     .termination_strategy(FixedIterations::new(100000))
+///     .exploration_strategy(RandomExploration::new())
+///     .run(&mut agent);
+///
+/// let test_state = MyState { x: 10, y: 9 };
+/// let go_up = MyAction { dx: 0, dy: -1 };
+/// let go_down = MyAction { dx: 0, dy: 1 };
+/// assert!(trainer.expected_value(&test_state, &go_down)
+///     > trainer.expected_value(&test_state, &go_up));
+/// ```
+pub struct TrainConfig<'a, S, T>
+where
+    S: State,
+{
+    trainer: &'a mut AgentTrainer<S, T>,
+    learning_strategy: Box<dyn LearningStrategy<S> + 'a>,
+    termination_strategy: Box<dyn TerminationStrategy<S> + 'a>,
+    exploration_strategy: Box<dyn ExplorationStrategy<S> + 'a>,
+}
+
+impl<'a, S, T> TrainConfig<'a, S, T>
+where
+    S: State,
+    T: QTable<S>,
+{
+    pub(crate) fn new(trainer: &'a mut AgentTrainer<S, T>) -> TrainConfig<'a, S, T> {
+        TrainConfig {
+            trainer,
+            learning_strategy: Box::new(QLearning::new(0.2, 0.01, 2.)),
+            termination_strategy: Box::new(FixedIterations::new(100000)),
+            exploration_strategy: Box::new(RandomExploration::new()),
+        }
+    }
+
+    /// Sets the [LearningStrategy] to train with.
+    pub fn learning_strategy(mut self, strategy: impl LearningStrategy<S> + 'a) -> Self {
+        self.learning_strategy = Box::new(strategy);
+        self
+    }
+
+    /// Sets the [TerminationStrategy] that decides when to stop training.
+    pub fn termination_strategy(mut self, strategy: impl TerminationStrategy<S> + 'a) -> Self {
+        self.termination_strategy = Box::new(strategy);
+        self
+    }
+
+    /// Sets the [ExplorationStrategy] to train with.
+    pub fn exploration_strategy(mut self, strategy: impl ExplorationStrategy<S> + 'a) -> Self {
+        self.exploration_strategy = Box::new(strategy);
+        self
+    }
+
+    /// Trains the [AgentTrainer] using the given [Agent] with the configured strategies.
+    pub fn run(mut self, agent: &mut dyn Agent<S>) {
+        self.trainer.train(
+            agent,
+            self.learning_strategy.as_ref(),
+            self.termination_strategy.as_mut(),
+            self.exploration_strategy.as_ref(),
+        );
+    }
+}
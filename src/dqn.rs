@@ -1,34 +1,720 @@
 // source: https://raw.githubusercontent.com/coreylowman/dfdx/main/examples/rl-dqn.rs
 use dfdx::{
     nn,
-    optim::{Momentum, Sgd, SgdConfig},
+    optim::{Adam, AdamConfig, Momentum, RMSprop, RMSpropConfig, Sgd, SgdConfig},
     prelude::*,
 };
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
 
 use crate::{
     mdp::{Agent, State},
+    normalize::{Feature, RewardNormalizer, StateNormalizer},
+    step_reward,
     strategy::{explore::ExplorationStrategy, terminate::TerminationStrategy},
 };
 
-const BATCH: usize = 64;
+/// The number of transitions in a training batch.
+pub const BATCH: usize = 64;
 
-type QNetwork<const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize> = (
+/// The element type of the tensors of a [DQNAgentTrainer], and of the state and action features it
+/// is trained on: `f32` (the default) or `f64`.
+///
+/// An `f64` network represents the action values more precisely, which helps when the values of
+/// actions differ by less than `f32` can resolve, or when rewards span many orders of magnitude.
+/// Its weights take twice the memory, and training is noticeably slower than with `f32`.
+///
+/// The element type is the last type parameter of the [DQNAgentTrainer], and must match that of
+/// its network type.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyAction(i32);
+/// # impl From<MyAction> for [f64; 2] {
+/// #     fn from(a: MyAction) -> Self { if a.0 < 0 { [1., 0.] } else { [0., 1.] } }
+/// # }
+/// # impl From<[f64; 2]> for MyAction {
+/// #     fn from(v: [f64; 2]) -> Self { MyAction(if v[0] >= v[1] { -1 } else { 1 }) }
+/// # }
+/// # impl State for MyState {
+/// #     type A = MyAction;
+/// #     fn reward(&self) -> f64 { -((self.0 - 10).abs() as f64) }
+/// #     fn actions(&self) -> Vec<MyAction> { vec![MyAction(-1), MyAction(1)] }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &MyAction) {
+/// #         self.state = MyState((self.state.0 + action.0).clamp(0, 20))
+/// #     }
+/// # }
+/// use rurel::dqn::{DQNAgentTrainer, QNetworkDevice, BATCH};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// // The features of states and actions are f64 as well.
+/// impl From<MyState> for [f64; 2] {
+///     fn from(s: MyState) -> Self { [s.0 as f64, 10.] }
+/// }
+///
+/// type Trainer = DQNAgentTrainer<MyState, 2, 2, 16, QNetworkDevice<2, 2, 16, f64>, f64>;
+/// let mut trainer = Trainer::new(0.9, 1e-3);
+/// let mut agent = MyAgent { state: MyState(0) };
+/// trainer.train(
+///     &mut agent,
+///     &mut FixedIterations::new(4 * BATCH as u32),
+///     &RandomExploration::new(),
+/// );
+///
+/// assert!(trainer.gradient_steps() > 0);
+/// let values: [f64; 2] = trainer.expected_value(&MyState(5));
+/// assert!(values.iter().all(|v| v.is_finite()));
+/// assert!(trainer.best_action(&MyState(5)).is_some());
+/// ```
+pub trait DQNFloat: Dtype + Feature + From<f32>
+where
+    Cpu: Device<Self>,
+{
+    /// Saves the given network to a `.npz` file at the given path.
+    #[doc(hidden)]
+    fn save_network<M: TensorCollection<Self, Cpu>>(
+        network: &M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()>;
+
+    /// Loads the given network from a `.npz` file at the given path.
+    #[doc(hidden)]
+    fn load_network<M: TensorCollection<Self, Cpu>>(
+        network: &mut M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()>;
+}
+
+impl DQNFloat for f32 {
+    fn save_network<M: TensorCollection<f32, Cpu>>(
+        network: &M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()> {
+        network
+            .save(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn load_network<M: TensorCollection<f32, Cpu>>(
+        network: &mut M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()> {
+        network
+            .load(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl DQNFloat for f64 {
+    fn save_network<M: TensorCollection<f64, Cpu>>(
+        network: &M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()> {
+        network
+            .save(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn load_network<M: TensorCollection<f64, Cpu>>(
+        network: &mut M,
+        path: &std::path::Path,
+    ) -> std::io::Result<()> {
+        network
+            .load(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The default Q network architecture of the [DQNAgentTrainer]: two hidden layers of `INNER_SIZE`
+/// neurons with ReLU activations.
+pub type QNetwork<const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize> = (
     (Linear<STATE_SIZE, INNER_SIZE>, ReLU),
     (Linear<INNER_SIZE, INNER_SIZE>, ReLU),
     Linear<INNER_SIZE, ACTION_SIZE>,
 );
 
-type QNetworkDevice<const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize> = (
-    (nn::modules::Linear<STATE_SIZE, INNER_SIZE, f32, Cpu>, ReLU),
-    (nn::modules::Linear<INNER_SIZE, INNER_SIZE, f32, Cpu>, ReLU),
-    nn::modules::Linear<INNER_SIZE, ACTION_SIZE, f32, Cpu>,
+/// The [QNetwork] as built on the CPU, with weights of type `E`.
+pub type QNetworkDevice<
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    E = f32,
+> = (
+    (nn::modules::Linear<STATE_SIZE, INNER_SIZE, E, Cpu>, ReLU),
+    (nn::modules::Linear<INNER_SIZE, INNER_SIZE, E, Cpu>, ReLU),
+    nn::modules::Linear<INNER_SIZE, ACTION_SIZE, E, Cpu>,
+);
+
+/// A deeper Q network architecture with three hidden layers of `INNER_SIZE` neurons with ReLU
+/// activations, as built on the CPU with weights of type `E`. Use it by passing it as the network
+/// type `M` of the [DQNAgentTrainer].
+pub type DeepQNetworkDevice<
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INNER_SIZE: usize,
+    E = f32,
+> = (
+    (nn::modules::Linear<STATE_SIZE, INNER_SIZE, E, Cpu>, ReLU),
+    (nn::modules::Linear<INNER_SIZE, INNER_SIZE, E, Cpu>, ReLU),
+    (nn::modules::Linear<INNER_SIZE, INNER_SIZE, E, Cpu>, ReLU),
+    nn::modules::Linear<INNER_SIZE, ACTION_SIZE, E, Cpu>,
 );
 
+/// A network that can be used as the Q network of a [DQNAgentTrainer]: a module on the CPU that
+/// maps states of `STATE_SIZE` to action values of `ACTION_SIZE`, both for single states and for
+/// batches, with weights of type `E`.
+///
+/// Since dfdx encodes the layer sizes in the type of a network, the architecture cannot be chosen
+/// at runtime. Instead, any network type implementing this trait can be used, such as
+/// [QNetworkDevice] (the default) or [DeepQNetworkDevice].
+pub trait QNetworkModule<const STATE_SIZE: usize, const ACTION_SIZE: usize, E = f32>:
+    BuildModule<Cpu, E>
+    + Clone
+    + Module<Tensor<Rank1<STATE_SIZE>, E, Cpu>, Output = Tensor<Rank1<ACTION_SIZE>, E, Cpu>>
+    + Module<
+        Tensor<(usize, Const<STATE_SIZE>), E, Cpu>,
+        Output = Tensor<(usize, Const<ACTION_SIZE>), E, Cpu>,
+    > + Module<
+        Tensor<Rank2<BATCH, STATE_SIZE>, E, Cpu>,
+        Output = Tensor<Rank2<BATCH, ACTION_SIZE>, E, Cpu>,
+    > + Module<
+        Tensor<Rank2<BATCH, STATE_SIZE>, E, Cpu, OwnedTape<E, Cpu>>,
+        Output = Tensor<Rank2<BATCH, ACTION_SIZE>, E, Cpu, OwnedTape<E, Cpu>>,
+    >
+where
+    E: DQNFloat,
+    Cpu: Device<E>,
+{
+}
+
+impl<M, const STATE_SIZE: usize, const ACTION_SIZE: usize, E>
+    QNetworkModule<STATE_SIZE, ACTION_SIZE, E> for M
+where
+    E: DQNFloat,
+    Cpu: Device<E>,
+    M: BuildModule<Cpu, E>
+        + Clone
+        + Module<Tensor<Rank1<STATE_SIZE>, E, Cpu>, Output = Tensor<Rank1<ACTION_SIZE>, E, Cpu>>
+        + Module<
+            Tensor<(usize, Const<STATE_SIZE>), E, Cpu>,
+            Output = Tensor<(usize, Const<ACTION_SIZE>), E, Cpu>,
+        > + Module<
+            Tensor<Rank2<BATCH, STATE_SIZE>, E, Cpu>,
+            Output = Tensor<Rank2<BATCH, ACTION_SIZE>, E, Cpu>,
+        > + Module<
+            Tensor<Rank2<BATCH, STATE_SIZE>, E, Cpu, OwnedTape<E, Cpu>>,
+            Output = Tensor<Rank2<BATCH, ACTION_SIZE>, E, Cpu, OwnedTape<E, Cpu>>,
+        >,
+{
+}
+
+/// Computes the action values of a single state using the given network, after normalizing the
+/// state with the given normalizer.
+fn forward_state<M, const STATE_SIZE: usize, const ACTION_SIZE: usize, E>(
+    dev: &Cpu,
+    network: &M,
+    normalizer: &StateNormalizer<STATE_SIZE>,
+    state: [E; STATE_SIZE],
+) -> [E; ACTION_SIZE]
+where
+    E: DQNFloat,
+    Cpu: Device<E>,
+    M: QNetworkModule<STATE_SIZE, ACTION_SIZE, E>,
+{
+    let states: Tensor<Rank1<STATE_SIZE>, E, _> = dev.tensor(normalizer.normalize(state));
+    let actions = network.forward(states).nans_to(E::default());
+    actions.array()
+}
+
+/// The loss function that is minimized between the predicted action values and the target values
+/// when training a [DQNAgentTrainer].
+///
+/// Huber loss is quadratic for errors smaller than its delta and linear for larger errors. This
+/// bounds the gradients of large errors, which makes training more robust to outliers and badly
+/// scaled rewards. Mean squared error keeps penalizing large errors quadratically, which corrects
+/// them faster but can make training unstable when errors are large.
+///
+/// # Examples
+///
+/// ```
+/// use dfdx::prelude::*;
+/// use rurel::dqn::DQNLoss;
+///
+/// let dev: Cpu = Default::default();
+/// let loss = |loss: DQNLoss| {
+///     let predictions = dev.tensor([0.0f32, 3.0]);
+///     let targets = dev.tensor([1.0f32, 0.0]);
+///     loss.compute(predictions, targets).array()
+/// };
+/// assert_eq!(loss(DQNLoss::Mse), (1. + 9.) / 2.);
+/// assert_eq!(loss(DQNLoss::Huber(1.0)), (0.5 + 2.5) / 2.);
+/// assert_eq!(loss(DQNLoss::Huber(2.0)), (0.5 + 4.) / 2.);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DQNLoss {
+    /// Huber loss with the given delta, the error at which the loss changes from quadratic to
+    /// linear.
+    Huber(f32),
+    /// Mean squared error.
+    Mse,
+}
+
+impl DQNLoss {
+    /// Computes the mean loss between the predictions and the targets.
+    pub fn compute<Sh: Shape, E: DQNFloat, T: Tape<E, Cpu>>(
+        &self,
+        predictions: Tensor<Sh, E, Cpu, T>,
+        targets: Tensor<Sh, E, Cpu>,
+    ) -> Tensor<Rank0, E, Cpu, T>
+    where
+        Cpu: Device<E>,
+    {
+        match *self {
+            DQNLoss::Huber(delta) => huber_loss(predictions, targets, delta),
+            DQNLoss::Mse => mse_loss(predictions, targets),
+        }
+    }
+}
+
+impl Default for DQNLoss {
+    fn default() -> Self {
+        DQNLoss::Huber(1.0)
+    }
+}
+
+/// The optimizer that updates the Q network of a [DQNAgentTrainer], together with its
+/// configuration. See
+/// [DQNAgentTrainer::with_optimizer](struct.DQNAgentTrainer.html#method.with_optimizer). The
+/// parameters are of the element type `E` of the network.
+#[derive(Debug, Clone, Copy)]
+pub enum DQNOptimizer<E = f32> {
+    /// Stochastic gradient descent, optionally with momentum.
+    Sgd(SgdConfig<E>),
+    /// Adam, with configurable betas and epsilon.
+    Adam(AdamConfig<E>),
+    /// RMSprop.
+    RMSprop(RMSpropConfig<E>),
+}
+
+/// An error that occurs when querying a [DQNAgentTrainer].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DQNError {
+    /// The action values could not be converted to an action, because the conversion from the
+    /// action values to the action type failed.
+    InvalidAction,
+    /// The network computed NaN action values, for example because training diverged.
+    NaNOutput,
+}
+
+impl std::fmt::Display for DQNError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DQNError::InvalidAction => write!(f, "the action values are not a valid action"),
+            DQNError::NaNOutput => write!(f, "the network computed NaN action values"),
+        }
+    }
+}
+
+impl std::error::Error for DQNError {}
+
+/// The state of the optimizer of a [DQNAgentTrainer].
+enum OptimizerState<M, E: DQNFloat>
+where
+    Cpu: Device<E>,
+{
+    Sgd(Sgd<M, E, Cpu>),
+    Adam(Adam<M, E, Cpu>),
+    RMSprop(RMSprop<M, E, Cpu>),
+}
+
+impl<M: BuildModule<Cpu, E>, E: DQNFloat> OptimizerState<M, E>
+where
+    Cpu: Device<E>,
+{
+    fn new(network: &M, optimizer: DQNOptimizer<E>) -> OptimizerState<M, E> {
+        match optimizer {
+            DQNOptimizer::Sgd(cfg) => OptimizerState::Sgd(Sgd::new(network, cfg)),
+            DQNOptimizer::Adam(cfg) => OptimizerState::Adam(Adam::new(network, cfg)),
+            DQNOptimizer::RMSprop(cfg) => OptimizerState::RMSprop(RMSprop::new(network, cfg)),
+        }
+    }
+
+    fn update(&mut self, network: &mut M, grads: &Gradients<E, Cpu>) {
+        match self {
+            OptimizerState::Sgd(sgd) => sgd.update(network, grads),
+            OptimizerState::Adam(adam) => adam.update(network, grads),
+            OptimizerState::RMSprop(rmsprop) => rmsprop.update(network, grads),
+        }
+        .expect("Unused params");
+    }
+
+    /// Changes the learning rate, keeping the rest of the state of the optimizer.
+    fn set_learning_rate(&mut self, learning_rate: E) {
+        match self {
+            OptimizerState::Sgd(sgd) => sgd.cfg.lr = learning_rate,
+            OptimizerState::Adam(adam) => adam.cfg.lr = learning_rate,
+            OptimizerState::RMSprop(rmsprop) => rmsprop.cfg.lr = learning_rate,
+        }
+    }
+}
+
+/// Sums the squares of the gradients of all trainable parameters of a network.
+struct GradientNormOp<'a, E: DQNFloat>
+where
+    Cpu: Device<E>,
+{
+    gradients: &'a Gradients<E, Cpu>,
+    sum_of_squares: f64,
+}
+
+impl<E: DQNFloat> TensorVisitor<E, Cpu> for GradientNormOp<'_, E>
+where
+    Cpu: Device<E>,
+{
+    type Viewer = ViewTensorRef;
+    type Err = <Cpu as HasErr>::Err;
+    type E2 = E;
+    type D2 = Cpu;
+
+    fn visit<Sh: Shape>(
+        &mut self,
+        opts: TensorOptions<Sh, E, Cpu>,
+        t: &Tensor<Sh, E, Cpu>,
+    ) -> Result<Option<Tensor<Sh, E, Cpu>>, Self::Err> {
+        if opts.do_gradient_update {
+            self.sum_of_squares += self
+                .gradients
+                .get(t)
+                .as_vec()
+                .iter()
+                .map(|g| g.to_f64().powi(2))
+                .sum::<f64>();
+        }
+        Ok(None)
+    }
+}
+
+/// Computes the L2 norm of the gradients of all trainable parameters of the given network. The
+/// norm is computed in `f64`, so that it does not overflow for large gradients.
+fn gradient_norm<M: BuildModule<Cpu, E>, E: DQNFloat>(
+    network: &M,
+    gradients: &Gradients<E, Cpu>,
+) -> f64
+where
+    Cpu: Device<E>,
+{
+    let mut op = GradientNormOp {
+        gradients,
+        sum_of_squares: 0.,
+    };
+    M::iter_tensors(&mut RecursiveWalker {
+        m: network,
+        f: &mut op,
+    })
+    .expect("Unable to compute the gradient norm");
+    op.sum_of_squares.sqrt()
+}
+
+/// Picks the legal action of the given `State` with the highest value, where the value of an
+/// action is the dot product of its encoding and the action values. Falls back to converting the
+/// action values to an action when the `State` has no actions, or `None` if that conversion fails.
+fn best_legal_action<S, const ACTION_SIZE: usize, E: Feature>(
+    state: &S,
+    action_values: [E; ACTION_SIZE],
+) -> Option<S::A>
+where
+    S: State,
+    S::A: Into<[E; ACTION_SIZE]> + TryFrom<[E; ACTION_SIZE]>,
+{
+    state
+        .actions()
+        .into_iter()
+        .max_by(|a1, a2| {
+            action_value(a1, &action_values).total_cmp(&action_value(a2, &action_values))
+        })
+        .or_else(|| S::A::try_from(action_values).ok())
+}
+
+/// Returns the value of an action, which is the dot product of its encoding and the action values,
+/// computed in `f64`.
+fn action_value<A, const ACTION_SIZE: usize, E: Feature>(
+    action: &A,
+    action_values: &[E; ACTION_SIZE],
+) -> f64
+where
+    A: Clone + Into<[E; ACTION_SIZE]>,
+{
+    let encoded: [E; ACTION_SIZE] = action.clone().into();
+    encoded
+        .iter()
+        .zip(action_values.iter())
+        .map(|(a, v)| a.to_f64() * v.to_f64())
+        .sum()
+}
+
+/// Normalizes a batch of states and flattens it into a single `Vec`, without copying the batch to
+/// the stack.
+fn flatten_batch<const STATE_SIZE: usize, E: Feature>(
+    batch: &[[E; STATE_SIZE]; BATCH],
+    normalizer: &StateNormalizer<STATE_SIZE>,
+) -> Vec<E> {
+    batch
+        .iter()
+        .flat_map(|state| normalizer.normalize(*state))
+        .collect()
+}
+
+/// Writes the statistics of a state normalizer as text: the number of observed states, and the
+/// mean and variance of every feature, each on their own line.
+fn write_state_normalizer<const STATE_SIZE: usize>(
+    normalizer: &StateNormalizer<STATE_SIZE>,
+) -> String {
+    let line = |values: &[f64]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "{}\n{}\n{}\n",
+        normalizer.count(),
+        line(normalizer.mean()),
+        line(&normalizer.variance())
+    )
+}
+
+/// Reads the statistics of a state normalizer as written by [write_state_normalizer], or `None`
+/// if they are invalid.
+fn read_state_normalizer<const STATE_SIZE: usize>(
+    text: &str,
+) -> Option<StateNormalizer<STATE_SIZE>> {
+    let mut lines = text.lines();
+    let count = lines.next()?.trim().parse().ok()?;
+    let mut values = || -> Option<Vec<f64>> {
+        let values = lines
+            .next()?
+            .split_whitespace()
+            .map(|v| v.parse().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        (values.len() == STATE_SIZE).then_some(values)
+    };
+    let mean = values()?;
+    let variance = values()?;
+    Some(StateNormalizer::from_statistics(count, &mean, &variance))
+}
+
+/// A batch of transitions to train a [DQNAgentTrainer] on, see
+/// [DQNAgentTrainer::train_dqn()].
+pub struct DQNBatch<const STATE_SIZE: usize, const ACTION_SIZE: usize, E = f32> {
+    pub states: Box<[[E; STATE_SIZE]; BATCH]>,
+    pub actions: [[E; ACTION_SIZE]; BATCH],
+    pub next_states: Box<[[E; STATE_SIZE]; BATCH]>,
+    pub rewards: [E; BATCH],
+    pub dones: [bool; BATCH],
+}
+
+// (state, action, reward, next state, done)
+type DQNTransition<const STATE_SIZE: usize, const ACTION_SIZE: usize, E> =
+    ([E; STATE_SIZE], [E; ACTION_SIZE], E, [E; STATE_SIZE], bool);
+
+/// Clips the given reward to the bounds of `clip`, then standardizes it with `normalizer`, when
+/// they are given.
+fn clip_and_normalize(
+    reward: f64,
+    clip: Option<(f64, f64)>,
+    normalizer: &mut Option<RewardNormalizer>,
+) -> f64 {
+    let mut reward = reward;
+    if let Some((min, max)) = clip {
+        reward = reward.clamp(min, max);
+    }
+    if let Some(normalizer) = normalizer {
+        normalizer.observe(reward);
+        reward = normalizer.normalize(reward);
+    }
+    reward
+}
+
+/// A ring buffer holding the last `capacity` transitions of a trainer, replacing the oldest
+/// transition when it is full.
+struct ReplayRing<T> {
+    capacity: usize,
+    transitions: Vec<T>,
+    next: usize,
+}
+
+impl<T> ReplayRing<T> {
+    fn new(capacity: usize) -> ReplayRing<T> {
+        ReplayRing {
+            capacity,
+            transitions: Vec::new(),
+            next: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    fn push(&mut self, transition: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+        } else {
+            self.transitions[self.next] = transition;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Samples a transition uniformly at random.
+    fn sample_one<R: Rng>(&self, rng: &mut R) -> &T {
+        &self.transitions[rng.gen_range(0..self.transitions.len())]
+    }
+}
+
+/// A ring buffer holding the last `capacity` transitions observed by a [DQNAgentTrainer], from
+/// which training batches are sampled uniformly at random.
+///
+/// # Examples
+///
+/// ```
+/// use rurel::dqn::{DQNReplayBuffer, BATCH};
+///
+/// let mut buffer = DQNReplayBuffer::<2, 3>::new(100);
+/// for i in 0..150 {
+///     buffer.push([i as f32, 0.], [1., 0., 0.], i as f32, [i as f32 + 1., 0.], false);
+/// }
+/// assert_eq!(buffer.len(), 100);
+///
+/// let batch = buffer.sample();
+/// assert_eq!(batch.states.len(), BATCH);
+/// assert_eq!(batch.next_states.len(), BATCH);
+/// assert_eq!(batch.actions.len(), BATCH);
+/// // The oldest 50 transitions were evicted
+/// assert!(batch.rewards.iter().all(|&r| r >= 50.));
+/// ```
+pub struct DQNReplayBuffer<const STATE_SIZE: usize, const ACTION_SIZE: usize, E = f32> {
+    transitions: ReplayRing<DQNTransition<STATE_SIZE, ACTION_SIZE, E>>,
+}
+
+impl<const STATE_SIZE: usize, const ACTION_SIZE: usize, E: Copy + Default + std::fmt::Debug>
+    DQNReplayBuffer<STATE_SIZE, ACTION_SIZE, E>
+{
+    /// Constructs an empty replay buffer that holds at most `capacity` transitions.
+    pub fn new(capacity: usize) -> DQNReplayBuffer<STATE_SIZE, ACTION_SIZE, E> {
+        DQNReplayBuffer {
+            transitions: ReplayRing::new(capacity),
+        }
+    }
+
+    /// The maximum number of transitions this buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.transitions.capacity
+    }
+
+    /// The number of transitions currently in this buffer.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns `true` if this buffer holds no transitions.
+    pub fn is_empty(&self) -> bool {
+        self.transitions.len() == 0
+    }
+
+    /// Adds a transition to this buffer, replacing the oldest transition if the buffer is full.
+    pub fn push(
+        &mut self,
+        state: [E; STATE_SIZE],
+        action: [E; ACTION_SIZE],
+        reward: E,
+        next_state: [E; STATE_SIZE],
+        done: bool,
+    ) {
+        self.transitions
+            .push((state, action, reward, next_state, done));
+    }
+
+    /// Samples a batch of [BATCH] transitions uniformly at random (with replacement).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is empty.
+    pub fn sample(&self) -> DQNBatch<STATE_SIZE, ACTION_SIZE, E> {
+        assert!(
+            !self.is_empty(),
+            "cannot sample from an empty replay buffer"
+        );
+        let mut rng = rand::thread_rng();
+        let mut states = vec![[E::default(); STATE_SIZE]; BATCH];
+        let mut actions = [[E::default(); ACTION_SIZE]; BATCH];
+        let mut next_states = vec![[E::default(); STATE_SIZE]; BATCH];
+        let mut rewards = [E::default(); BATCH];
+        let mut dones = [false; BATCH];
+        for i in 0..BATCH {
+            let (state, action, reward, next_state, done) = *self.transitions.sample_one(&mut rng);
+            states[i] = state;
+            actions[i] = action;
+            next_states[i] = next_state;
+            rewards[i] = reward;
+            dones[i] = done;
+        }
+        DQNBatch {
+            states: states.into_boxed_slice().try_into().unwrap(),
+            actions,
+            next_states: next_states.into_boxed_slice().try_into().unwrap(),
+            rewards,
+            dones,
+        }
+    }
+}
+
+/// A summary of a training run, as returned by [DQNAgentTrainer::train].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DQNTrainReport<E = f32> {
+    /// The number of steps taken.
+    pub steps: u64,
+    /// The mean loss of every batch the network was trained on, in order, as returned by
+    /// [train_dqn](DQNAgentTrainer::train_dqn).
+    pub losses: Vec<E>,
+}
+
+impl<E: DQNFloat> DQNTrainReport<E>
+where
+    Cpu: Device<E>,
+{
+    /// Returns the mean loss over all batches, or `None` if no batch was trained on.
+    pub fn mean_loss(&self) -> Option<E> {
+        if self.losses.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.losses.iter().map(|loss| loss.to_f64()).sum();
+        Some(<E as Feature>::from_f64(sum / self.losses.len() as f64))
+    }
+}
+
 /// An `DQNAgentTrainer` can be trained for using a certain [Agent](mdp/trait.Agent.html). After
 /// training, the `DQNAgentTrainer` contains learned knowledge about the process, and can be queried
 /// for this. For example, you can ask the `DQNAgentTrainer` the expected values of all possible
 /// actions in a given state.
 ///
+/// The Q network is a [QNetworkDevice] by default. A different architecture can be chosen with the
+/// network type `M`, see [QNetworkModule]. The network computes in `f32` unless the element type
+/// `E` is set to `f64`, see [DQNFloat].
+///
 /// The code is partially taken from https://github.com/coreylowman/dfdx/blob/main/examples/rl-dqn.rs.
 ///
 pub struct DQNAgentTrainer<
@@ -36,26 +722,45 @@ pub struct DQNAgentTrainer<
     const STATE_SIZE: usize,
     const ACTION_SIZE: usize,
     const INNER_SIZE: usize,
+    M = QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE>,
+    E = f32,
 > where
-    S: State + Into<[f32; STATE_SIZE]>,
-    S::A: Into<[f32; ACTION_SIZE]>,
-    S::A: From<[f32; ACTION_SIZE]>,
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]>,
+    S::A: TryFrom<[E; ACTION_SIZE]>,
+    E: DQNFloat,
+    Cpu: Device<E>,
 {
     // values future rewards
-    gamma: f32,
-    q_network: QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE>,
-    target_q_net: QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE>,
-    sgd: Sgd<QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE>, f32, Cpu>,
+    gamma: E,
+    // gradient steps per call to train_dqn
+    train_iterations: usize,
+    // gradient steps between syncs of the target network
+    target_update_interval: usize,
+    gradient_steps: usize,
+    loss: DQNLoss,
+    max_grad_norm: Option<E>,
+    reward_normalizer: Option<RewardNormalizer>,
+    reward_clip: Option<(f64, f64)>,
+    state_normalizer: StateNormalizer<STATE_SIZE>,
+    q_network: M,
+    target_q_net: M,
+    optimizer_config: DQNOptimizer<E>,
+    optimizer: OptimizerState<M, E>,
+    replay_buffer: DQNReplayBuffer<STATE_SIZE, ACTION_SIZE, E>,
     dev: Cpu,
     phantom: std::marker::PhantomData<S>,
 }
 
-impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize>
-    DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE>
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, M, E>
+    DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E>
 where
-    S: State + Into<[f32; STATE_SIZE]>,
-    S::A: Into<[f32; ACTION_SIZE]>,
-    S::A: From<[f32; ACTION_SIZE]>,
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]>,
+    S::A: TryFrom<[E; ACTION_SIZE]>,
+    M: QNetworkModule<STATE_SIZE, ACTION_SIZE, E>,
+    E: DQNFloat,
+    Cpu: Device<E>,
 {
     /// Creates a new `DQNAgentTrainer` with the given parameters.
     ///
@@ -66,95 +771,701 @@ where
     ///
     /// # Returns
     ///
-    /// A new `DQNAgentTrainer` with the given parameters.
+    /// A new `DQNAgentTrainer` with the given parameters, using SGD with Nesterov momentum.
     ///
     pub fn new(
-        gamma: f32,
-        learning_rate: f32,
-    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE> {
+        gamma: E,
+        learning_rate: E,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E> {
+        Self::with_optimizer(
+            gamma,
+            DQNOptimizer::Sgd(SgdConfig {
+                lr: learning_rate,
+                momentum: Some(Momentum::Nesterov(<E as Feature>::from_f64(0.9))),
+                weight_decay: None,
+            }),
+        )
+    }
+
+    /// Creates a new `DQNAgentTrainer` that updates its network using the given optimizer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use dfdx::optim::{AdamConfig, RMSpropConfig, SgdConfig};
+    /// use rurel::dqn::{DQNAgentTrainer, DQNOptimizer};
+    ///
+    /// for optimizer in [
+    ///     DQNOptimizer::Sgd(SgdConfig::default()),
+    ///     DQNOptimizer::Adam(AdamConfig { betas: [0.8, 0.99], eps: 1e-6, ..Default::default() }),
+    ///     DQNOptimizer::RMSprop(RMSpropConfig::default()),
+    /// ] {
+    ///     let trainer = DQNAgentTrainer::<MyState, 1, 2, 8>::with_optimizer(0.9, optimizer);
+    ///     assert!(trainer.best_action(&MyState(0)).is_some());
+    /// }
+    /// ```
+    pub fn with_optimizer(
+        gamma: E,
+        optimizer: DQNOptimizer<E>,
+    ) -> DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E> {
         let dev = AutoDevice::default();
 
         // initialize model
-        let q_net = dev.build_module::<QNetwork<STATE_SIZE, ACTION_SIZE, INNER_SIZE>, f32>();
+        let q_net = M::build(&dev);
         let target_q_net = q_net.clone();
 
         // initialize optimizer
-        let sgd = Sgd::new(
-            &q_net,
-            SgdConfig {
-                lr: learning_rate,
-                momentum: Some(Momentum::Nesterov(0.9)),
-                weight_decay: None,
-            },
-        );
+        let optimizer_config = optimizer;
+        let optimizer = OptimizerState::new(&q_net, optimizer_config);
 
         DQNAgentTrainer {
             gamma,
+            train_iterations: 20,
+            target_update_interval: 20,
+            gradient_steps: 0,
+            loss: DQNLoss::default(),
+            max_grad_norm: None,
+            reward_normalizer: None,
+            reward_clip: None,
+            state_normalizer: StateNormalizer::new(),
             q_network: q_net,
             target_q_net,
-            sgd,
+            optimizer_config,
+            optimizer,
+            replay_buffer: DQNReplayBuffer::new(10_000),
             dev,
             phantom: std::marker::PhantomData,
         }
     }
 
+    /// Returns the discount factor for future rewards.
+    pub fn gamma(&self) -> E {
+        self.gamma
+    }
+
+    /// Sets the discount factor for future rewards. The new discount factor is used for the
+    /// training targets of all following gradient steps; the network is not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use rurel::dqn::{DQNAgentTrainer, BATCH};
+    ///
+    /// let train = |trainer: &mut DQNAgentTrainer<MyState, 1, 2, 8>| {
+    ///     for _ in 0..50 {
+    ///         trainer.train_dqn(
+    ///             Box::new([[1.0]; BATCH]),
+    ///             [[1.0, 0.0]; BATCH],
+    ///             Box::new([[1.0]; BATCH]),
+    ///             [1.0; BATCH],
+    ///             [false; BATCH],
+    ///         );
+    ///     }
+    /// };
+    ///
+    /// let mut discounted = DQNAgentTrainer::<MyState, 1, 2, 8>::new(0.5, 1e-2);
+    /// let mut myopic = DQNAgentTrainer::<MyState, 1, 2, 8>::new(0.5, 1e-2);
+    /// myopic.import_model(discounted.export_learned_values());
+    /// myopic.set_gamma(0.);
+    /// assert_eq!(myopic.gamma(), 0.);
+    /// train(&mut discounted);
+    /// train(&mut myopic);
+    ///
+    /// // Repeating the action forever is worth 1 / (1 - 0.5), or only the reward without
+    /// // discounting the future.
+    /// assert!((discounted.expected_value(&MyState(1))[0] - 2.).abs() < 0.2);
+    /// assert!((myopic.expected_value(&MyState(1))[0] - 1.).abs() < 0.1);
+    /// ```
+    pub fn set_gamma(&mut self, gamma: E) {
+        self.gamma = gamma;
+    }
+
+    /// Returns the learning rate of the optimizer.
+    pub fn learning_rate(&self) -> E {
+        match self.optimizer_config {
+            DQNOptimizer::Sgd(cfg) => cfg.lr,
+            DQNOptimizer::Adam(cfg) => cfg.lr,
+            DQNOptimizer::RMSprop(cfg) => cfg.lr,
+        }
+    }
+
+    /// Sets the learning rate of the optimizer. The rest of the state of the optimizer, such as
+    /// momentum or the moment estimates of Adam, is kept.
+    pub fn set_learning_rate(&mut self, learning_rate: E) {
+        match &mut self.optimizer_config {
+            DQNOptimizer::Sgd(cfg) => cfg.lr = learning_rate,
+            DQNOptimizer::Adam(cfg) => cfg.lr = learning_rate,
+            DQNOptimizer::RMSprop(cfg) => cfg.lr = learning_rate,
+        }
+        self.optimizer.set_learning_rate(learning_rate);
+    }
+
+    /// Returns the number of gradient steps taken in every call to
+    /// [train_dqn](DQNAgentTrainer::train_dqn).
+    pub fn train_iterations(&self) -> usize {
+        self.train_iterations
+    }
+
+    /// Sets the number of gradient steps taken in every call to
+    /// [train_dqn](DQNAgentTrainer::train_dqn). Defaults to 20.
+    pub fn set_train_iterations(&mut self, train_iterations: usize) {
+        self.train_iterations = train_iterations;
+    }
+
+    /// Returns the number of gradient steps taken so far.
+    pub fn gradient_steps(&self) -> usize {
+        self.gradient_steps
+    }
+
+    /// Sets the number of gradient steps after which the target network is synced with the
+    /// trained network. The target network only computes the training targets, which therefore
+    /// only change every `target_update_interval` gradient steps; all values and actions are
+    /// computed by the trained network. Defaults to 20. An interval of 0 is treated as 1, syncing
+    /// after every gradient step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use rurel::dqn::{DQNAgentTrainer, BATCH};
+    ///
+    /// let network = DQNAgentTrainer::<MyState, 1, 2, 8>::new(0.9, 1e-2).export_learned_values();
+    ///
+    /// // The values learned in 10 gradient steps from the same network, with the given interval.
+    /// let train = |interval: usize| {
+    ///     let mut trainer = DQNAgentTrainer::<MyState, 1, 2, 8>::new(0.9, 1e-2);
+    ///     trainer.import_model(network.clone());
+    ///     trainer.set_train_iterations(10);
+    ///     trainer.set_target_update_interval(interval);
+    ///     trainer.train_dqn(
+    ///         Box::new([[1.0]; BATCH]),
+    ///         [[1.0, 0.0]; BATCH],
+    ///         Box::new([[2.0]; BATCH]),
+    ///         [1.0; BATCH],
+    ///         [false; BATCH],
+    ///     );
+    ///     trainer.expected_value(&MyState(1))
+    /// };
+    /// // Syncing after every step changes the targets during training.
+    /// assert_ne!(train(20), train(1));
+    /// // An interval of 0 syncs after every gradient step as well.
+    /// assert_eq!(train(0), train(1));
+    /// ```
+    pub fn set_target_update_interval(&mut self, target_update_interval: usize) {
+        self.target_update_interval = target_update_interval.max(1);
+    }
+
     /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
     /// value was learned.
-    pub fn expected_value(&self, state: &S) -> [f32; ACTION_SIZE] {
-        let state_: [f32; STATE_SIZE] = (state.clone()).into();
-        let states: Tensor<Rank1<STATE_SIZE>, f32, _> =
-            self.dev.tensor(state_).normalize::<Axis<0>>(0.001);
-        let actions = self.target_q_net.forward(states).nans_to(0f32);
-        actions.array()
+    pub fn expected_value(&self, state: &S) -> [E; ACTION_SIZE] {
+        forward_state(
+            &self.dev,
+            &self.q_network,
+            &self.state_normalizer,
+            state.clone().into(),
+        )
+    }
+
+    /// Fetches the learned values for all actions of each of the given states, in a single forward
+    /// pass of the network. The result is the same as calling
+    /// [expected_value](DQNAgentTrainer::expected_value) for every state, but faster for many
+    /// states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use rurel::dqn::DQNAgentTrainer;
+    ///
+    /// let trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-3);
+    /// let states: Vec<MyState> = (-50..50).map(MyState).collect();
+    /// let batch = trainer.expected_value_batch(&states);
+    /// assert_eq!(batch.len(), states.len());
+    /// for (state, values) in states.iter().zip(batch) {
+    ///     let single = trainer.expected_value(state);
+    ///     for (b, s) in values.iter().zip(single.iter()) {
+    ///         assert!((b - s).abs() < 1e-5);
+    ///     }
+    /// }
+    /// ```
+    pub fn expected_value_batch(&self, states: &[S]) -> Vec<[E; ACTION_SIZE]> {
+        if states.is_empty() {
+            return vec![];
+        }
+        let data: Vec<E> = states
+            .iter()
+            .flat_map(|state| self.state_normalizer.normalize(state.clone().into()))
+            .collect();
+        let states: Tensor<(usize, Const<STATE_SIZE>), E, _> =
+            self.dev.tensor_from_vec(data, (states.len(), Const));
+        let actions = self.q_network.forward(states).nans_to(E::default());
+        actions
+            .as_vec()
+            .chunks(ACTION_SIZE)
+            .map(|values| values.try_into().unwrap())
+            .collect()
     }
 
     /// Returns a clone of the entire learned state to be saved or used elsewhere.
-    pub fn export_learned_values(&self) -> QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE> {
+    pub fn export_learned_values(&self) -> M {
         self.learned_values().clone()
     }
 
     // Returns a reference to the learned state.
-    pub fn learned_values(&self) -> &QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE> {
+    pub fn learned_values(&self) -> &M {
         &self.q_network
     }
 
     /// Imports a model, completely replacing any learned progress
-    pub fn import_model(&mut self, model: QNetworkDevice<STATE_SIZE, ACTION_SIZE, INNER_SIZE>) {
+    pub fn import_model(&mut self, model: M) {
         self.q_network.clone_from(&model);
         self.target_q_net.clone_from(&self.q_network);
     }
 
-    /// Returns the best action for the given `State`, or `None` if no values were learned.
+    /// Returns the statistics that states are normalized with before they are passed to the
+    /// network, see [StateNormalizer]. The statistics are fit to all states observed by
+    /// [train](DQNAgentTrainer::train), and the same normalization is applied when training on a
+    /// batch and when computing values or actions. States passed directly to
+    /// [train_dqn](DQNAgentTrainer::train_dqn) do not update the statistics.
+    ///
+    /// The statistics belong to the learned network: they are part of a checkpoint, but should be
+    /// saved and restored alongside the weights when using
+    /// [save_to_path](DQNAgentTrainer::save_to_path) or [import_model](DQNAgentTrainer::import_model).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl From<MyState> for [f32; 2] { fn from(s: MyState) -> Self { [s.0 as f32, 1.] } }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction(usize);
+    /// # impl From<MyAction> for [f32; 2] {
+    /// #     fn from(a: MyAction) -> Self { let mut v = [0.; 2]; v[a.0] = 1.; v }
+    /// # }
+    /// # impl From<[f32; 2]> for MyAction {
+    /// #     fn from(v: [f32; 2]) -> Self { MyAction(if v[0] >= v[1] { 0 } else { 1 }) }
+    /// # }
+    /// # impl State for MyState {
+    /// #     type A = MyAction;
+    /// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+    /// #     fn actions(&self) -> Vec<MyAction> { vec![MyAction(0), MyAction(1)] }
+    /// # }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         let dx = if action.0 == 0 { -1 } else { 1 };
+    /// #         self.state = MyState((self.state.0 + dx).clamp(-5, 5))
+    /// #     }
+    /// # }
+    /// use dfdx::prelude::*;
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::normalize::StateNormalizer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-3);
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(&mut agent, &mut FixedIterations::new(256), &RandomExploration::new());
+    /// let normalizer = trainer.state_normalizer().clone();
+    /// assert!(normalizer.count() > 256);
+    ///
+    /// // Values are computed from states normalized with the statistics fit during training.
+    /// let dev = AutoDevice::default();
+    /// let network = trainer.export_learned_values();
+    /// for x in -5..=5 {
+    ///     let input = dev.tensor(normalizer.normalize(MyState(x).into()));
+    ///     let values: [f32; 2] = network.forward(input).array();
+    ///     assert_eq!(trainer.expected_value(&MyState(x)), values);
+    /// }
+    ///
+    /// // The statistics can be restored together with the weights.
+    /// let mut restored = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-3);
+    /// restored.import_model(network);
+    /// restored.set_state_normalizer(StateNormalizer::from_statistics(
+    ///     normalizer.count(),
+    ///     normalizer.mean(),
+    ///     &normalizer.variance(),
+    /// ));
+    /// for x in -5..=5 {
+    ///     let (expected, actual) = (trainer.expected_value(&MyState(x)), restored.expected_value(&MyState(x)));
+    ///     for (e, a) in expected.iter().zip(actual.iter()) {
+    ///         assert!((e - a).abs() < 1e-4);
+    ///     }
+    /// }
+    /// ```
+    pub fn state_normalizer(&self) -> &StateNormalizer<STATE_SIZE> {
+        &self.state_normalizer
+    }
+
+    /// Replaces the statistics that states are normalized with, see
+    /// [state_normalizer](DQNAgentTrainer::state_normalizer).
+    pub fn set_state_normalizer(&mut self, state_normalizer: StateNormalizer<STATE_SIZE>) {
+        self.state_normalizer = state_normalizer;
+    }
+
+    /// Saves the weights of the trained network to the file at `path`, in the numpy `.npz`
+    /// format. The state normalization is not saved, see
+    /// [state_normalizer](DQNAgentTrainer::state_normalizer).
+    pub fn save_to_path<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        E::save_network(&self.q_network, path.as_ref())
+    }
+
+    /// Loads the weights of the network from the `.npz` file at `path`, as saved by
+    /// [save_to_path](DQNAgentTrainer::save_to_path), completely replacing any learned progress.
+    pub fn load_from_path<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        E::load_network(&mut self.q_network, path.as_ref())?;
+        self.target_q_net.clone_from(&self.q_network);
+        Ok(())
+    }
+
+    /// Saves a checkpoint of training to the directory at `path`, creating it if needed. The
+    /// checkpoint holds the weights of the trained network and the target network, in the numpy
+    /// `.npz` format, the statistics of the state normalization, and the number of gradient steps
+    /// taken so far.
+    ///
+    /// The internal state of the optimizer, such as momentum or the moment estimates of Adam, is
+    /// not saved. See [load_checkpoint](DQNAgentTrainer::load_checkpoint).
+    pub fn save_checkpoint<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+        E::save_network(&self.q_network, &path.join("q_network.npz"))?;
+        E::save_network(&self.target_q_net, &path.join("target_q_network.npz"))?;
+        std::fs::write(
+            path.join("state_normalizer"),
+            write_state_normalizer(&self.state_normalizer),
+        )?;
+        std::fs::write(path.join("gradient_steps"), self.gradient_steps.to_string())
+    }
+
+    /// Loads a checkpoint from the directory at `path`, as saved by
+    /// [save_checkpoint](DQNAgentTrainer::save_checkpoint), so that training can be resumed.
+    ///
+    /// The optimizer is re-initialized from its configuration, as if no gradient steps were taken
+    /// yet. With an optimizer that keeps no state between steps, such as SGD without momentum,
+    /// resumed training is the same as uninterrupted training. With other optimizers, the
+    /// optimizer state is rebuilt during the first gradient steps after resuming.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use dfdx::optim::SgdConfig;
+    /// use rurel::dqn::{DQNAgentTrainer, DQNOptimizer, BATCH};
+    ///
+    /// let optimizer = DQNOptimizer::Sgd(SgdConfig { lr: 1e-2, momentum: None, weight_decay: None });
+    /// let train = |trainer: &mut DQNAgentTrainer<MyState, 2, 2, 8>| {
+    ///     let states: [[f32; 2]; BATCH] = std::array::from_fn(|i| [i as f32, 1.]);
+    ///     let next_states: [[f32; 2]; BATCH] = std::array::from_fn(|i| [i as f32 + 1., 1.]);
+    ///     trainer.train_dqn(
+    ///         Box::new(states),
+    ///         std::array::from_fn(|i| if i % 2 == 0 { [1., 0.] } else { [0., 1.] }),
+    ///         Box::new(next_states),
+    ///         std::array::from_fn(|i| i as f32),
+    ///         [false; BATCH],
+    ///     );
+    /// };
+    ///
+    /// let path = std::env::temp_dir().join("rurel_dqn_checkpoint");
+    /// let mut trainer = DQNAgentTrainer::with_optimizer(0.9, optimizer);
+    /// train(&mut trainer);
+    /// trainer.save_checkpoint(&path).unwrap();
+    /// train(&mut trainer);
+    ///
+    /// let mut resumed = DQNAgentTrainer::with_optimizer(0.9, optimizer);
+    /// resumed.load_checkpoint(&path).unwrap();
+    /// train(&mut resumed);
+    ///
+    /// for x in 0..10 {
+    ///     let expected = trainer.expected_value(&MyState(x));
+    ///     let actual = resumed.expected_value(&MyState(x));
+    ///     for (e, a) in expected.iter().zip(actual.iter()) {
+    ///         assert!((e - a).abs() < 1e-4);
+    ///     }
+    /// }
+    /// # std::fs::remove_dir_all(&path).unwrap();
+    /// ```
+    pub fn load_checkpoint<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let gradient_steps = std::fs::read_to_string(path.join("gradient_steps"))?
+            .trim()
+            .parse()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let state_normalizer =
+            read_state_normalizer(&std::fs::read_to_string(path.join("state_normalizer"))?)
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "invalid state normalizer statistics",
+                    )
+                })?;
+        E::load_network(&mut self.q_network, &path.join("q_network.npz"))?;
+        E::load_network(&mut self.target_q_net, &path.join("target_q_network.npz"))?;
+        self.gradient_steps = gradient_steps;
+        self.state_normalizer = state_normalizer;
+        self.optimizer = OptimizerState::new(&self.q_network, self.optimizer_config);
+        Ok(())
+    }
+
+    /// Returns the best action for the given `State`. Only actions returned by
+    /// [State::actions()](../mdp/trait.State.html#tymethod.actions) are considered. For a `State`
+    /// without actions, the action values are converted to an action, and `None` is returned if
+    /// that conversion fails.
     pub fn best_action(&self, state: &S) -> Option<S::A> {
         let target = self.expected_value(state);
 
-        Some(target.into())
+        best_legal_action(state, target)
+    }
+
+    /// Samples an action for the given `State` from a softmax distribution over the values of its
+    /// actions, divided by `temperature`. A lower temperature makes the best action more likely,
+    /// a higher temperature makes the distribution closer to uniform. A temperature of (nearly)
+    /// zero returns the best action, like [best_action](DQNAgentTrainer::best_action), as does a
+    /// `State` without actions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `State` has no actions and the action values cannot be converted to an
+    /// action.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use dfdx::prelude::*;
+    /// use rurel::dqn::DQNAgentTrainer;
+    ///
+    /// // A network that values action 0 at 1 and action 1 at 0 in every state.
+    /// let dev: Cpu = Default::default();
+    /// let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-3);
+    /// let mut network = trainer.export_learned_values();
+    /// network.2.weight = dev.zeros();
+    /// network.2.bias = dev.tensor([1., 0.]);
+    /// trainer.import_model(network);
+    ///
+    /// // The share of samples that picked action 0.
+    /// let share = |temperature: f32| {
+    ///     let samples = (0..1000)
+    ///         .filter(|_| trainer.sample_action(&MyState(0), temperature) == MyAction(0))
+    ///         .count();
+    ///     samples as f64 / 1000.
+    /// };
+    /// assert_eq!(share(0.), 1.);
+    /// assert!(share(0.1) > 0.99);
+    /// assert!((share(100.) - 0.5).abs() < 0.1);
+    /// ```
+    pub fn sample_action(&self, state: &S, temperature: E) -> S::A {
+        let mut actions = state.actions();
+        if actions.len() == 1 {
+            return actions.remove(0);
+        }
+        let action_values = self.expected_value(state);
+        let temperature = temperature.to_f64();
+        if temperature <= f32::EPSILON as f64 || actions.is_empty() {
+            return best_legal_action(state, action_values)
+                .expect("the action values are not a valid action");
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .map(|action| action_value(action, &action_values))
+            .collect();
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let weights = values.iter().map(|v| ((v - max) / temperature).exp());
+        let index = WeightedIndex::new(weights)
+            .expect("invalid action values")
+            .sample(&mut rand::thread_rng());
+        actions[index].clone()
+    }
+
+    /// Returns the best action for the given `State`, like
+    /// [best_action](DQNAgentTrainer::best_action), but returns an error instead of hiding the
+    /// problem when the network computes NaN action values, or when the action values cannot be
+    /// converted to an action. Implement `TryFrom` instead of `From` for the action type to report
+    /// invalid action values. The conversion is only needed when the `State` has no
+    /// actions, otherwise the best of its actions is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::State;
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl From<MyState> for [f32; 2] { fn from(s: MyState) -> Self { [s.0 as f32, 1.] } }
+    /// # #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// # struct MyAction(usize);
+    /// # impl From<MyAction> for [f32; 2] {
+    /// #     fn from(a: MyAction) -> Self { let mut v = [0.; 2]; v[a.0] = 1.; v }
+    /// # }
+    /// // Equal action values do not encode an action.
+    /// impl TryFrom<[f32; 2]> for MyAction {
+    ///     type Error = ();
+    ///
+    ///     fn try_from(v: [f32; 2]) -> Result<Self, ()> {
+    ///         if v[0] == v[1] {
+    ///             return Err(());
+    ///         }
+    ///         Ok(MyAction(if v[0] > v[1] { 0 } else { 1 }))
+    ///     }
+    /// }
+    ///
+    /// impl State for MyState {
+    ///     type A = MyAction;
+    ///     fn reward(&self) -> f64 { self.0 as f64 }
+    ///     // The game is over at 10.
+    ///     fn actions(&self) -> Vec<MyAction> {
+    ///         if self.0 == 10 { vec![] } else { vec![MyAction(0), MyAction(1)] }
+    ///     }
+    /// }
+    ///
+    /// use dfdx::prelude::*;
+    /// use rurel::dqn::{DQNAgentTrainer, DQNError};
+    ///
+    /// let dev: Cpu = Default::default();
+    /// let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-3);
+    /// assert!(trainer.try_best_action(&MyState(0)).is_ok());
+    /// assert_eq!(DQNAgentTrainer::<MyState, 2, 2, 8>::validate_actions(&MyState(0)), Ok(()));
+    ///
+    /// // A network that computes the same value for every action.
+    /// let mut network = trainer.export_learned_values();
+    /// network.2.weight = dev.zeros();
+    /// network.2.bias = dev.zeros();
+    /// trainer.import_model(network.clone());
+    /// assert!(trainer.try_best_action(&MyState(0)).is_ok());
+    /// assert_eq!(trainer.try_best_action(&MyState(10)), Err(DQNError::InvalidAction));
+    ///
+    /// // A network that computes NaN values.
+    /// network.2.bias = dev.tensor([f32::NAN; 2]);
+    /// trainer.import_model(network);
+    /// assert_eq!(trainer.try_best_action(&MyState(0)), Err(DQNError::NaNOutput));
+    /// ```
+    pub fn try_best_action(&self, state: &S) -> Result<S::A, DQNError> {
+        let states: Tensor<Rank1<STATE_SIZE>, E, _> = self
+            .dev
+            .tensor(self.state_normalizer.normalize(state.clone().into()));
+        let action_values: [E; ACTION_SIZE] = self.q_network.forward(states).array();
+        if action_values.iter().any(|v| v.to_f64().is_nan()) {
+            return Err(DQNError::NaNOutput);
+        }
+        best_legal_action(state, action_values).ok_or(DQNError::InvalidAction)
+    }
+
+    /// Checks that every action of the given `State` survives the conversion to action values and
+    /// back, as required by the network. Returns [DQNError::InvalidAction] if the conversion of an
+    /// action fails or yields a different action. In debug builds,
+    /// [train](DQNAgentTrainer::train) checks the actions of the initial state.
+    pub fn validate_actions(state: &S) -> Result<(), DQNError> {
+        for action in state.actions() {
+            let encoded: [E; ACTION_SIZE] = action.clone().into();
+            let decoded = S::A::try_from(encoded).map_err(|_| DQNError::InvalidAction)?;
+            if decoded != action {
+                return Err(DQNError::InvalidAction);
+            }
+        }
+        Ok(())
     }
 
+    /// Trains the network on a single batch of transitions. The states are passed in boxes and
+    /// are copied to the heap directly, so large states do not overflow the stack.
+    ///
+    /// Returns the mean loss over the gradient steps, computed before each step updates the
+    /// network, which can be used to monitor convergence. The loss is NaN when the number of
+    /// [train iterations](DQNAgentTrainer::set_train_iterations) is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::State;
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct BigState(i32);
+    /// # impl From<BigState> for [f32; 10000] { fn from(s: BigState) -> Self { [s.0 as f32; 10000] } }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction(usize);
+    /// # impl From<MyAction> for [f32; 2] {
+    /// #     fn from(a: MyAction) -> Self { let mut v = [0.; 2]; v[a.0] = 1.; v }
+    /// # }
+    /// # impl From<[f32; 2]> for MyAction {
+    /// #     fn from(v: [f32; 2]) -> Self { MyAction(if v[0] >= v[1] { 0 } else { 1 }) }
+    /// # }
+    /// # impl State for BigState {
+    /// #     type A = MyAction;
+    /// #     fn reward(&self) -> f64 { self.0 as f64 }
+    /// #     fn actions(&self) -> Vec<MyAction> { vec![MyAction(0), MyAction(1)] }
+    /// # }
+    /// use rurel::dqn::{DQNAgentTrainer, BATCH};
+    ///
+    /// // A batch of states takes 2.5MB, more than the stack of this thread.
+    /// std::thread::Builder::new()
+    ///     .stack_size(1 << 20)
+    ///     .spawn(|| {
+    ///         let mut trainer = DQNAgentTrainer::<BigState, 10000, 2, 4>::new(0.9, 1e-3);
+    ///         trainer.set_train_iterations(1);
+    ///         let states = vec![[1.0; 10000]; BATCH].into_boxed_slice().try_into().unwrap();
+    ///         let next_states = vec![[2.0; 10000]; BATCH].into_boxed_slice().try_into().unwrap();
+    ///         trainer.train_dqn(
+    ///             states,
+    ///             [[1.0, 0.0]; BATCH],
+    ///             next_states,
+    ///             [1.0; BATCH],
+    ///             [false; BATCH],
+    ///         );
+    ///     })
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// The loss decreases while the network fits a fixed batch:
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use rurel::dqn::{DQNAgentTrainer, BATCH};
+    ///
+    /// let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-2);
+    /// let losses: Vec<f32> = (0..10)
+    ///     .map(|_| {
+    ///         trainer.train_dqn(
+    ///             Box::new(std::array::from_fn(|i| [(i % 4) as f32, 1.])),
+    ///             std::array::from_fn(|i| if i % 2 == 0 { [1., 0.] } else { [0., 1.] }),
+    ///             Box::new(std::array::from_fn(|i| [(i % 4) as f32 + 1., 1.])),
+    ///             std::array::from_fn(|i| (i % 4) as f32),
+    ///             [true; BATCH],
+    ///         )
+    ///     })
+    ///     .collect();
+    /// assert!(losses.iter().all(|loss| loss.is_finite()));
+    /// assert!(losses[9] < losses[0]);
+    /// ```
     #[allow(clippy::boxed_local)]
     pub fn train_dqn(
         &mut self,
-        states: Box<[[f32; STATE_SIZE]; BATCH]>,
-        actions: [[f32; ACTION_SIZE]; BATCH],
-        next_states: Box<[[f32; STATE_SIZE]; BATCH]>,
-        rewards: [f32; BATCH],
+        states: Box<[[E; STATE_SIZE]; BATCH]>,
+        actions: [[E; ACTION_SIZE]; BATCH],
+        next_states: Box<[[E; STATE_SIZE]; BATCH]>,
+        rewards: [E; BATCH],
         dones: [bool; BATCH],
-    ) {
-        self.target_q_net.clone_from(&self.q_network);
+    ) -> E {
         let mut grads = self.q_network.alloc_grads();
+        let mut total_loss = 0.;
 
-        let dones: Tensor<Rank1<BATCH>, f32, _> =
-            self.dev.tensor(dones.map(|d| if d { 1f32 } else { 0f32 }));
+        let dones: Tensor<Rank1<BATCH>, E, _> =
+            self.dev
+                .tensor(dones.map(|d| if d { E::ONE } else { E::default() }));
         let rewards = self.dev.tensor(rewards);
 
         // Convert to tensors and normalize the states for better training
-        let states: Tensor<Rank2<BATCH, STATE_SIZE>, f32, _> =
-            self.dev.tensor(*states).normalize::<Axis<1>>(0.001);
+        let states: Tensor<Rank2<BATCH, STATE_SIZE>, E, _> = self.dev.tensor_from_vec(
+            flatten_batch(&states, &self.state_normalizer),
+            Default::default(),
+        );
 
         // Convert actions to tensors and get the max action for each batch
         let actions: Tensor<Rank1<BATCH>, usize, _> = self.dev.tensor(actions.map(|a| {
             let mut max_idx = 0;
-            let mut max_val = 0f32;
+            let mut max_val = E::default();
             for (i, v) in a.iter().enumerate() {
                 if *v > max_val {
                     max_val = *v;
@@ -165,103 +1476,913 @@ where
         }));
 
         // Convert to tensors and normalize the states for better training
-        let next_states: Tensor<Rank2<BATCH, STATE_SIZE>, f32, _> =
-            self.dev.tensor(*next_states).normalize::<Axis<1>>(0.001);
+        let next_states: Tensor<Rank2<BATCH, STATE_SIZE>, E, _> = self.dev.tensor_from_vec(
+            flatten_batch(&next_states, &self.state_normalizer),
+            Default::default(),
+        );
 
         // Compute the estimated Q-value for the action
-        for _step in 0..20 {
-            let q_values = self.q_network.forward(states.trace(grads));
-
-            let action_qs = q_values.select(actions.clone());
-
+        for _step in 0..self.train_iterations {
             // targ_q = R + discount * max(Q(S'))
             // curr_q = Q(S)[A]
             // loss = huber(curr_q, targ_q, 1)
             let next_q_values = self.target_q_net.forward(next_states.clone());
             let max_next_q = next_q_values.max::<Rank1<BATCH>, _>();
-            let target_q = (max_next_q * (-dones.clone() + 1.0)) * self.gamma + rewards.clone();
+            let target_q = (max_next_q * (-dones.clone() + E::ONE)) * self.gamma + rewards.clone();
 
-            let loss = huber_loss(action_qs, target_q, 1.0);
+            let q_values = self.q_network.forward(states.trace(grads));
+            let action_qs = q_values.select(actions.clone());
+            #[cfg(feature = "logging")]
+            if _step + 1 == self.train_iterations {
+                let action_qs = action_qs.retaped::<NoneTape>();
+                log::debug!(
+                    "gradient step {}: loss {}, mean Q-value {}",
+                    self.gradient_steps + 1,
+                    self.loss
+                        .compute(action_qs.clone(), target_q.clone())
+                        .array()
+                        .to_f64(),
+                    action_qs.mean::<Rank0, _>().array().to_f64(),
+                );
+            }
+            let loss = self.loss.compute(action_qs, target_q.clone());
+            total_loss += loss.array().to_f64();
 
             grads = loss.backward();
 
+            if let Some(max_grad_norm) = self.max_grad_norm {
+                let norm = gradient_norm(&self.q_network, &grads);
+                if norm > max_grad_norm.to_f64() {
+                    // The gradients cannot be scaled in place, so the gradients of the loss
+                    // scaled by the same factor are computed instead.
+                    self.q_network.zero_grads(&mut grads);
+                    let q_values = self.q_network.forward(states.trace(grads));
+                    let action_qs = q_values.select(actions.clone());
+                    let loss = self.loss.compute(action_qs, target_q);
+                    grads =
+                        (loss * <E as Feature>::from_f64(max_grad_norm.to_f64() / norm)).backward();
+                }
+            }
+
             // update weights with optimizer
-            self.sgd
-                .update(&mut self.q_network, &grads)
-                .expect("Unused params");
+            self.optimizer.update(&mut self.q_network, &grads);
             self.q_network.zero_grads(&mut grads);
+
+            self.gradient_steps += 1;
+            if self
+                .gradient_steps
+                .is_multiple_of(self.target_update_interval)
+            {
+                self.target_q_net.clone_from(&self.q_network);
+            }
         }
-        self.target_q_net.clone_from(&self.q_network);
+        <E as Feature>::from_f64(total_loss / self.train_iterations as f64)
+    }
+
+    /// Enables reward normalization: during training, rewards are standardized using the running
+    /// mean and variance of all rewards observed so far before they are stored in the replay
+    /// buffer, see [RewardNormalizer]. Reward normalization is disabled by default.
+    pub fn with_reward_normalization(mut self) -> Self {
+        self.reward_normalizer = Some(RewardNormalizer::new());
+        self
+    }
+
+    /// Enables reward clipping: during training, rewards are clipped to the range from `min` to
+    /// `max` before they are normalized and stored in the replay buffer, so the network is only
+    /// trained on clipped rewards. Clipping rewards to `[-1, 1]` is common to keep training
+    /// stable when rewards vary in magnitude. Reward clipping is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState { x: i32, y: i32 }
+    /// # impl From<MyState> for [f32; 2] { fn from(s: MyState) -> Self { [s.x as f32, s.y as f32] } }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction { dx: i32, dy: i32 }
+    /// # const MOVES: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    /// # impl From<MyAction> for [f32; 4] {
+    /// #     fn from(a: MyAction) -> Self {
+    /// #         let mut v = [0.; 4];
+    /// #         v[MOVES.iter().position(|&m| m == (a.dx, a.dy)).unwrap()] = 1.;
+    /// #         v
+    /// #     }
+    /// # }
+    /// # impl From<[f32; 4]> for MyAction {
+    /// #     fn from(v: [f32; 4]) -> Self {
+    /// #         let i = (0..4).max_by(|&a, &b| v[a].total_cmp(&v[b])).unwrap();
+    /// #         MyAction { dx: MOVES[i].0, dy: MOVES[i].1 }
+    /// #     }
+    /// # }
+    /// # impl State for MyState {
+    /// #     type A = MyAction;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -((((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt())
+    /// #     }
+    /// #     fn actions(&self) -> Vec<MyAction> {
+    /// #         MOVES.iter().map(|&(dx, dy)| MyAction { dx, dy }).collect()
+    /// #     }
+    /// # }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState {
+    /// #             x: (((self.state.x + action.dx) % 21) + 21) % 21,
+    /// #             y: (((self.state.y + action.dy) % 21) + 21) % 21,
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rurel::dqn::DQNAgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The rewards of the 2D grid are the negative distances to (10, 10).
+    /// let mut trainer = DQNAgentTrainer::<MyState, 2, 4, 8>::new(0.9, 1e-3).with_reward_clip(-1., 1.);
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(&mut agent, &mut FixedIterations::new(1000), &RandomExploration::new());
+    ///
+    /// // The batches the network was trained on only hold clipped rewards.
+    /// for _ in 0..10 {
+    ///     let batch = trainer.replay_buffer().sample();
+    ///     assert!(batch.rewards.iter().all(|r| (-1. ..=1.).contains(r)));
+    /// }
+    /// ```
+    pub fn with_reward_clip(mut self, min: f64, max: f64) -> Self {
+        self.reward_clip = Some((min, max));
+        self
+    }
+
+    /// Returns the replay buffer holding the transitions that training batches are sampled from.
+    pub fn replay_buffer(&self) -> &DQNReplayBuffer<STATE_SIZE, ACTION_SIZE, E> {
+        &self.replay_buffer
+    }
+
+    /// Returns the loss function minimized during training.
+    pub fn loss(&self) -> DQNLoss {
+        self.loss
+    }
+
+    /// Sets the loss function minimized during training, such as Huber loss with a different
+    /// delta. Defaults to `DQNLoss::Huber(1.0)`.
+    pub fn set_loss(&mut self, loss: DQNLoss) {
+        self.loss = loss;
+    }
+
+    /// Sets the maximum L2 norm of the gradients of a training step. When the gradients of all
+    /// parameters of the network together have a larger norm, they are scaled down to this norm
+    /// before the optimizer updates the network. This keeps training stable when large rewards
+    /// produce exploding gradients. Defaults to `None`, which disables gradient clipping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::dqn::{MyAction, MyState};
+    /// use rurel::dqn::{DQNAgentTrainer, DQNLoss, QNetworkDevice, BATCH};
+    ///
+    /// let finite = |network: &QNetworkDevice<2, 2, 8>| {
+    ///     [&network.0 .0.weight.as_vec(), &network.1 .0.weight.as_vec(), &network.2.weight.as_vec()]
+    ///         .iter()
+    ///         .all(|weights| weights.iter().all(|w| w.is_finite()))
+    /// };
+    ///
+    /// for max_grad_norm in [None, Some(1.0)] {
+    ///     let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 8>::new(0.9, 1e-2);
+    ///     trainer.set_loss(DQNLoss::Mse);
+    ///     trainer.set_max_grad_norm(max_grad_norm);
+    ///     // A batch with huge rewards.
+    ///     trainer.train_dqn(
+    ///         Box::new(std::array::from_fn(|i| [i as f32, 1.])),
+    ///         [[1., 0.]; BATCH],
+    ///         Box::new(std::array::from_fn(|i| [i as f32 + 1., 1.])),
+    ///         [1e20; BATCH],
+    ///         [false; BATCH],
+    ///     );
+    ///     assert_eq!(finite(trainer.learned_values()), max_grad_norm.is_some());
+    /// }
+    /// ```
+    pub fn set_max_grad_norm(&mut self, max_grad_norm: Option<E>) {
+        self.max_grad_norm = max_grad_norm;
+    }
+
+    /// Sets the maximum number of transitions kept in the replay buffer that training batches are
+    /// sampled from. Defaults to 10000. This clears the replay buffer.
+    pub fn set_replay_capacity(&mut self, capacity: usize) {
+        self.replay_buffer = DQNReplayBuffer::new(capacity);
     }
 
     /// Trains this [DQNAgentTrainer] using the given [ExplorationStrategy] and
     /// [Agent] until the [TerminationStrategy] decides to stop.
+    ///
+    /// Every observed transition is stored in a replay buffer. After every [BATCH] steps, the
+    /// network is trained on a batch sampled uniformly from the replay buffer. Training starts
+    /// once the replay buffer holds at least [BATCH] transitions.
+    ///
+    /// Every observed state updates the statistics of the state normalization, see
+    /// [state_normalizer](DQNAgentTrainer::state_normalizer).
+    ///
+    /// Returns a [DQNTrainReport] with the mean loss of every batch the network was trained on,
+    /// for example to plot a learning curve.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl From<MyState> for [f32; 1] { fn from(s: MyState) -> Self { [s.0 as f32] } }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction(i32);
+    /// # impl From<MyAction> for [f32; 2] {
+    /// #     fn from(a: MyAction) -> Self { if a.0 < 0 { [1., 0.] } else { [0., 1.] } }
+    /// # }
+    /// # impl From<[f32; 2]> for MyAction {
+    /// #     fn from(v: [f32; 2]) -> Self { MyAction(if v[0] >= v[1] { -1 } else { 1 }) }
+    /// # }
+    /// # impl State for MyState {
+    /// #     type A = MyAction;
+    /// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+    /// #     fn actions(&self) -> Vec<MyAction> { vec![MyAction(-1), MyAction(1)] }
+    /// # }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState((self.state.0 + action.0).clamp(-5, 5))
+    /// #     }
+    /// # }
+    /// use rurel::dqn::{DQNAgentTrainer, BATCH};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = DQNAgentTrainer::<MyState, 1, 2, 8>::new(0.9, 1e-3);
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let report = trainer.train(
+    ///     &mut agent,
+    ///     &mut FixedIterations::new(4 * BATCH as u32),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// assert_eq!(report.steps, 4 * BATCH as u64);
+    /// assert_eq!(report.losses.len(), 4);
+    /// assert!(report.mean_loss().unwrap().is_finite());
+    /// ```
     pub fn train(
         &mut self,
         agent: &mut dyn Agent<S>,
         termination_strategy: &mut dyn TerminationStrategy<S>,
         exploration_strategy: &dyn ExplorationStrategy<S>,
-    ) {
+    ) -> DQNTrainReport<E> {
+        debug_assert!(
+            Self::validate_actions(agent.current_state()).is_ok(),
+            "the actions of the initial state do not survive the conversion to action values"
+        );
+        let mut steps: usize = 0;
+        let mut losses = Vec::new();
+        #[cfg(feature = "logging")]
+        let started = std::time::Instant::now();
+        self.state_normalizer
+            .observe(&agent.current_state().clone().into());
         loop {
-            // Initialize batch
-            let mut states: Box<[[f32; STATE_SIZE]; BATCH]> = {
-                let b = vec![0.0; STATE_SIZE].into_boxed_slice();
-                let big = unsafe { Box::from_raw(Box::into_raw(b) as *mut [f32; STATE_SIZE]) };
-
-                let b = vec![*big; BATCH].into_boxed_slice();
-                unsafe { Box::from_raw(Box::into_raw(b) as *mut [[f32; STATE_SIZE]; BATCH]) }
-            };
-            let mut actions: [[f32; ACTION_SIZE]; BATCH] = [[0.0; ACTION_SIZE]; BATCH];
-            let mut next_states: Box<[[f32; STATE_SIZE]; BATCH]> = {
-                let b = vec![0.0; STATE_SIZE].into_boxed_slice();
-                let big = unsafe { Box::from_raw(Box::into_raw(b) as *mut [f32; STATE_SIZE]) };
-
-                let b = vec![*big; BATCH].into_boxed_slice();
-                unsafe { Box::from_raw(Box::into_raw(b) as *mut [[f32; STATE_SIZE]; BATCH]) }
-            };
-            let mut rewards: [f32; BATCH] = [0.0; BATCH];
-            let mut dones = [false; BATCH];
-
-            let mut s_t_next = agent.current_state();
-
-            for i in 0..BATCH {
-                let s_t = agent.current_state().clone();
-                let action = exploration_strategy.pick_action(agent);
+            let s_t = agent.current_state().clone();
+            let action = exploration_strategy.pick_action(agent);
 
-                // current action value
-                s_t_next = agent.current_state();
-                let r_t_next = s_t_next.reward();
+            // current action value
+            let s_t_next = agent.current_state();
+            let reward = step_reward(agent, &s_t, &action);
+            let done = termination_strategy.should_stop_full(s_t_next, Some(&action), reward);
+            let r_t_next =
+                clip_and_normalize(reward, self.reward_clip, &mut self.reward_normalizer);
+            self.state_normalizer.observe(&(*s_t_next).clone().into());
 
-                states[i] = s_t.into();
-                actions[i] = action.into();
-                next_states[i] = (*s_t_next).clone().into();
-                rewards[i] = r_t_next as f32;
-
-                if termination_strategy.should_stop(s_t_next) {
-                    dones[i] = true;
-                    break;
-                }
+            self.replay_buffer.push(
+                s_t.into(),
+                action.into(),
+                <E as Feature>::from_f64(r_t_next),
+                (*s_t_next).clone().into(),
+                done,
+            );
+            steps += 1;
+            #[cfg(feature = "logging")]
+            if (steps as u64).is_multiple_of(crate::logging::LOG_INTERVAL) {
+                log::debug!(
+                    "step {}: {:.0} steps/s",
+                    steps,
+                    crate::logging::steps_per_sec(steps as u64, started),
+                );
             }
 
             // train the network
-            self.train_dqn(states, actions, next_states, rewards, dones);
+            if steps.is_multiple_of(BATCH) && self.replay_buffer.len() >= BATCH {
+                let batch = self.replay_buffer.sample();
+                losses.push(self.train_dqn(
+                    batch.states,
+                    batch.actions,
+                    batch.next_states,
+                    batch.rewards,
+                    batch.dones,
+                ));
+            }
 
             // terminate if the agent is done
-            if termination_strategy.should_stop(s_t_next) {
+            if done {
                 break;
             }
         }
+        #[cfg(feature = "logging")]
+        log::info!(
+            "trained {} steps in {:.2}s ({:.0} steps/s)",
+            steps,
+            started.elapsed().as_secs_f64(),
+            crate::logging::steps_per_sec(steps as u64, started),
+        );
+        DQNTrainReport {
+            steps: steps as u64,
+            losses,
+        }
     }
 }
 
-impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize> Default
-    for DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE>
+impl<S, const STATE_SIZE: usize, const ACTION_SIZE: usize, const INNER_SIZE: usize, M, E> Default
+    for DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E>
 where
-    S: State + Into<[f32; STATE_SIZE]>,
-    S::A: Into<[f32; ACTION_SIZE]>,
-    S::A: From<[f32; ACTION_SIZE]>,
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]>,
+    S::A: TryFrom<[E; ACTION_SIZE]>,
+    M: QNetworkModule<STATE_SIZE, ACTION_SIZE, E>,
+    E: DQNFloat,
+    Cpu: Device<E>,
 {
     fn default() -> Self {
-        Self::new(0.99, 1e-3)
+        Self::new(
+            <E as Feature>::from_f64(0.99),
+            <E as Feature>::from_f64(1e-3),
+        )
+    }
+}
+
+/// The epsilon-greedy exploration strategy for the [DQNAgentTrainer]. With probability `epsilon`
+/// it takes a random action, and otherwise the best action according to the network of a
+/// [DQNAgentTrainer].
+///
+/// Since an [ExplorationStrategy] has no access to the trainer that is training, this strategy
+/// holds a snapshot of the trainer's network, taken on construction. Call
+/// [sync](DQNEpsilonGreedy::sync) between training runs to update the snapshot to what the
+/// trainer has learned since.
+///
+/// # Panics
+///
+/// Panics when asked to pick a random action in a `State` without actions, or when the best
+/// action of such a `State` cannot be converted from the action values. Use a termination strategy
+/// that stops in such states, like
+/// [SinkStates](../strategy/terminate/struct.SinkStates.html).
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// # impl From<MyState> for [f32; 2] { fn from(s: MyState) -> Self { [s.0 as f32, 10.] } }
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyAction(i32);
+/// # impl From<MyAction> for [f32; 2] {
+/// #     fn from(a: MyAction) -> Self { if a.0 < 0 { [1., 0.] } else { [0., 1.] } }
+/// # }
+/// # impl From<[f32; 2]> for MyAction {
+/// #     fn from(v: [f32; 2]) -> Self { MyAction(if v[0] >= v[1] { -1 } else { 1 }) }
+/// # }
+/// # impl State for MyState {
+/// #     type A = MyAction;
+/// #     fn reward(&self) -> f64 { -((self.0 - 10).abs() as f64) }
+/// #     fn actions(&self) -> Vec<MyAction> { vec![MyAction(-1), MyAction(1)] }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &MyAction) {
+/// #         self.state = MyState((self.state.0 + action.0).clamp(0, 20))
+/// #     }
+/// # }
+/// use rurel::dqn::{DQNAgentTrainer, DQNEpsilonGreedy};
+/// use rurel::strategy::explore::{ExplorationStrategy, RandomExploration};
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = DQNAgentTrainer::<MyState, 2, 2, 16>::new(0.9, 1e-2);
+/// let mut exploration = DQNEpsilonGreedy::new(0.5, &trainer);
+/// let mut agent = MyAgent { state: MyState(0) };
+/// for _ in 0..10 {
+///     trainer.train(&mut agent, &mut FixedIterations::new(256), &exploration);
+///     exploration.sync(&trainer);
+/// }
+///
+/// // Sum of rewards received while following an exploration strategy
+/// let rewards = |exploration: &dyn ExplorationStrategy<MyState>| {
+///     let mut agent = MyAgent { state: MyState(0) };
+///     (0..100)
+///         .map(|_| {
+///             exploration.pick_action(&mut agent);
+///             agent.current_state().reward()
+///         })
+///         .sum::<f64>()
+/// };
+/// let greedy = DQNEpsilonGreedy::new(0.1, &trainer);
+/// assert!(rewards(&greedy) > rewards(&RandomExploration::new()));
+/// ```
+pub struct DQNEpsilonGreedy<M, const STATE_SIZE: usize, const ACTION_SIZE: usize, E = f32> {
+    epsilon: f64,
+    network: M,
+    state_normalizer: StateNormalizer<STATE_SIZE>,
+    dev: Cpu,
+    phantom: std::marker::PhantomData<E>,
+}
+
+impl<M, const STATE_SIZE: usize, const ACTION_SIZE: usize, E>
+    DQNEpsilonGreedy<M, STATE_SIZE, ACTION_SIZE, E>
+where
+    M: QNetworkModule<STATE_SIZE, ACTION_SIZE, E>,
+    E: DQNFloat,
+    Cpu: Device<E>,
+{
+    /// Constructs the epsilon-greedy strategy, taking a random action with probability `epsilon`,
+    /// and otherwise the best action according to the current network and state normalization of
+    /// `trainer`.
+    pub fn new<S, const INNER_SIZE: usize>(
+        epsilon: f64,
+        trainer: &DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E>,
+    ) -> DQNEpsilonGreedy<M, STATE_SIZE, ACTION_SIZE, E>
+    where
+        S: State + Into<[E; STATE_SIZE]>,
+        S::A: Into<[E; ACTION_SIZE]>,
+        S::A: TryFrom<[E; ACTION_SIZE]>,
+    {
+        DQNEpsilonGreedy {
+            epsilon,
+            network: trainer.q_network.clone(),
+            state_normalizer: trainer.state_normalizer.clone(),
+            dev: trainer.dev.clone(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Updates the snapshot of the network and the state normalization to the current ones of
+    /// `trainer`.
+    pub fn sync<S, const INNER_SIZE: usize>(
+        &mut self,
+        trainer: &DQNAgentTrainer<S, STATE_SIZE, ACTION_SIZE, INNER_SIZE, M, E>,
+    ) where
+        S: State + Into<[E; STATE_SIZE]>,
+        S::A: Into<[E; ACTION_SIZE]>,
+        S::A: TryFrom<[E; ACTION_SIZE]>,
+    {
+        self.network.clone_from(&trainer.q_network);
+        self.state_normalizer.clone_from(&trainer.state_normalizer);
+    }
+}
+
+impl<S, M, const STATE_SIZE: usize, const ACTION_SIZE: usize, E> ExplorationStrategy<S>
+    for DQNEpsilonGreedy<M, STATE_SIZE, ACTION_SIZE, E>
+where
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]> + TryFrom<[E; ACTION_SIZE]>,
+    M: QNetworkModule<STATE_SIZE, ACTION_SIZE, E>,
+    E: DQNFloat,
+    Cpu: Device<E>,
+{
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        if rand::random::<f64>() < self.epsilon {
+            agent
+                .pick_random_action()
+                .expect("no actions can be taken from the current State")
+        } else {
+            let state = agent.current_state();
+            let action_values = forward_state(
+                &self.dev,
+                &self.network,
+                &self.state_normalizer,
+                state.clone().into(),
+            );
+            let action = best_legal_action(state, action_values)
+                .expect("the action values are not a valid action");
+            agent.take_action(&action);
+            action
+        }
+    }
+}
+
+/// The default Q network architecture of the [DQNStateActionTrainer]: a [QNetwork] that maps the
+/// concatenated features of a state and an action of `INPUT_SIZE` to a single value, as built on
+/// the CPU.
+pub type StateActionQNetworkDevice<const INPUT_SIZE: usize, const INNER_SIZE: usize, E = f32> =
+    QNetworkDevice<INPUT_SIZE, 1, INNER_SIZE, E>;
+
+// A transition stored in the replay buffer of a DQNStateActionTrainer, with the features of all
+// actions of the next state to compute the target value.
+struct StateActionTransition<const STATE_SIZE: usize, const ACTION_SIZE: usize, E> {
+    state: [E; STATE_SIZE],
+    action: [E; ACTION_SIZE],
+    reward: E,
+    next_state: [E; STATE_SIZE],
+    next_actions: Vec<[E; ACTION_SIZE]>,
+    done: bool,
+}
+
+/// Concatenates the features of a state and an action into the input of a state-action network.
+fn concat_features<
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INPUT_SIZE: usize,
+    E: Copy + Default,
+>(
+    state: &[E; STATE_SIZE],
+    action: &[E; ACTION_SIZE],
+) -> [E; INPUT_SIZE] {
+    let mut input = [E::default(); INPUT_SIZE];
+    input[..STATE_SIZE].copy_from_slice(state);
+    input[STATE_SIZE..].copy_from_slice(action);
+    input
+}
+
+/// A `DQNStateActionTrainer` learns the value of actions like a [DQNAgentTrainer], but with a
+/// network that takes the features of a state and an action, and returns the value of taking that
+/// action in that state. The action is not encoded as one output per action, but as a feature
+/// vector of `ACTION_SIZE`, so the size of the network does not depend on the number of actions.
+/// This allows large and parameterized action spaces, where similar actions have similar
+/// features. The drawback is that the network is evaluated once for every action of a state.
+///
+/// The input of the network is the concatenation of the `STATE_SIZE` features of the state and the
+/// `ACTION_SIZE` features of the action, so `INPUT_SIZE` must be `STATE_SIZE + ACTION_SIZE`. The
+/// state features are normalized with the running statistics of all observed states, like in a
+/// [DQNAgentTrainer]; the action features are used as they are.
+///
+/// The Q network is a [StateActionQNetworkDevice] by default. A different architecture can be
+/// chosen with the network type `M`, see [QNetworkModule]. The network computes in `f32` unless
+/// the element type `E` is set to `f64`, see [DQNFloat].
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// # impl From<MyState> for [f32; 1] { fn from(s: MyState) -> Self { [s.0 as f32] } }
+/// // An action moving any distance up to 3 in either direction.
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Move(i32);
+///
+/// impl From<Move> for [f32; 1] {
+///     fn from(m: Move) -> Self {
+///         [m.0 as f32 / 3.]
+///     }
+/// }
+/// # impl State for MyState {
+/// #     type A = Move;
+/// #     fn reward(&self) -> f64 { -((self.0 - 10).abs() as f64) }
+/// #     fn actions(&self) -> Vec<Move> { (-3..=3).filter(|&d| d != 0).map(Move).collect() }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &Move) {
+/// #         self.state = MyState((self.state.0 + action.0).clamp(0, 20))
+/// #     }
+/// # }
+/// use rurel::dqn::DQNStateActionTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = DQNStateActionTrainer::<MyState, 1, 1, 2, 16>::new(0.9, 1e-2)
+///     .with_reward_normalization();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// trainer.train(&mut agent, &mut FixedIterations::new(4096), &RandomExploration::new());
+///
+/// // Far from the goal at 10, moving towards it is learned to be better than moving away.
+/// assert!(trainer.best_action(&MyState(0)).unwrap().0 > 0);
+/// assert!(trainer.best_action(&MyState(20)).unwrap().0 < 0);
+/// assert!(
+///     trainer.expected_value(&MyState(2), &Move(3)) > trainer.expected_value(&MyState(2), &Move(-3))
+/// );
+/// ```
+pub struct DQNStateActionTrainer<
+    S,
+    const STATE_SIZE: usize,
+    const ACTION_SIZE: usize,
+    const INPUT_SIZE: usize,
+    const INNER_SIZE: usize,
+    M = StateActionQNetworkDevice<INPUT_SIZE, INNER_SIZE>,
+    E = f32,
+> where
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]>,
+    E: DQNFloat,
+    Cpu: Device<E>,
+{
+    // values future rewards
+    gamma: E,
+    // gradient steps per training batch
+    train_iterations: usize,
+    // gradient steps between syncs of the target network
+    target_update_interval: usize,
+    gradient_steps: usize,
+    loss: DQNLoss,
+    reward_normalizer: Option<RewardNormalizer>,
+    reward_clip: Option<(f64, f64)>,
+    state_normalizer: StateNormalizer<STATE_SIZE>,
+    q_network: M,
+    target_q_net: M,
+    optimizer: OptimizerState<M, E>,
+    replay_buffer: ReplayRing<StateActionTransition<STATE_SIZE, ACTION_SIZE, E>>,
+    dev: Cpu,
+    phantom: std::marker::PhantomData<S>,
+}
+
+impl<
+        S,
+        const STATE_SIZE: usize,
+        const ACTION_SIZE: usize,
+        const INPUT_SIZE: usize,
+        const INNER_SIZE: usize,
+        M,
+        E,
+    > DQNStateActionTrainer<S, STATE_SIZE, ACTION_SIZE, INPUT_SIZE, INNER_SIZE, M, E>
+where
+    S: State + Into<[E; STATE_SIZE]>,
+    S::A: Into<[E; ACTION_SIZE]>,
+    M: QNetworkModule<INPUT_SIZE, 1, E>,
+    E: DQNFloat,
+    Cpu: Device<E>,
+{
+    const INPUT_SIZE_MATCHES: () = assert!(
+        INPUT_SIZE == STATE_SIZE + ACTION_SIZE,
+        "INPUT_SIZE must be STATE_SIZE + ACTION_SIZE"
+    );
+
+    /// Creates a new `DQNStateActionTrainer` with the discount factor `gamma` for future rewards,
+    /// using SGD with Nesterov momentum and the given learning rate.
+    pub fn new(
+        gamma: E,
+        learning_rate: E,
+    ) -> DQNStateActionTrainer<S, STATE_SIZE, ACTION_SIZE, INPUT_SIZE, INNER_SIZE, M, E> {
+        Self::with_optimizer(
+            gamma,
+            DQNOptimizer::Sgd(SgdConfig {
+                lr: learning_rate,
+                momentum: Some(Momentum::Nesterov(<E as Feature>::from_f64(0.9))),
+                weight_decay: None,
+            }),
+        )
+    }
+
+    /// Creates a new `DQNStateActionTrainer` that updates its network using the given optimizer.
+    pub fn with_optimizer(
+        gamma: E,
+        optimizer: DQNOptimizer<E>,
+    ) -> DQNStateActionTrainer<S, STATE_SIZE, ACTION_SIZE, INPUT_SIZE, INNER_SIZE, M, E> {
+        let () = Self::INPUT_SIZE_MATCHES;
+        let dev = AutoDevice::default();
+        let q_net = M::build(&dev);
+        let target_q_net = q_net.clone();
+        let optimizer = OptimizerState::new(&q_net, optimizer);
+
+        DQNStateActionTrainer {
+            gamma,
+            train_iterations: 20,
+            target_update_interval: 20,
+            gradient_steps: 0,
+            loss: DQNLoss::default(),
+            reward_normalizer: None,
+            reward_clip: None,
+            state_normalizer: StateNormalizer::new(),
+            q_network: q_net,
+            target_q_net,
+            optimizer,
+            replay_buffer: ReplayRing::new(10_000),
+            dev,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Enables reward normalization, see
+    /// [DQNAgentTrainer::with_reward_normalization]. Reward normalization is disabled by default.
+    pub fn with_reward_normalization(mut self) -> Self {
+        self.reward_normalizer = Some(RewardNormalizer::new());
+        self
+    }
+
+    /// Enables reward clipping to the range from `min` to `max`, see
+    /// [DQNAgentTrainer::with_reward_clip]. Reward clipping is disabled by default.
+    pub fn with_reward_clip(mut self, min: f64, max: f64) -> Self {
+        self.reward_clip = Some((min, max));
+        self
+    }
+
+    /// Sets the number of gradient steps between two updates of the target network, which
+    /// computes the training targets. Defaults to 20. An interval of 0 is
+    /// treated as 1, syncing after every gradient step.
+    pub fn set_target_update_interval(&mut self, target_update_interval: usize) {
+        self.target_update_interval = target_update_interval.max(1);
+    }
+
+    /// Sets the loss function that is minimized when training. Defaults to
+    /// [DQNLoss::Huber] with a delta of 1.
+    pub fn set_loss(&mut self, loss: DQNLoss) {
+        self.loss = loss;
+    }
+
+    /// Sets the maximum number of transitions kept in the replay buffer that training batches are
+    /// sampled from. Defaults to 10000. This clears the replay buffer.
+    pub fn set_replay_capacity(&mut self, capacity: usize) {
+        self.replay_buffer = ReplayRing::new(capacity);
+    }
+
+    /// Returns the number of gradient steps taken so far.
+    pub fn gradient_steps(&self) -> usize {
+        self.gradient_steps
+    }
+
+    /// Returns a clone of the entire learned state to be saved or used elsewhere.
+    pub fn export_learned_values(&self) -> M {
+        self.q_network.clone()
+    }
+
+    /// Imports a model, completely replacing any learned progress.
+    pub fn import_model(&mut self, model: M) {
+        self.q_network.clone_from(&model);
+        self.target_q_net.clone_from(&self.q_network);
+    }
+
+    /// Returns the input of the network for the given state and action features, normalizing the
+    /// state features.
+    fn input(&self, state: [E; STATE_SIZE], action: &[E; ACTION_SIZE]) -> [E; INPUT_SIZE] {
+        concat_features(&self.state_normalizer.normalize(state), action)
+    }
+
+    /// Fetches the learned value of the given `Action` in the given `State`.
+    pub fn expected_value(&self, state: &S, action: &S::A) -> E {
+        let input = self.input(state.clone().into(), &action.clone().into());
+        let input: Tensor<Rank1<INPUT_SIZE>, E, _> = self.dev.tensor(input);
+        self.q_network.forward(input).nans_to(E::default()).array()[0]
+    }
+
+    /// Fetches the learned values of all actions of the given `State`, in a single forward pass
+    /// of the network.
+    pub fn expected_values(&self, state: &S) -> Vec<(S::A, E)> {
+        let actions = state.actions();
+        if actions.is_empty() {
+            return vec![];
+        }
+        let state_features: [E; STATE_SIZE] = state.clone().into();
+        let data: Vec<E> = actions
+            .iter()
+            .flat_map(|action| self.input(state_features, &action.clone().into()))
+            .collect();
+        let inputs: Tensor<(usize, Const<INPUT_SIZE>), E, _> =
+            self.dev.tensor_from_vec(data, (actions.len(), Const));
+        let values = self
+            .q_network
+            .forward(inputs)
+            .nans_to(E::default())
+            .as_vec();
+        actions.into_iter().zip(values).collect()
+    }
+
+    /// Returns the action of the given `State` with the highest learned value, or `None` if the
+    /// `State` has no actions.
+    pub fn best_action(&self, state: &S) -> Option<S::A> {
+        self.expected_values(state)
+            .into_iter()
+            .max_by(|(_, v1), (_, v2)| v1.to_f64().total_cmp(&v2.to_f64()))
+            .map(|(action, _)| action)
+    }
+
+    /// Trains the network on a batch of [BATCH] transitions sampled uniformly from the replay
+    /// buffer. The target value of a transition is its reward plus the discounted highest value
+    /// of the actions of its next state.
+    fn train_batch(&mut self) {
+        let mut rng = rand::thread_rng();
+        let batch: Vec<&StateActionTransition<STATE_SIZE, ACTION_SIZE, E>> = (0..BATCH)
+            .map(|_| self.replay_buffer.sample_one(&mut rng))
+            .collect();
+
+        let inputs: Vec<E> = batch
+            .iter()
+            .flat_map(|t| self.input(t.state, &t.action))
+            .collect();
+        let inputs: Tensor<Rank2<BATCH, INPUT_SIZE>, E, _> =
+            self.dev.tensor_from_vec(inputs, Default::default());
+        let rewards: Vec<f64> = batch.iter().map(|t| t.reward.to_f64()).collect();
+        // The inputs of all actions of the next states, and the number of actions per transition.
+        let next_counts: Vec<usize> = batch
+            .iter()
+            .map(|t| if t.done { 0 } else { t.next_actions.len() })
+            .collect();
+        let next_inputs: Vec<E> = batch
+            .iter()
+            .filter(|t| !t.done)
+            .flat_map(|t| {
+                t.next_actions
+                    .iter()
+                    .flat_map(|action| self.input(t.next_state, action))
+            })
+            .collect();
+        let next_len: usize = next_counts.iter().sum();
+        let gamma = self.gamma.to_f64();
+
+        let mut grads = self.q_network.alloc_grads();
+        for _step in 0..self.train_iterations {
+            // targ_q = R + discount * max(Q(S', A'))
+            let next_values = if next_len == 0 {
+                vec![]
+            } else {
+                let next_inputs: Tensor<(usize, Const<INPUT_SIZE>), E, _> = self
+                    .dev
+                    .tensor_from_vec(next_inputs.clone(), (next_len, Const));
+                self.target_q_net.forward(next_inputs).as_vec()
+            };
+            let mut next_values = next_values.into_iter();
+            let targets: Vec<E> = rewards
+                .iter()
+                .zip(&next_counts)
+                .map(|(reward, &count)| {
+                    let max_next = next_values
+                        .by_ref()
+                        .take(count)
+                        .map(|v| v.to_f64())
+                        .fold(None, |max: Option<f64>, v| {
+                            Some(max.map_or(v, |m| m.max(v)))
+                        });
+                    <E as Feature>::from_f64(reward + gamma * max_next.unwrap_or(0.))
+                })
+                .collect();
+            let targets: Tensor<Rank1<BATCH>, E, _> =
+                self.dev.tensor_from_vec(targets, Default::default());
+
+            let q_values = self
+                .q_network
+                .forward(inputs.trace(grads))
+                .reshape::<Rank1<BATCH>>();
+            let loss = self.loss.compute(q_values, targets);
+            grads = loss.backward();
+
+            self.optimizer.update(&mut self.q_network, &grads);
+            self.q_network.zero_grads(&mut grads);
+
+            self.gradient_steps += 1;
+            if self
+                .gradient_steps
+                .is_multiple_of(self.target_update_interval)
+            {
+                self.target_q_net.clone_from(&self.q_network);
+            }
+        }
+    }
+
+    /// Trains this [DQNStateActionTrainer] using the given [ExplorationStrategy] and [Agent]
+    /// until the [TerminationStrategy] decides to stop.
+    ///
+    /// Every observed transition is stored in a replay buffer, together with the features of all
+    /// actions of its next state. After every [BATCH] steps, the network is trained on a batch
+    /// sampled uniformly from the replay buffer. Training starts once the replay buffer holds at
+    /// least [BATCH] transitions.
+    pub fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        let mut steps: usize = 0;
+        self.state_normalizer
+            .observe(&agent.current_state().clone().into());
+        loop {
+            let s_t = agent.current_state().clone();
+            let action = exploration_strategy.pick_action(agent);
+
+            let s_t_next = agent.current_state();
+            let reward = step_reward(agent, &s_t, &action);
+            let done = termination_strategy.should_stop_full(s_t_next, Some(&action), reward);
+            let r_t_next =
+                clip_and_normalize(reward, self.reward_clip, &mut self.reward_normalizer);
+            let next_state: [E; STATE_SIZE] = s_t_next.clone().into();
+            self.state_normalizer.observe(&next_state);
+
+            self.replay_buffer.push(StateActionTransition {
+                state: s_t.into(),
+                action: action.into(),
+                reward: <E as Feature>::from_f64(r_t_next),
+                next_state,
+                next_actions: s_t_next.actions().into_iter().map(Into::into).collect(),
+                done,
+            });
+            steps += 1;
+
+            if steps.is_multiple_of(BATCH) && self.replay_buffer.len() >= BATCH {
+                self.train_batch();
+            }
+
+            if done {
+                break;
+            }
+        }
     }
 }
@@ -2,12 +2,11 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashMap;
-
 use rurel::mdp::{Agent, State};
 use rurel::strategy::explore::RandomExploration;
 use rurel::strategy::learn::QLearning;
 use rurel::strategy::terminate::FixedIterations;
+use rurel::viz::render_grid;
 use rurel::AgentTrainer;
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -83,28 +82,24 @@ fn main() {
         &mut FixedIterations::new(100000),
         &RandomExploration::new(),
     );
-    for j in 0..21 {
-        for i in 0..21 {
-            let entry: &HashMap<MyAction, f64> = trainer
-                .expected_values(&MyState {
-                    x: i,
-                    y: j,
-                    ..initial_state
-                })
-                .unwrap();
-            let best_action = entry
-                .iter()
-                .max_by(|(_, v1), (_, v2)| v1.partial_cmp(v2).unwrap())
-                .map(|(v, _)| v)
-                .unwrap();
-            match best_action {
-                MyAction::Move { dx: -1, dy: 0 } => print!("<"),
-                MyAction::Move { dx: 1, dy: 0 } => print!(">"),
-                MyAction::Move { dx: 0, dy: -1 } => print!("^"),
-                MyAction::Move { dx: 0, dy: 1 } => print!("v"),
-                _ => unreachable!(),
+    let states = (0..21).flat_map(|j| {
+        (0..21).map(move |i| {
+            let state = MyState {
+                x: i,
+                y: j,
+                ..initial_state
             };
-        }
-        println!();
-    }
+            (i as usize, j as usize, state)
+        })
+    });
+    print!(
+        "{}",
+        render_grid(&trainer, states, |action| match action {
+            MyAction::Move { dx: -1, dy: 0 } => '<',
+            MyAction::Move { dx: 1, dy: 0 } => '>',
+            MyAction::Move { dx: 0, dy: -1 } => '^',
+            MyAction::Move { dx: 0, dy: 1 } => 'v',
+            _ => unreachable!(),
+        })
+    );
 }
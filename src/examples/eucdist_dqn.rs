@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+#![cfg_attr(not(feature = "dqn"), allow(dead_code))]
+
 #[cfg(feature = "dqn")]
 use rurel::dqn::DQNAgentTrainer;
 use rurel::mdp::{Agent, State};
@@ -136,6 +138,7 @@ impl Agent<MyState> for MyAgent {
 fn main() {
     use rurel::strategy::explore::RandomExploration;
     use rurel::strategy::terminate::FixedIterations;
+    use rurel::viz::render_grid_with;
     let (tx, ty) = (10, 10);
     let (maxx, maxy) = (21, 21);
     let initial_state = MyState {
@@ -156,28 +159,33 @@ fn main() {
         &mut FixedIterations::new(10_000),
         &RandomExploration::new(),
     );
-    for j in 0..maxy {
-        for i in 0..maxx {
-            let best_action = trainer
-                .best_action(&MyState {
-                    tx,
-                    ty,
-                    x: i,
-                    y: j,
-                    maxx,
-                    maxy,
-                })
-                .unwrap();
-            match best_action {
-                MyAction::Move { dx: -1, dy: 0 } => print!("<"),
-                MyAction::Move { dx: 1, dy: 0 } => print!(">"),
-                MyAction::Move { dx: 0, dy: -1 } => print!("^"),
-                MyAction::Move { dx: 0, dy: 1 } => print!("v"),
-                _ => print!("-"),
+    let states = (0..maxy).flat_map(|j| {
+        (0..maxx).map(move |i| {
+            let state = MyState {
+                tx,
+                ty,
+                x: i,
+                y: j,
+                maxx,
+                maxy,
             };
-        }
-        println!();
-    }
+            (i as usize, j as usize, state)
+        })
+    });
+    print!(
+        "{}",
+        render_grid_with(
+            |state| trainer.best_action(state),
+            states,
+            |action| match action {
+                MyAction::Move { dx: -1, dy: 0 } => '<',
+                MyAction::Move { dx: 1, dy: 0 } => '>',
+                MyAction::Move { dx: 0, dy: -1 } => '^',
+                MyAction::Move { dx: 0, dy: 1 } => 'v',
+                _ => '-',
+            }
+        )
+    );
 
     /*
     >>>>>vvvvvvvvvv<<<<<<
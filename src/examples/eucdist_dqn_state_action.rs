@@ -0,0 +1,141 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg_attr(not(feature = "dqn"), allow(dead_code))]
+
+#[cfg(feature = "dqn")]
+use rurel::dqn::DQNStateActionTrainer;
+use rurel::mdp::{Agent, State};
+
+/// A simple 2D grid world where the agent can jump up to 2 cells in each direction.
+/// The agent has to reach (10, 10).
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct MyState {
+    x: i32,
+    y: i32,
+}
+
+const TARGET: (i32, i32) = (10, 10);
+const SIZE: i32 = 21;
+
+// Into float array has to be implemented for the DQN state
+impl From<MyState> for [f32; 2] {
+    fn from(val: MyState) -> Self {
+        [val.x as f32, val.y as f32]
+    }
+}
+
+// A parameterized action. Instead of encoding each of the 24 jumps as a separate output of the
+// network, the network takes the features of the jump as input.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Jump {
+    dx: i32,
+    dy: i32,
+}
+
+// Into float array has to be implemented for the action, so that the network can take it as
+// input. Jumps with similar features are similar jumps.
+impl From<Jump> for [f32; 2] {
+    fn from(val: Jump) -> Self {
+        [val.dx as f32 / 2., val.dy as f32 / 2.]
+    }
+}
+
+impl State for MyState {
+    type A = Jump;
+
+    fn reward(&self) -> f64 {
+        let d = (((TARGET.0 - self.x).pow(2) + (TARGET.1 - self.y).pow(2)) as f64).sqrt();
+        -d
+    }
+
+    fn actions(&self) -> Vec<Jump> {
+        (-2..=2)
+            .flat_map(|dx| (-2..=2).map(move |dy| Jump { dx, dy }))
+            .filter(|jump| jump.dx != 0 || jump.dy != 0)
+            .collect()
+    }
+}
+
+struct MyAgent {
+    state: MyState,
+}
+
+impl Agent<MyState> for MyAgent {
+    fn current_state(&self) -> &MyState {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &Jump) {
+        self.state = MyState {
+            x: (self.state.x + action.dx).clamp(0, SIZE - 1),
+            y: (self.state.y + action.dy).clamp(0, SIZE - 1),
+        };
+    }
+}
+
+#[cfg(feature = "dqn")]
+fn main() {
+    use rurel::strategy::explore::RandomExploration;
+    use rurel::strategy::terminate::FixedIterations;
+    use rurel::viz::render_grid_with;
+
+    // 2 state features and 2 action features make 4 inputs.
+    let mut trainer = DQNStateActionTrainer::<MyState, 2, 2, 4, 64>::new(0.9, 1e-3);
+    let mut agent = MyAgent {
+        state: MyState { x: 0, y: 0 },
+    };
+    trainer.train(
+        &mut agent,
+        &mut FixedIterations::new(20_000),
+        &RandomExploration::new(),
+    );
+    let states = (0..SIZE)
+        .flat_map(|j| (0..SIZE).map(move |i| (i as usize, j as usize, MyState { x: i, y: j })));
+    print!(
+        "{}",
+        render_grid_with(
+            |state| trainer.best_action(state),
+            states,
+            |jump| match (jump.dx.signum(), jump.dy.signum()) {
+                (-1, 0) => '<',
+                (1, 0) => '>',
+                (0, -1) => '^',
+                (0, 1) => 'v',
+                (-1, -1) | (1, 1) => '\\',
+                _ => '/',
+            }
+        )
+    );
+
+    /*
+    \\\\\\\\\\\v/////////
+    \\\\\\\\\\\v/////////
+    \\\\\\\\\\\v/////////
+    \\\\\\\\\\\v/////////
+    \\\\\\\\\\vv/////////
+    \\\\\\\\\\v//////////
+    \\\\\\\\\v///////////
+    \\\\\\\\\\v//////////
+    >>>>\\\\\\v//////////
+    ////>\\\\\v//////////
+    //////>>>>v<<<<</////
+    //////////^\\\\\<////
+    //////////^\\\\\\\\\\
+    //////////\\\\\\\\\\\
+    //////////\\\\\\\\\\\
+    //////////\\\\\\\\\\\
+    /////////^\\\\\\\\\\\
+    /////////^\\\\\\\\\\\
+    /////////^\\\\\\\\\\\
+    //////////\\\\\\\\\\\
+    //////////^\\\\\\\\\\
+    */
+}
+
+#[cfg(not(feature = "dqn"))]
+fn main() {
+    panic!("Use the 'dqn' feature to run this example");
+}
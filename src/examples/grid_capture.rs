@@ -0,0 +1,139 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use rurel::mdp::{Agent, State};
+use rurel::multi::MultiAgentTrainer;
+use rurel::strategy::explore::RandomExploration;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::SinkStates;
+
+const SIZE: i32 = 5;
+const HUNTER: usize = 0;
+const PREY: usize = 1;
+
+/// A hunter and a prey take turns moving on a grid. The game ends when they are on the same cell.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct GameState {
+    positions: [(i32, i32); 2],
+    turn: usize,
+}
+
+impl GameState {
+    fn captured(&self) -> bool {
+        self.positions[HUNTER] == self.positions[PREY]
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+enum GameAction {
+    Move { dx: i32, dy: i32 },
+}
+
+impl State for GameState {
+    type A = GameAction;
+
+    fn reward(&self) -> f64 {
+        0.0
+    }
+
+    fn actions(&self) -> Vec<GameAction> {
+        if self.captured() {
+            return vec![];
+        }
+        vec![
+            GameAction::Move { dx: -1, dy: 0 },
+            GameAction::Move { dx: 1, dy: 0 },
+            GameAction::Move { dx: 0, dy: -1 },
+            GameAction::Move { dx: 0, dy: 1 },
+        ]
+    }
+}
+
+struct GameAgent {
+    state: GameState,
+}
+
+impl Agent<GameState> for GameAgent {
+    fn current_state(&self) -> &GameState {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &GameAction) {
+        match action {
+            &GameAction::Move { dx, dy } => {
+                let mut positions = self.state.positions;
+                let (x, y) = positions[self.state.turn];
+                positions[self.state.turn] =
+                    ((x + dx).clamp(0, SIZE - 1), (y + dy).clamp(0, SIZE - 1));
+                self.state = GameState {
+                    positions,
+                    turn: 1 - self.state.turn,
+                };
+            }
+        }
+    }
+}
+
+/// Capturing is worth 1 to the hunter and -1 to the prey. Every move costs the hunter a little,
+/// so it learns to capture quickly.
+fn reward(state: &GameState, player: usize) -> f64 {
+    match (state.captured(), player) {
+        (true, HUNTER) => 1.0,
+        (true, _) => -1.0,
+        (false, HUNTER) => -0.01,
+        (false, _) => 0.0,
+    }
+}
+
+fn main() {
+    let mut trainer = MultiAgentTrainer::new(2);
+    for game in 0..20000 {
+        let mut agent = GameAgent {
+            state: GameState {
+                positions: [(0, 0), (game % SIZE, SIZE - 1)],
+                turn: HUNTER,
+            },
+        };
+        trainer.train(
+            &mut agent,
+            &|state| state.turn,
+            &reward,
+            &QLearning::new(0.2, 0.9, 0.0),
+            &mut SinkStates {},
+            &RandomExploration::new(),
+        );
+    }
+
+    println!(
+        "Learned states: hunter {}, prey {}",
+        trainer.trainer(HUNTER).num_states(),
+        trainer.trainer(PREY).num_states()
+    );
+
+    // Show the moves of the hunter towards the prey in the center, and the moves of the prey
+    // when the hunter is in the center.
+    let arrow = |action: Option<GameAction>| match action {
+        Some(GameAction::Move { dx: -1, dy: 0 }) => "<",
+        Some(GameAction::Move { dx: 1, dy: 0 }) => ">",
+        Some(GameAction::Move { dx: 0, dy: -1 }) => "^",
+        Some(GameAction::Move { dx: 0, dy: 1 }) => "v",
+        _ => ".",
+    };
+    let center = (SIZE / 2, SIZE / 2);
+    for (player, title) in [(HUNTER, "Hunter"), (PREY, "Prey")] {
+        println!("{}:", title);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let mut positions = [center, center];
+                positions[player] = (x, y);
+                let state = GameState {
+                    positions,
+                    turn: player,
+                };
+                print!("{}", arrow(trainer.trainer(player).best_action(&state)));
+            }
+            println!();
+        }
+    }
+}
@@ -0,0 +1,170 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use rand::Rng;
+use rurel::mdp::{Agent, State};
+use rurel::strategy::explore::RandomExploration;
+use rurel::strategy::learn::QLearning;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::table::QTable;
+use rurel::AgentTrainer;
+
+const TILES: usize = 10;
+const FEATURES: usize = 2 * (TILES + 1);
+
+/// A position on a continuous line between 0 and 1. Positions are compared by their bits, the
+/// value function below never relies on this.
+#[derive(Clone, Copy, PartialEq)]
+struct MyState {
+    x: f64,
+}
+
+impl Eq for MyState {}
+
+impl Hash for MyState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x.to_bits().hash(state);
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum MyAction {
+    Left,
+    Right,
+}
+
+impl State for MyState {
+    type A = MyAction;
+
+    fn reward(&self) -> f64 {
+        -(0.7 - self.x).abs()
+    }
+
+    fn actions(&self) -> Vec<MyAction> {
+        vec![MyAction::Left, MyAction::Right]
+    }
+}
+
+struct MyAgent {
+    state: MyState,
+}
+
+impl Agent<MyState> for MyAgent {
+    fn current_state(&self) -> &MyState {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &MyAction) {
+        let step = rand::thread_rng().gen_range(0.02..0.08);
+        let x = match action {
+            MyAction::Left => self.state.x - step,
+            MyAction::Right => self.state.x + step,
+        };
+        self.state = MyState { x: x.clamp(0., 1.) };
+    }
+}
+
+/// A linear value function over two overlapping tilings of the line. Instead of storing a value
+/// per state, every inserted value moves the weights of the active tiles towards it, so what is
+/// learned for one position generalizes to nearby positions.
+struct LinearTable {
+    weights: [[f64; FEATURES]; 2],
+}
+
+impl LinearTable {
+    fn new() -> LinearTable {
+        LinearTable {
+            weights: [[0.; FEATURES]; 2],
+        }
+    }
+
+    fn features(state: &MyState) -> [usize; 2] {
+        let tile = state.x * TILES as f64;
+        [
+            (tile as usize).min(TILES),
+            TILES + 1 + ((tile + 0.5) as usize).min(TILES),
+        ]
+    }
+}
+
+impl QTable<MyState> for LinearTable {
+    fn get(&self, state: &MyState, action: &MyAction) -> Option<f64> {
+        let weights = &self.weights[*action as usize];
+        Some(Self::features(state).iter().map(|&f| weights[f]).sum())
+    }
+
+    fn insert(&mut self, state: MyState, action: MyAction, value: f64) {
+        let error = value - self.get(&state, &action).unwrap();
+        let features = Self::features(&state);
+        for f in features {
+            self.weights[action as usize][f] += error / features.len() as f64;
+        }
+    }
+
+    fn best(&self, state: &MyState) -> Option<(MyAction, f64)> {
+        state
+            .actions()
+            .into_iter()
+            .map(|action| (action, self.get(state, &action).unwrap()))
+            .max_by(|(_, v1), (_, v2)| v1.partial_cmp(v2).unwrap())
+    }
+
+    fn action_values(&self, state: &MyState) -> Option<Cow<'_, HashMap<MyAction, f64>>> {
+        Some(Cow::Owned(
+            state
+                .actions()
+                .into_iter()
+                .map(|action| (action, self.get(state, &action).unwrap()))
+                .collect(),
+        ))
+    }
+
+    // There are no stored states, only weights, so there is nothing to iterate or count.
+    fn iter(&self) -> Box<dyn Iterator<Item = (&MyState, &MyAction, f64)> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    fn num_states(&self) -> usize {
+        0
+    }
+
+    fn num_state_actions(&self) -> usize {
+        0
+    }
+
+    fn contains_state(&self, _state: &MyState) -> bool {
+        true
+    }
+
+    fn clear(&mut self) {
+        self.weights = [[0.; FEATURES]; 2];
+    }
+
+    fn remove_state(&mut self, _state: &MyState) {}
+}
+
+fn main() {
+    let mut trainer = AgentTrainer::with_table(LinearTable::new());
+    let mut agent = MyAgent {
+        state: MyState { x: 0. },
+    };
+    trainer.train(
+        &mut agent,
+        &QLearning::new(0.2, 0.01, 2.),
+        &mut FixedIterations::new(100000),
+        &RandomExploration::new(),
+    );
+    for i in 0..=20 {
+        let state = MyState { x: i as f64 / 20. };
+        match trainer.best_action(&state).unwrap() {
+            MyAction::Left => print!("<"),
+            MyAction::Right => print!(">"),
+        }
+    }
+    println!();
+}
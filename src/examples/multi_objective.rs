@@ -0,0 +1,98 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use rurel::mdp::{Agent, State};
+use rurel::multi_objective::{
+    linear_scalarization, MultiObjectiveQLearning, MultiObjectiveState, MultiObjectiveTrainer,
+};
+use rurel::strategy::explore::RandomExploration;
+use rurel::strategy::terminate::FixedIterations;
+use rurel::viz::render_grid_with;
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct MyState {
+    x: i32,
+    y: i32,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct MyAction {
+    dx: i32,
+    dy: i32,
+}
+
+fn distance(state: &MyState, (x, y): (i32, i32)) -> f64 {
+    (((x - state.x).pow(2) + (y - state.y).pow(2)) as f64).sqrt()
+}
+
+impl State for MyState {
+    type A = MyAction;
+
+    fn reward(&self) -> f64 {
+        0.0
+    }
+
+    fn actions(&self) -> Vec<MyAction> {
+        vec![
+            MyAction { dx: 0, dy: -1 }, // up
+            MyAction { dx: 0, dy: 1 },  // down
+            MyAction { dx: -1, dy: 0 }, // left
+            MyAction { dx: 1, dy: 0 },  // right
+        ]
+    }
+}
+
+// Two competing objectives: getting close to (5, 10), and getting close to (15, 10).
+impl MultiObjectiveState<2> for MyState {
+    fn rewards(&self) -> [f64; 2] {
+        [-distance(self, (5, 10)), -distance(self, (15, 10))]
+    }
+}
+
+struct MyAgent {
+    state: MyState,
+}
+
+impl Agent<MyState> for MyAgent {
+    fn current_state(&self) -> &MyState {
+        &self.state
+    }
+
+    fn take_action(&mut self, action: &MyAction) {
+        self.state = MyState {
+            x: (self.state.x + action.dx).clamp(0, 20),
+            y: (self.state.y + action.dy).clamp(0, 20),
+        }
+    }
+}
+
+fn main() {
+    for weights in [[1.0, 0.0], [0.5, 0.5], [0.0, 1.0]] {
+        let mut trainer = MultiObjectiveTrainer::new(linear_scalarization(weights));
+        let mut agent = MyAgent {
+            state: MyState { x: 0, y: 0 },
+        };
+        trainer.train(
+            &mut agent,
+            &MultiObjectiveQLearning::new(0.2, 0.5, 0.0),
+            &mut FixedIterations::new(200000),
+            &RandomExploration::new(),
+        );
+
+        println!("weights {:?}:", weights);
+        let states = (0..21).flat_map(|y| (0..21).map(move |x| (x, y, MyState { x, y })));
+        let grid = render_grid_with(
+            |state| trainer.best_action(state),
+            states.map(|(x, y, state)| (x as usize, y as usize, state)),
+            |action| match (action.dx, action.dy) {
+                (-1, 0) => '<',
+                (1, 0) => '>',
+                (0, -1) => '^',
+                (0, 1) => 'v',
+                _ => '-',
+            },
+        );
+        println!("{}", grid);
+    }
+}
@@ -2,10 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use rurel::mdp::{Agent, State};
+use rand::Rng;
+use rurel::mdp::{Agent, ResettableAgent, State};
 use rurel::strategy::explore::RandomExploration;
 use rurel::strategy::learn::QLearning;
-use rurel::strategy::terminate::SinkStates;
 use rurel::AgentTrainer;
 
 const TARGET: i32 = 100;
@@ -66,22 +66,29 @@ impl Agent<CoinState> for CoinAgent {
     }
 }
 
+impl ResettableAgent<CoinState> for CoinAgent {
+    // Every trial starts with a random balance
+    fn reset(&mut self) {
+        self.state = CoinState {
+            balance: rand::thread_rng().gen_range(1..TARGET - 1),
+        }
+    }
+}
+
 fn main() {
-    const TRIALS: i32 = 100000;
+    const TRIALS: usize = 100000;
     let mut trainer = AgentTrainer::new();
-    for trial in 0..TRIALS {
-        let mut agent = CoinAgent {
-            state: CoinState {
-                balance: 1 + trial % 98,
-            },
-        };
-        trainer.train(
-            &mut agent,
-            &QLearning::new(0.2, 1.0, 0.0),
-            &mut SinkStates {},
-            &RandomExploration::new(),
-        );
-    }
+    let mut agent = CoinAgent {
+        state: CoinState { balance: 1 },
+    };
+    // Every trial ends when the balance reaches 0 or the target, where no more bets can be made
+    trainer.train_for_episodes(
+        &mut agent,
+        &QLearning::new(0.2, 1.0, 0.0),
+        &RandomExploration::new(),
+        TRIALS,
+        u64::MAX,
+    );
 
     println!("Balance\tBet\tQ-value");
     for balance in 1..TARGET {
@@ -0,0 +1,135 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Small environments shared by the documentation examples. Not part of the public API.
+
+/// A line of positions from -5 to 5, with a reward that is highest at 0.
+pub mod line {
+    use crate::mdp::{Agent, State};
+
+    #[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Debug)]
+    #[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+    pub struct MyState(pub i32);
+
+    impl State for MyState {
+        type A = i32;
+        fn reward(&self) -> f64 {
+            -(self.0.abs() as f64)
+        }
+        fn actions(&self) -> Vec<i32> {
+            vec![-1, 1]
+        }
+    }
+
+    pub struct MyAgent {
+        pub state: MyState,
+    }
+
+    impl Agent<MyState> for MyAgent {
+        fn current_state(&self) -> &MyState {
+            &self.state
+        }
+        fn take_action(&mut self, action: &i32) {
+            self.state = MyState((self.state.0 + action).clamp(-5, 5))
+        }
+    }
+}
+
+/// A 21x21 grid that wraps around at its edges, with a reward that is highest at (10, 10).
+pub mod grid {
+    use crate::mdp::{Agent, State};
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    pub struct MyState {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    pub struct MyAction {
+        pub dx: i32,
+        pub dy: i32,
+    }
+
+    impl State for MyState {
+        type A = MyAction;
+        fn reward(&self) -> f64 {
+            -((((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt())
+        }
+        fn actions(&self) -> Vec<MyAction> {
+            vec![
+                MyAction { dx: 0, dy: -1 },
+                MyAction { dx: 0, dy: 1 },
+                MyAction { dx: -1, dy: 0 },
+                MyAction { dx: 1, dy: 0 },
+            ]
+        }
+    }
+
+    pub struct MyAgent {
+        pub state: MyState,
+    }
+
+    impl Agent<MyState> for MyAgent {
+        fn current_state(&self) -> &MyState {
+            &self.state
+        }
+        fn take_action(&mut self, action: &MyAction) {
+            self.state = MyState {
+                x: (((self.state.x + action.dx) % 21) + 21) % 21,
+                y: (((self.state.y + action.dy) % 21) + 21) % 21,
+            }
+        }
+    }
+}
+
+/// A state with a reward equal to its position, and two actions encoded one-hot, for the
+/// [DQNAgentTrainer](crate::dqn::DQNAgentTrainer). States are encoded as 1 feature (the position)
+/// or 2 features (the position and a constant).
+#[cfg(feature = "dqn")]
+pub mod dqn {
+    use crate::mdp::State;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    pub struct MyState(pub i32);
+
+    impl From<MyState> for [f32; 1] {
+        fn from(s: MyState) -> Self {
+            [s.0 as f32]
+        }
+    }
+
+    impl From<MyState> for [f32; 2] {
+        fn from(s: MyState) -> Self {
+            [s.0 as f32, 1.]
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    pub struct MyAction(pub usize);
+
+    impl From<MyAction> for [f32; 2] {
+        fn from(a: MyAction) -> Self {
+            let mut v = [0.; 2];
+            v[a.0] = 1.;
+            v
+        }
+    }
+
+    impl From<[f32; 2]> for MyAction {
+        fn from(v: [f32; 2]) -> Self {
+            MyAction(if v[0] >= v[1] { 0 } else { 1 })
+        }
+    }
+
+    impl State for MyState {
+        type A = MyAction;
+        fn reward(&self) -> f64 {
+            self.0 as f64
+        }
+        fn actions(&self) -> Vec<MyAction> {
+            vec![MyAction(0), MyAction(1)]
+        }
+    }
+}
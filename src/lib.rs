@@ -76,41 +76,431 @@
 //! assert!(trainer.expected_value(&test_state, &go_down)
 //!     > trainer.expected_value(&test_state, &go_up));
 //! ```
+//!
+//! # no_std
+//!
+//! Training requires `std`, which is enabled by the default `std` feature. Without it, the crate
+//! is `no_std` and does not allocate or depend on `rand`, and only the
+//! [policy](policy/index.html) module is available. Its [PolicyTable](policy/struct.PolicyTable.html)
+//! answers [best_action](policy/struct.PolicyTable.html#method.best_action) and
+//! [expected_value](policy/struct.PolicyTable.html#method.expected_value) queries from a table
+//! that was learned and exported with `std`, for example on an embedded target:
+//!
+//! ```toml
+//! rurel = { version = "0.6.0", default-features = false }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
 // Run tests in the readme, but don't include readme in the documentation.
-#[cfg(doctest)]
+#[cfg(all(doctest, feature = "std"))]
 #[doc = include_str!("../README.md")]
 mod doc_test {}
 
-use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use mdp::{Agent, ResettableAgent, State};
+#[cfg(feature = "std")]
+use normalize::RewardNormalizer;
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
+#[cfg(feature = "std")]
+use rand::seq::SliceRandom;
+#[cfg(feature = "std")]
+use rand::{RngCore, SeedableRng};
+#[cfg(feature = "std")]
+use replay::{ReplayMemory, Transition};
+#[cfg(feature = "std")]
+use report::Reporter;
+#[cfg(feature = "std")]
+use shape::RewardShaper;
+#[cfg(feature = "std")]
+use similarity::StateSimilarity;
+#[cfg(feature = "std")]
+use strategy::episode::EpisodeBoundary;
+#[cfg(feature = "std")]
+use strategy::explore::{ExplorationStrategy, ParameterizedExplorationStrategy};
+#[cfg(feature = "std")]
+use strategy::learn::{
+    LearningStrategy, MonteCarlo, QLearning, SarsaLambda, TreeBackup, VisitMode,
+};
+#[cfg(feature = "std")]
+use strategy::terminate::{TerminationStrategy, ValidationPlateau};
+#[cfg(feature = "std")]
+use table::QTable;
 
-use mdp::{Agent, State};
-use strategy::explore::ExplorationStrategy;
-use strategy::learn::LearningStrategy;
-use strategy::terminate::TerminationStrategy;
+#[cfg(feature = "std")]
+pub use config::TrainConfig;
 
+#[cfg(feature = "std")]
+pub mod config;
 #[cfg(feature = "dqn")]
 pub mod dqn;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod fixtures;
+#[cfg(feature = "logging")]
+pub mod logging;
+#[cfg(feature = "std")]
 pub mod mdp;
+#[cfg(feature = "std")]
+pub mod multi;
+#[cfg(feature = "multi_objective")]
+pub mod multi_objective;
+#[cfg(feature = "std")]
+pub mod normalize;
+pub mod policy;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod shape;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod similarity;
+#[cfg(feature = "bincode")]
+pub mod snapshot;
+#[cfg(feature = "std")]
 pub mod strategy;
+#[cfg(feature = "std")]
+pub mod table;
+#[cfg(feature = "std")]
+pub mod viz;
+
+/// A single step taken during training, as passed to the callback of
+/// [AgentTrainer::train_with_callback] and [AgentTrainer::train_inspecting].
+#[cfg(feature = "std")]
+pub struct TrainingStep<'a, S>
+where
+    S: State,
+{
+    /// The index of this step, starting at 0.
+    pub iteration: u64,
+    /// The `State` the action was taken from.
+    pub state: &'a S,
+    /// The action that was taken.
+    pub action: &'a S::A,
+    /// The `State` the agent arrived in after taking the action.
+    pub next_state: &'a S,
+    /// The reward received for arriving in `next_state`.
+    pub reward: f64,
+    /// The newly learned value for taking `action` in `state`.
+    pub value: f64,
+    /// Whether `next_state` is terminal, which ends the episode: a sink state (a `State` without
+    /// actions), or a `State` the agent marks as
+    /// [terminal](mdp/trait.Agent.html#method.is_terminal).
+    pub terminal: bool,
+}
+
+/// A step taken by the training loop, as passed to the learning hook of the loop.
+#[cfg(feature = "std")]
+struct Step<'a, S>
+where
+    S: State,
+{
+    state: &'a S,
+    action: &'a S::A,
+    next_state: &'a S,
+    // the reward that was received, as passed to termination strategies
+    reward: f64,
+    // the reward to learn from, after shaping, clipping and normalization
+    learned_reward: f64,
+    terminal: bool,
+}
+
+/// Statistics gathered during the last call to [AgentTrainer::train], available through
+/// [AgentTrainer::last_stats].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg(feature = "std")]
+pub struct TrainingStats {
+    /// The number of value updates that were performed.
+    pub steps: u64,
+    /// The sum of all rewards received.
+    pub total_reward: f64,
+    /// The number of episodes that ended in a terminal state (a sink state, or a `State` the agent
+    /// marks as [terminal](mdp/trait.Agent.html#method.is_terminal)), or at an
+    /// [EpisodeBoundary](strategy/episode/trait.EpisodeBoundary.html).
+    pub episodes: u64,
+    /// The highest reward received.
+    pub max_reward: f64,
+    /// The lowest reward received.
+    pub min_reward: f64,
+}
+
+#[cfg(feature = "std")]
+impl TrainingStats {
+    fn new() -> TrainingStats {
+        TrainingStats {
+            steps: 0,
+            total_reward: 0.,
+            episodes: 0,
+            max_reward: f64::NEG_INFINITY,
+            min_reward: f64::INFINITY,
+        }
+    }
+
+    fn record(&mut self, reward: f64) {
+        self.steps += 1;
+        self.total_reward += reward;
+        self.max_reward = self.max_reward.max(reward);
+        self.min_reward = self.min_reward.min(reward);
+    }
+
+    /// Adds the statistics of another training run to these statistics.
+    #[cfg(feature = "rayon")]
+    fn merge(&mut self, other: &TrainingStats) {
+        self.steps += other.steps;
+        self.total_reward += other.total_reward;
+        self.episodes += other.episodes;
+        self.max_reward = self.max_reward.max(other.max_reward);
+        self.min_reward = self.min_reward.min(other.min_reward);
+    }
+}
+
+/// A summary of a training run, as returned by [AgentTrainer::train_reporting].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg(feature = "std")]
+pub struct TrainReport {
+    /// The number of value updates that were performed.
+    pub iterations: u64,
+    /// The number of episodes that ended in a terminal state (a sink state, or a `State` the agent
+    /// marks as [terminal](mdp/trait.Agent.html#method.is_terminal)), or at an
+    /// [EpisodeBoundary](strategy/episode/trait.EpisodeBoundary.html).
+    pub episodes: u64,
+    /// The time training took.
+    pub elapsed: Duration,
+}
+
+/// The result of a greedy rollout, as returned by [AgentTrainer::rollout].
+#[derive(Clone)]
+#[cfg(feature = "std")]
+pub struct RolloutResult<S>
+where
+    S: State,
+{
+    /// The visited states, starting with the state the rollout started from.
+    pub states: Vec<S>,
+    /// The actions taken, where `actions[i]` was taken from `states[i]`.
+    pub actions: Vec<S::A>,
+    /// The sum of all rewards received.
+    pub total_reward: f64,
+}
+
+/// Learned values ordered by `State` and by action, as returned by
+/// [AgentTrainer::export_learned_values_sorted].
+#[cfg(feature = "std")]
+pub type SortedValues<S> = Vec<(S, Vec<(<S as State>::A, f64)>)>;
+
+/// Decides how learned values of multiple trainers are combined when they learned a value for the
+/// same action in the same `State`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "std")]
+pub enum MergeStrategy {
+    /// Take the average of the learned values.
+    Average,
+    /// Take the highest of the learned values.
+    Max,
+    /// Take the value learned last, i.e. the value of the other trainer in
+    /// [AgentTrainer::merge], the imported value in [AgentTrainer::import_state_merge], or the
+    /// value of the last worker in parallel training.
+    Overwrite,
+}
+
+/// Merges the given learned values into a single table. Values for actions learned by only one of
+/// the tables are kept as-is.
+#[cfg(feature = "rayon")]
+fn merge_tables<S: State>(
+    tables: Vec<HashMap<S, HashMap<S::A, f64>>>,
+    strategy: MergeStrategy,
+) -> HashMap<S, HashMap<S::A, f64>> {
+    let mut merged: HashMap<S, HashMap<S::A, (f64, u32)>> = HashMap::new();
+    for table in tables {
+        for (state, actions) in table {
+            let merged_actions = merged.entry(state).or_default();
+            for (action, value) in actions {
+                let (acc, n) = merged_actions.entry(action).or_insert((value, 0));
+                if *n > 0 {
+                    *acc = match strategy {
+                        MergeStrategy::Average => *acc + value,
+                        MergeStrategy::Max => acc.max(value),
+                        MergeStrategy::Overwrite => value,
+                    };
+                }
+                *n += 1;
+            }
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(state, actions)| {
+            let actions = actions
+                .into_iter()
+                .map(|(action, (acc, n))| match strategy {
+                    MergeStrategy::Average => (action, acc / n as f64),
+                    MergeStrategy::Max | MergeStrategy::Overwrite => (action, acc),
+                })
+                .collect();
+            (state, actions)
+        })
+        .collect()
+}
+
+/// Compares two learned values. NaN is treated as lower than any other value, so that a NaN value
+/// is never preferred over a proper one.
+pub(crate) fn compare_values(v1: f64, v2: f64) -> core::cmp::Ordering {
+    match (v1.is_nan(), v2.is_nan()) {
+        (true, true) => core::cmp::Ordering::Equal,
+        (true, false) => core::cmp::Ordering::Less,
+        (false, true) => core::cmp::Ordering::Greater,
+        (false, false) => v1.partial_cmp(&v2).unwrap(),
+    }
+}
+
+/// Returns `true` if the current `State` of the given agent is terminal: when it is a sink state
+/// (a `State` without actions), or when the agent marks it as terminal.
+#[cfg(feature = "std")]
+pub(crate) fn is_terminal<S: State, A: Agent<S> + ?Sized>(agent: &A) -> bool {
+    agent.is_terminal() || agent.current_state().actions().is_empty()
+}
+
+/// Returns the reward for the action that the given agent just took in `state`: the reward the
+/// agent observed, or else the reward of the transition to its current `State`.
+#[cfg(feature = "std")]
+pub(crate) fn step_reward<S: State, A: Agent<S> + ?Sized>(
+    agent: &A,
+    state: &S,
+    action: &S::A,
+) -> f64 {
+    agent
+        .observe_reward()
+        .unwrap_or_else(|| state.transition_reward(action, agent.current_state()))
+}
+
+/// The trace below which an action is no longer updated by [SarsaLambda] learning.
+#[cfg(feature = "std")]
+const MIN_TRACE: f64 = 1e-6;
+
+/// A callback observing a training step, given a read-only view of the trainer after the value of
+/// the step was updated. See [AgentTrainer::train_inspecting].
+#[cfg(feature = "std")]
+pub type StepCallback<'a, S, T> = dyn FnMut(&AgentTrainer<S, T>, &TrainingStep<S>) + 'a;
+
+/// The termination strategy used while training in episodes, which ends an episode when either
+/// the termination strategy or the episode boundary decides so, and remembers which one did.
+#[cfg(feature = "std")]
+struct EpisodeTermination<'a, S: State> {
+    termination_strategy: &'a mut dyn TerminationStrategy<S>,
+    episode_boundary: &'a mut dyn EpisodeBoundary<S>,
+    stopped: bool,
+}
+
+#[cfg(feature = "std")]
+impl<S: State> TerminationStrategy<S> for EpisodeTermination<'_, S> {
+    fn should_stop(&mut self, state: &S) -> bool {
+        self.stopped = self.termination_strategy.should_stop(state);
+        self.stopped || self.episode_boundary.should_end_episode(state)
+    }
+
+    fn should_stop_full(
+        &mut self,
+        state: &S,
+        last_action: Option<&S::A>,
+        last_reward: f64,
+    ) -> bool {
+        self.stopped = self
+            .termination_strategy
+            .should_stop_full(state, last_action, last_reward);
+        self.stopped || self.episode_boundary.should_end_episode(state)
+    }
+}
+
+// Stops at the end of a step that arrived at a terminal state, as recorded by a training callback.
+#[cfg(feature = "std")]
+struct TerminalStates<'a>(&'a Cell<bool>);
+
+#[cfg(feature = "std")]
+impl<S: State> TerminationStrategy<S> for TerminalStates<'_> {
+    fn should_stop(&mut self, _: &S) -> bool {
+        self.0.get()
+    }
+}
+
+/// The termination strategy used while training with a cancellation flag, which ends training when
+/// the flag is set or the termination strategy decides so.
+#[cfg(feature = "std")]
+struct Cancellable<'a, S: State> {
+    termination_strategy: &'a mut dyn TerminationStrategy<S>,
+    cancel: &'a AtomicBool,
+}
+
+#[cfg(feature = "std")]
+impl<S: State> TerminationStrategy<S> for Cancellable<'_, S> {
+    fn should_stop(&mut self, state: &S) -> bool {
+        self.cancel.load(Ordering::Relaxed) || self.termination_strategy.should_stop(state)
+    }
+
+    fn should_stop_full(
+        &mut self,
+        state: &S,
+        last_action: Option<&S::A>,
+        last_reward: f64,
+    ) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+            || self
+                .termination_strategy
+                .should_stop_full(state, last_action, last_reward)
+    }
+}
 
 /// An `AgentTrainer` can be trained for using a certain [Agent](mdp/trait.Agent.html). After
 /// training, the `AgentTrainer` contains learned knowledge about the process, and can be queried
 /// for this. For example, you can ask the `AgentTrainer` the expected values of all possible
 /// actions in a given state.
-pub struct AgentTrainer<S>
+///
+/// The learned values are stored in a [QTable], which is a nested `HashMap` by default.
+#[cfg(feature = "std")]
+pub struct AgentTrainer<S, T = HashMap<S, HashMap<<S as State>::A, f64>>>
 where
     S: State,
 {
-    q: HashMap<S, HashMap<S::A, f64>>,
+    q: T,
+    stats: Option<TrainingStats>,
+    visits: Option<HashMap<S, u64>>,
+    normalizer: Option<RewardNormalizer>,
+    shaper: Option<Arc<dyn RewardShaper<S> + Send + Sync>>,
+    similarity: Option<Arc<dyn StateSimilarity<S> + Send + Sync>>,
+    reward_clip: Option<(f64, f64)>,
+    phantom: std::marker::PhantomData<S>,
 }
 
+#[cfg(feature = "std")]
 impl<S> AgentTrainer<S>
 where
     S: State,
 {
     pub fn new() -> AgentTrainer<S> {
-        AgentTrainer { q: HashMap::new() }
+        AgentTrainer {
+            q: HashMap::new(),
+            stats: None,
+            visits: None,
+            normalizer: None,
+            shaper: None,
+            similarity: None,
+            reward_clip: None,
+            phantom: std::marker::PhantomData,
+        }
     }
 
     /// Fetches the learned values for the given state, by `Action`, or `None` if no value was
@@ -120,10 +510,16 @@ where
         self.q.get(state)
     }
 
-    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
-    /// value was learned.
-    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<f64> {
-        self.q.get(state).and_then(|m| m.get(action).copied())
+    /// Fetches the learned values for each of the given states, by `Action`, or `None` for the
+    /// states for which no value was learned.
+    pub fn expected_values_batch<'a>(
+        &'a self,
+        states: impl IntoIterator<Item = &'a S>,
+    ) -> Vec<Option<&'a HashMap<S::A, f64>>> {
+        states
+            .into_iter()
+            .map(|state| self.expected_values(state))
+            .collect()
     }
 
     /// Returns a clone of the entire learned state to be saved or used elsewhere.
@@ -140,15 +536,1580 @@ where
     pub fn import_state(&mut self, q: HashMap<S, HashMap<S::A, f64>>) {
         self.q = q;
     }
+}
+
+#[cfg(feature = "std")]
+impl<S, T> AgentTrainer<S, T>
+where
+    S: State,
+    T: QTable<S>,
+{
+    /// Creates an `AgentTrainer` that stores its learned values in the given [QTable], instead of
+    /// the default nested `HashMap`.
+    pub fn with_table(table: T) -> AgentTrainer<S, T> {
+        AgentTrainer {
+            q: table,
+            stats: None,
+            visits: None,
+            normalizer: None,
+            shaper: None,
+            similarity: None,
+            reward_clip: None,
+            phantom: std::marker::PhantomData,
+        }
+    }
 
-    /// Returns the best action for the given `State`, or `None` if no values were learned.
+    /// Enables reward normalization: during training, rewards are standardized using the running
+    /// mean and variance of all rewards observed so far before the learned values are updated,
+    /// see [RewardNormalizer]. The learned values are then on the scale of the standardized
+    /// rewards. Reward normalization is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new().with_reward_normalization();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(100000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let test_state = MyState { x: 10, y: 9 };
+    /// let go_up = MyAction { dx: 0, dy: -1 };
+    /// let go_down = MyAction { dx: 0, dy: 1 };
+    /// assert!(trainer.expected_value(&test_state, &go_down)
+    ///     > trainer.expected_value(&test_state, &go_up));
+    /// for (x, y) in [(5, 5), (15, 15), (5, 15), (15, 5)] {
+    ///     let state = MyState { x, y };
+    ///     let mut agent = MyAgent { state: state.clone() };
+    ///     agent.take_action(&trainer.best_action(&state).unwrap());
+    ///     assert!(agent.state.reward() > state.reward());
+    /// }
+    /// ```
+    pub fn with_reward_normalization(mut self) -> Self {
+        self.normalizer = Some(RewardNormalizer::new());
+        self
+    }
+
+    /// Enables reward shaping: during training, rewards are passed through the given
+    /// [RewardShaper] before the learned values are updated. The received rewards, as recorded in
+    /// the [TrainingStats], are not shaped. Reward shaping is disabled by default.
+    pub fn with_reward_shaping(
+        mut self,
+        shaper: impl RewardShaper<S> + Send + Sync + 'static,
+    ) -> Self {
+        self.shaper = Some(Arc::new(shaper));
+        self
+    }
+
+    /// Sets the [StateSimilarity] used by
+    /// [expected_value_generalized](AgentTrainer::expected_value_generalized) to borrow values
+    /// for states without learned values. Training and all other queries are not affected.
+    pub fn with_state_similarity(
+        mut self,
+        similarity: impl StateSimilarity<S> + Send + Sync + 'static,
+    ) -> Self {
+        self.similarity = Some(Arc::new(similarity));
+        self
+    }
+
+    /// Enables reward clipping: during training, rewards are clipped to the range from `min` to
+    /// `max` before they are shaped or normalized, and before the learned values are updated.
+    /// This keeps rare, large rewards from destabilizing learning. The received rewards, as
+    /// recorded in the [TrainingStats], are not clipped. Reward clipping is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new().with_reward_clip(-1., 1.);
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(1., 0., 0.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// // Without discounting and with a learning rate of 1, the values are the clipped rewards.
+    /// assert!(trainer.iter_values().all(|(_, _, value)| (-1. ..=1.).contains(&value)));
+    /// assert_eq!(trainer.expected_value(&MyState(3), &1), Some(-1.));
+    /// assert_eq!(trainer.last_stats().unwrap().min_reward, -5.);
+    /// ```
+    pub fn with_reward_clip(mut self, min: f64, max: f64) -> Self {
+        self.reward_clip = Some((min, max));
+        self
+    }
+
+    /// Starts configuring a training run of this [AgentTrainer], see [TrainConfig].
+    pub fn configure(&mut self) -> TrainConfig<'_, S, T> {
+        TrainConfig::new(self)
+    }
+
+    /// Removes all learned values, so learning restarts from scratch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    /// assert!(trainer.expected_values(&MyState { x: 1, y: 0 }).is_some());
+    ///
+    /// trainer.reset_state(&MyState { x: 1, y: 0 });
+    /// assert!(trainer.expected_values(&MyState { x: 1, y: 0 }).is_none());
+    /// assert!(trainer.expected_values(&MyState { x: 0, y: 1 }).is_some());
+    ///
+    /// trainer.reset();
+    /// for x in 0..21 {
+    ///     for y in 0..21 {
+    ///         assert!(trainer.expected_values(&MyState { x, y }).is_none());
+    ///     }
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.q.clear();
+    }
+
+    /// Removes the learned values for all actions in the given `State`.
+    pub fn reset_state(&mut self, state: &S) {
+        self.q.remove_state(state);
+    }
+
+    /// Removes the learned values for all actions in every `State` for which `predicate` returns
+    /// `true`, given the `State` and its learned values, and returns the number of removed
+    /// `State`s. Their visit counts are removed as well. This bounds the memory used by a trainer
+    /// that keeps learning online, where many states are visited only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(100000),
+    ///     &RandomExploration::new(),
+    /// );
+    /// let best_values = trainer.learned_values().clone();
+    /// let best_value = |state| best_values[state].values().cloned().fold(f64::NEG_INFINITY, f64::max);
+    ///
+    /// // Prune all states whose best value is below -5.
+    /// let pruned = trainer.prune(|_, values| values.values().all(|&value| value < -5.));
+    ///
+    /// assert!(pruned > 0);
+    /// assert_eq!(trainer.num_states(), best_values.len() - pruned);
+    /// for state in best_values.keys() {
+    ///     assert_eq!(trainer.has_state(state), best_value(state) >= -5.);
+    /// }
+    /// ```
+    pub fn prune(&mut self, predicate: impl Fn(&S, &HashMap<S::A, f64>) -> bool) -> usize {
+        let mut visits = self.visits.take();
+        let pruned = self.prune_states(predicate);
+        if let Some(visits) = &mut visits {
+            for state in &pruned {
+                visits.remove(state);
+            }
+        }
+        self.visits = visits;
+        pruned.len()
+    }
+
+    /// Removes the learned values for all actions in every `State` that was visited fewer than
+    /// `min_visits` times since [enable_visit_tracking](AgentTrainer::enable_visit_tracking) was
+    /// called, like [prune](AgentTrainer::prune), and returns the number of removed `State`s.
+    /// Does nothing if visits are not tracked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.enable_visit_tracking();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let num_states = trainer.num_states();
+    /// let pruned = trainer.prune_unvisited(10);
+    /// assert_eq!(trainer.num_states(), num_states - pruned);
+    /// assert!(trainer.learned_values().keys().all(|state| trainer.visit_count(state) >= 10));
+    /// ```
+    pub fn prune_unvisited(&mut self, min_visits: u64) -> usize {
+        let Some(mut visits) = self.visits.take() else {
+            return 0;
+        };
+        let pruned =
+            self.prune_states(|state, _| visits.get(state).copied().unwrap_or(0) < min_visits);
+        for state in &pruned {
+            visits.remove(state);
+        }
+        self.visits = Some(visits);
+        pruned.len()
+    }
+
+    /// Removes the learned values of every `State` for which `predicate` returns `true`, and
+    /// returns the removed `State`s.
+    fn prune_states(&mut self, predicate: impl Fn(&S, &HashMap<S::A, f64>) -> bool) -> Vec<S> {
+        let states: HashSet<&S> = self.q.iter().map(|(state, _, _)| state).collect();
+        let pruned: Vec<S> = states
+            .into_iter()
+            .filter(|&state| {
+                self.q
+                    .action_values(state)
+                    .is_some_and(|values| predicate(state, &values))
+            })
+            .cloned()
+            .collect();
+        for state in &pruned {
+            self.q.remove_state(state);
+        }
+        pruned
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
+    /// value was learned.
+    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<f64> {
+        self.q.get(state, action)
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State` like
+    /// [expected_value](AgentTrainer::expected_value). If no value was learned and a
+    /// [StateSimilarity] is set, returns the average of the learned values of the neighbors of the
+    /// `State`, weighted by their similarity, or `None` if no neighbor has a learned value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::similarity::StateSimilarity;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The cells to the left and to the right are equally similar.
+    /// struct Adjacent;
+    ///
+    /// impl StateSimilarity<MyState> for Adjacent {
+    ///     fn neighbors(&self, state: &MyState) -> Vec<(MyState, f64)> {
+    ///         vec![(MyState(state.0 - 1), 1.), (MyState(state.0 + 1), 1.)]
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new().with_state_similarity(Adjacent);
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// // Learned states are looked up exactly.
+    /// let value = trainer.expected_value(&MyState(0), &1);
+    /// assert_eq!(trainer.expected_value_generalized(&MyState(0), &1), value);
+    /// // The agent never reached 6, which borrows the value of its only learned neighbor 5.
+    /// assert_eq!(trainer.expected_value(&MyState(6), &-1), None);
+    /// let value = trainer.expected_value(&MyState(5), &-1);
+    /// assert_eq!(trainer.expected_value_generalized(&MyState(6), &-1), value);
+    /// // No neighbor of 10 was learned.
+    /// assert_eq!(trainer.expected_value_generalized(&MyState(10), &-1), None);
+    /// ```
+    pub fn expected_value_generalized(&self, state: &S, action: &S::A) -> Option<f64> {
+        let value = self.expected_value(state, action);
+        let similarity = match &self.similarity {
+            Some(similarity) if value.is_none() => similarity,
+            _ => return value,
+        };
+        let (weighted_sum, total_weight) = similarity
+            .neighbors(state)
+            .iter()
+            .filter_map(|(neighbor, weight)| {
+                self.expected_value(neighbor, action)
+                    .map(|value| (value, *weight))
+            })
+            .fold((0., 0.), |(sum, total), (value, weight)| {
+                (sum + value * weight, total + weight)
+            });
+        if total_weight > 0. {
+            Some(weighted_sum / total_weight)
+        } else {
+            None
+        }
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State`, or `default` if no
+    /// value was learned.
+    ///
+    /// Note that `default` is only used for this query and is never stored. It is unrelated to the
+    /// `initial_value` of [QLearning](strategy/learn/struct.QLearning.html), which is the value
+    /// that is stored the first time an action is taken during training.
+    pub fn expected_value_or(&self, state: &S, action: &S::A, default: f64) -> f64 {
+        self.expected_value(state, action).unwrap_or(default)
+    }
+
+    /// Iterates over all learned (state, action, value) triples, without cloning the learned
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let pairs: usize = trainer.learned_values().values().map(|actions| actions.len()).sum();
+    /// assert_eq!(trainer.iter_values().count(), pairs);
+    /// assert!(trainer.iter_values().all(|(state, action, value)| {
+    ///     trainer.expected_value(state, action) == Some(value)
+    /// }));
+    /// ```
+    pub fn iter_values(&self) -> impl Iterator<Item = (&S, &S::A, f64)> + '_ {
+        self.q.iter()
+    }
+
+    /// Returns a copy of all learned values, ordered by `State` and by action. Unlike the
+    /// `HashMap` returned by [export_learned_values](AgentTrainer::export_learned_values), the
+    /// order is deterministic, which makes the output suitable for diffing and stable
+    /// serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let export = || {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = MyAgent { state: MyState(0) };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(1000),
+    ///         &RandomExploration::with_seed(7),
+    ///     );
+    ///     format!("{:?}", trainer.export_learned_values_sorted())
+    /// };
+    /// assert_eq!(export().as_bytes(), export().as_bytes());
+    /// ```
+    pub fn export_learned_values_sorted(&self) -> SortedValues<S>
+    where
+        S: Ord,
+        S::A: Ord,
+    {
+        let mut values: BTreeMap<&S, Vec<(S::A, f64)>> = BTreeMap::new();
+        for (state, action, value) in self.q.iter() {
+            values
+                .entry(state)
+                .or_default()
+                .push((action.clone(), value));
+        }
+        values
+            .into_iter()
+            .map(|(state, mut actions)| {
+                actions.sort_by(|(a1, _), (a2, _)| a1.cmp(a2));
+                (state.clone(), actions)
+            })
+            .collect()
+    }
+
+    /// Returns all learned values as `(state, action, value)` entries, sorted by state and then by
+    /// action, as needed to create a [PolicyTable](policy/struct.PolicyTable.html) that can be used
+    /// without `std`.
+    pub fn export_policy_entries(&self) -> Vec<(S, S::A, f64)>
+    where
+        S: Ord,
+        S::A: Ord,
+    {
+        self.export_learned_values_sorted()
+            .into_iter()
+            .flat_map(|(state, actions)| {
+                actions
+                    .into_iter()
+                    .map(move |(action, value)| (state.clone(), action, value))
+            })
+            .collect()
+    }
+
+    /// Returns the best action of every learned `State`, see [best_action](AgentTrainer::best_action).
+    /// This is all that is needed to follow the learned policy, and is much smaller than the
+    /// learned values. It can be loaded into a
+    /// [GreedyPolicy](policy/struct.GreedyPolicy.html) using
+    /// [GreedyPolicy::import_policy](policy/struct.GreedyPolicy.html#method.import_policy).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::policy::GreedyPolicy;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let exported = trainer.export_policy();
+    /// assert_eq!(exported.len(), trainer.num_states());
+    /// let policy = GreedyPolicy::import_policy(exported);
+    /// for (state, _, _) in trainer.iter_values() {
+    ///     assert_eq!(policy.best_action(state), trainer.best_action(state).as_ref());
+    /// }
+    /// assert_eq!(policy.best_action(&MyState(100)), None);
+    /// ```
+    pub fn export_policy(&self) -> HashMap<S, S::A> {
+        let mut policy = HashMap::new();
+        for (state, _, _) in self.q.iter() {
+            if !policy.contains_key(state) {
+                if let Some((action, _)) = self.q.best(state) {
+                    policy.insert(state.clone(), action);
+                }
+            }
+        }
+        policy
+    }
+
+    /// Pre-populates the learned values of all actions of the given states with estimates from a
+    /// heuristic function, giving the training an informed starting point. This overwrites any
+    /// values learned before for these states. Like any learned value, the seeded values are
+    /// gradually replaced during training, at the pace of the learning rate of the
+    /// [LearningStrategy](strategy/learn/trait.LearningStrategy.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let grid = || (0..21).flat_map(|x| (0..21).map(move |y| MyState { x, y }));
+    /// // Counts the states in which the best action moves closer to the target.
+    /// let good_moves = |trainer: &AgentTrainer<MyState>| {
+    ///     grid()
+    ///         .filter(|state| match trainer.best_action(state) {
+    ///             Some(action) => {
+    ///                 let mut agent = MyAgent { state: state.clone() };
+    ///                 agent.take_action(&action);
+    ///                 agent.state.reward() > state.reward()
+    ///             }
+    ///             None => false,
+    ///         })
+    ///         .count()
+    /// };
+    ///
+    /// let mut cold = AgentTrainer::new();
+    /// let mut warm = AgentTrainer::new();
+    /// // Estimate the value of an action by the reward of the state it leads to.
+    /// warm.seed_values(
+    ///     |state, action| {
+    ///         let mut agent = MyAgent { state: state.clone() };
+    ///         agent.take_action(action);
+    ///         agent.state.reward()
+    ///     },
+    ///     grid(),
+    /// );
+    /// for trainer in [&mut cold, &mut warm] {
+    ///     let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(1000),
+    ///         &RandomExploration::new(),
+    ///     );
+    /// }
+    /// assert!(good_moves(&warm) > good_moves(&cold));
+    /// ```
+    pub fn seed_values<F, I>(&mut self, f: F, states: I)
+    where
+        F: Fn(&S, &S::A) -> f64,
+        I: IntoIterator<Item = S>,
+    {
+        for state in states {
+            for action in state.actions() {
+                let value = f(&state, &action);
+                self.q.insert(state.clone(), action, value);
+            }
+        }
+    }
+
+    /// Merges the values learned by another trainer into this one. When both trainers learned a
+    /// value for the same action in the same `State`, the values are combined using the given
+    /// [MergeStrategy]. Values learned by only one of the trainers are kept as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState { x: i32, y: i32 }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction { dx: i32, dy: i32 }
+    /// # impl State for MyState {
+    /// #     type A = MyAction;
+    /// #     fn reward(&self) -> f64 {
+    /// #         -((((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt())
+    /// #     }
+    /// #     fn actions(&self) -> Vec<MyAction> {
+    /// #         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+    /// #              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 }]
+    /// #     }
+    /// # }
+    /// // An agent that is confined to the square region between `min` and `max`.
+    /// struct MyAgent { state: MyState, min: i32, max: i32 }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState {
+    /// #             x: (self.state.x + action.dx).clamp(self.min, self.max),
+    /// #             y: (self.state.y + action.dy).clamp(self.min, self.max),
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rurel::{AgentTrainer, MergeStrategy};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let train = |min, max| {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = MyAgent { state: MyState { x: min, y: min }, min, max };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(10000),
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     trainer
+    /// };
+    /// let low = train(0, 10);
+    /// let high = train(10, 20);
+    ///
+    /// let mut merged = AgentTrainer::new();
+    /// merged.merge(&low, MergeStrategy::Overwrite);
+    /// merged.merge(&high, MergeStrategy::Max);
+    ///
+    /// for (state, action, value) in low.iter_values().chain(high.iter_values()) {
+    ///     assert!(merged.expected_value(state, action).unwrap() >= value);
+    /// }
+    /// let only_low = MyState { x: 0, y: 0 };
+    /// assert!(merged.expected_values(&only_low) == low.expected_values(&only_low));
+    /// assert!(!high.has_state(&only_low));
+    /// ```
+    pub fn merge<U: QTable<S>>(&mut self, other: &AgentTrainer<S, U>, strategy: MergeStrategy) {
+        for (state, action, value) in other.iter_values() {
+            self.merge_value(state.clone(), action.clone(), value, strategy);
+        }
+    }
+
+    /// Imports the given learned values, merging them into the values learned so far instead of
+    /// replacing them like [import_state](AgentTrainer::import_state). This allows continuing
+    /// training on top of a pretrained subset of the values. Values of actions that were only
+    /// imported are added, and values that were only learned are kept. When a value was both
+    /// learned and imported, the values are combined using the given [MergeStrategy], where
+    /// [MergeStrategy::Overwrite] takes the imported value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::{AgentTrainer, MergeStrategy};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    /// let learned = trainer.export_learned_values();
+    ///
+    /// // Pretrained values of a state that was learned, and of a state that was not.
+    /// let pretrained = HashMap::from([
+    ///     (MyState(5), HashMap::from([(-1, 10.)])),
+    ///     (MyState(6), HashMap::from([(-1, 20.), (1, 30.)])),
+    /// ]);
+    /// trainer.import_state_merge(pretrained, MergeStrategy::Overwrite);
+    ///
+    /// assert_eq!(trainer.expected_value(&MyState(5), &-1), Some(10.));
+    /// assert_eq!(trainer.expected_value(&MyState(5), &1), learned[&MyState(5)].get(&1).copied());
+    /// assert_eq!(trainer.expected_value(&MyState(6), &1), Some(30.));
+    /// for x in -5..5 {
+    ///     assert!(trainer.expected_values(&MyState(x)) == learned.get(&MyState(x)));
+    /// }
+    /// ```
+    pub fn import_state_merge(
+        &mut self,
+        q: HashMap<S, HashMap<S::A, f64>>,
+        strategy: MergeStrategy,
+    ) {
+        for (state, actions) in q {
+            for (action, value) in actions {
+                self.merge_value(state.clone(), action, value, strategy);
+            }
+        }
+    }
+
+    /// Merges the given value of `action` in `state` into the learned value, if any.
+    fn merge_value(&mut self, state: S, action: S::A, value: f64, strategy: MergeStrategy) {
+        let value = match self.q.get(&state, &action) {
+            None => value,
+            Some(old) => match strategy {
+                MergeStrategy::Average => (old + value) / 2.,
+                MergeStrategy::Max => old.max(value),
+                MergeStrategy::Overwrite => value,
+            },
+        };
+        self.q.insert(state, action, value);
+    }
+
+    /// Returns the number of `State`s for which at least one value was learned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// assert_eq!(trainer.num_states(), 0);
+    /// assert!(!trainer.has_state(&MyState { x: 0, y: 0 }));
+    ///
+    /// let mut counts = vec![];
+    /// for _ in 0..3 {
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(1000),
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     counts.push((trainer.num_states(), trainer.num_state_actions()));
+    /// }
+    /// assert!(counts[0] < counts[1] && counts[1] < counts[2]);
+    /// assert!(trainer.num_state_actions() <= 4 * trainer.num_states());
+    /// assert!(trainer.has_state(&MyState { x: 0, y: 0 }));
+    /// ```
+    pub fn num_states(&self) -> usize {
+        self.q.num_states()
+    }
+
+    /// Returns the number of (state, action) pairs for which a value was learned.
+    pub fn num_state_actions(&self) -> usize {
+        self.q.num_state_actions()
+    }
+
+    /// Returns whether a value was learned for at least one action in the given `State`.
+    pub fn has_state(&self, state: &S) -> bool {
+        self.q.contains_state(state)
+    }
+
+    /// Returns the best action of all actions of the given `State`, where actions without a
+    /// learned value are treated as having `unseen_value`. Choosing a high `unseen_value` gives
+    /// optimistic initialization, which encourages trying actions that were never taken, without
+    /// mutating the learned values. Returns `None` if the `State` has no actions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use rurel::fixtures::line::MyState;
+    /// use rurel::AgentTrainer;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(HashMap::from([(MyState(0), HashMap::from([(-1, -1.0)]))]));
+    ///
+    /// assert_eq!(trainer.best_action(&MyState(0)), Some(-1));
+    /// assert_eq!(trainer.best_action_optimistic(&MyState(0), 0.0), Some(1));
+    /// assert_eq!(trainer.best_action_optimistic(&MyState(0), -2.0), Some(-1));
+    /// ```
+    pub fn best_action_optimistic(&self, state: &S, unseen_value: f64) -> Option<S::A> {
+        state
+            .actions()
+            .into_iter()
+            .map(|action| {
+                let value = self.expected_value_or(state, &action, unseen_value);
+                (action, value)
+            })
+            .max_by(|(_, v1), (_, v2)| compare_values(*v1, *v2))
+            .map(|(action, _)| action)
+    }
+
+    /// Returns the [TrainingStats] of the last training run, or `None` if this trainer was never
+    /// trained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct CoinState { balance: i32 }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct CoinAction { bet: i32 }
+    /// # impl State for CoinState {
+    /// #     type A = CoinAction;
+    /// #     fn reward(&self) -> f64 { if self.balance >= 100 { 1.0 } else { 0.0 } }
+    /// #     fn actions(&self) -> Vec<CoinAction> {
+    /// #         let max_bet = self.balance.min(100 - self.balance).max(0);
+    /// #         (1..=max_bet).map(|bet| CoinAction { bet }).collect()
+    /// #     }
+    /// # }
+    /// # struct CoinAgent { state: CoinState }
+    /// # impl Agent<CoinState> for CoinAgent {
+    /// #     fn current_state(&self) -> &CoinState { &self.state }
+    /// #     fn take_action(&mut self, action: &CoinAction) {
+    /// #         let won = rand::random::<u8>() <= 100;
+    /// #         let bet = if won { action.bet } else { -action.bet };
+    /// #         self.state = CoinState { balance: self.state.balance + bet };
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::SinkStates;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut episodes = 0;
+    /// for trial in 0..100 {
+    ///     let mut agent = CoinAgent { state: CoinState { balance: 1 + trial % 98 } };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 1.0, 0.0),
+    ///         &mut SinkStates {},
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     episodes += trainer.last_stats().unwrap().episodes;
+    /// }
+    /// assert_eq!(episodes, 100);
+    /// ```
+    pub fn last_stats(&self) -> Option<&TrainingStats> {
+        self.stats.as_ref()
+    }
+
+    /// Starts counting how often every `State` is visited during training. Counting is disabled
+    /// by default, so training does not pay for it when it is not used. Enabling it again resets
+    /// the counts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.enable_visit_tracking();
+    /// let start = MyState { x: 0, y: 0 };
+    /// // Train 100 short episodes, all from the same start state.
+    /// for _ in 0..100 {
+    ///     let mut agent = MyAgent { state: start.clone() };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(10),
+    ///         &RandomExploration::new(),
+    ///     );
+    /// }
+    ///
+    /// let most_visited = trainer.most_visited(usize::MAX);
+    /// assert_eq!(most_visited.iter().map(|(_, count)| count).sum::<u64>(), 1000);
+    /// assert!(most_visited.windows(2).all(|w| w[0].1 >= w[1].1));
+    /// assert!(most_visited[0].0 == start);
+    /// assert!(trainer.visit_count(&start) >= 100);
+    /// assert_eq!(trainer.visit_count(&MyState { x: 10, y: 100 }), 0);
+    /// ```
+    pub fn enable_visit_tracking(&mut self) {
+        self.visits = Some(HashMap::new());
+    }
+
+    /// Returns how often the given `State` was visited during training since
+    /// [enable_visit_tracking](AgentTrainer::enable_visit_tracking) was called, or 0 if visits
+    /// are not tracked.
+    pub fn visit_count(&self, state: &S) -> u64 {
+        self.visits
+            .as_ref()
+            .and_then(|visits| visits.get(state).copied())
+            .unwrap_or(0)
+    }
+
+    /// Returns the `n` most visited states together with their visit counts, most visited first.
+    /// Returns an empty `Vec` if visits are not tracked.
+    pub fn most_visited(&self, n: usize) -> Vec<(S, u64)> {
+        let mut visits: Vec<(S, u64)> = self
+            .visits
+            .iter()
+            .flatten()
+            .map(|(state, &count)| (state.clone(), count))
+            .collect();
+        visits.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        visits.truncate(n);
+        visits
+    }
+
+    /// Returns the best action for the given `State`, or `None` if no values were learned. Actions
+    /// with a NaN value are only returned when all values of the `State` are NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &i32) {
+    /// #         self.state = MyState((self.state.0 + action).clamp(-5, 5))
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A badly scaled reward, which is NaN at 5.
+    /// impl State for MyState {
+    ///     type A = i32;
+    ///     fn reward(&self) -> f64 { if self.0 == 5 { f64::NAN } else { self.0 as f64 } }
+    ///     fn actions(&self) -> Vec<i32> { vec![-1, 1] }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// assert!(trainer.expected_value(&MyState(4), &1).unwrap().is_nan());
+    /// assert_eq!(trainer.best_action(&MyState(4)), Some(-1));
+    /// let (_, value) = trainer.best_action_with_value(&MyState(3)).unwrap();
+    /// assert!(value.is_finite());
+    /// ```
     pub fn best_action(&self, state: &S) -> Option<S::A> {
-        self.expected_values(state)
-            .and_then(|m| {
-                m.iter()
-                    .max_by(|&(_, v1), &(_, v2)| v1.partial_cmp(v2).unwrap())
+        self.best_action_with_value(state).map(|t| t.0)
+    }
+
+    /// Returns the best action for the given `State` together with its learned value, or `None`
+    /// if no values were learned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let state = MyState(3);
+    /// let (action, value) = trainer.best_action_with_value(&state).unwrap();
+    /// assert_eq!(trainer.expected_value(&state, &action), Some(value));
+    /// assert!(trainer.best_action_with_value(&MyState(100)).is_none());
+    /// ```
+    pub fn best_action_with_value(&self, state: &S) -> Option<(S::A, f64)> {
+        self.q.best(state)
+    }
+
+    /// Returns one of the best actions for the given `State`, or `None` if no values were
+    /// learned. Unlike [best_action](AgentTrainer::best_action), which always returns the same
+    /// action when several actions have the same value, this picks uniformly at random among all
+    /// actions whose value is within `epsilon` of the best value. This avoids a systematic bias
+    /// towards one of the actions in symmetric states. Actions with a NaN value are only picked
+    /// when all values of the `State` are NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::State;
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl State for MyState {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+    /// #     fn actions(&self) -> Vec<i32> { vec![-2, -1, 1, 2] }
+    /// # }
+    /// use std::collections::HashMap;
+    ///
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rurel::AgentTrainer;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(HashMap::from([(
+    ///     MyState(0),
+    ///     HashMap::from([(-2, 1.0), (-1, 1.0), (1, 1.0 - 1e-12), (2, 0.5)]),
+    /// )]));
+    ///
+    /// let mut rng = StdRng::seed_from_u64(0);
+    /// let mut counts = HashMap::new();
+    /// for _ in 0..3000 {
+    ///     let action = trainer.best_action_random_tiebreak(&MyState(0), 1e-9, &mut rng).unwrap();
+    ///     *counts.entry(action).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.get(&2), None);
+    /// for action in [-2, -1, 1] {
+    ///     assert!((800..1200).contains(&counts[&action]));
+    /// }
+    /// assert!(trainer.best_action_random_tiebreak(&MyState(1), 1e-9, &mut rng).is_none());
+    /// ```
+    pub fn best_action_random_tiebreak(
+        &self,
+        state: &S,
+        epsilon: f64,
+        rng: &mut dyn RngCore,
+    ) -> Option<S::A> {
+        let values = self.q.action_values(state)?;
+        // Follow the order of the actions of the `State`, so that a seeded `rng` picks the same
+        // actions in every run.
+        let learned: Vec<(S::A, f64)> = state
+            .actions()
+            .into_iter()
+            .filter_map(|action| values.get(&action).map(|&value| (action, value)))
+            .collect();
+        let max = learned
+            .iter()
+            .map(|(_, value)| *value)
+            .filter(|value| !value.is_nan())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let best: Vec<&S::A> = learned
+            .iter()
+            .filter(|(_, value)| max == f64::NEG_INFINITY || *value >= max - epsilon)
+            .map(|(action, _)| action)
+            .collect();
+        best.choose(rng).map(|&action| action.clone())
+    }
+
+    /// Returns up to `n` actions for the given `State` together with their learned values, best
+    /// first, for example to suggest several good actions instead of one. Actions without a
+    /// learned value are left out, so fewer than `n` actions are returned when fewer values were
+    /// learned. Actions with the same value keep the order of the actions of the `State`, and
+    /// actions with a NaN value come last.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::State;
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl State for MyState {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+    /// #     fn actions(&self) -> Vec<i32> { vec![-2, -1, 1, 2] }
+    /// # }
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::AgentTrainer;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(HashMap::from([(
+    ///     MyState(0),
+    ///     HashMap::from([(-2, 0.5), (-1, 1.0), (1, 3.0), (2, 1.0)]),
+    /// )]));
+    ///
+    /// let state = MyState(0);
+    /// let best = trainer.best_n_actions(&state, 3);
+    /// // The tie between -1 and 2 is broken by the order of the actions of the `State`.
+    /// assert_eq!(best, vec![(1, 3.0), (-1, 1.0), (2, 1.0)]);
+    /// assert!(best.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    ///
+    /// // The returned values are the highest learned values.
+    /// let mut values: Vec<f64> = trainer.expected_values(&state).unwrap().values().copied().collect();
+    /// values.sort_by(|a, b| b.total_cmp(a));
+    /// assert!(best.iter().map(|(_, value)| *value).eq(values[..3].iter().copied()));
+    ///
+    /// assert_eq!(trainer.best_n_actions(&state, 10).len(), 4);
+    /// assert!(trainer.best_n_actions(&MyState(1), 3).is_empty());
+    /// ```
+    pub fn best_n_actions(&self, state: &S, n: usize) -> Vec<(S::A, f64)> {
+        let Some(values) = self.q.action_values(state) else {
+            return Vec::new();
+        };
+        let mut learned: Vec<(S::A, f64)> = state
+            .actions()
+            .into_iter()
+            .filter_map(|action| values.get(&action).map(|&value| (action, value)))
+            .collect();
+        // the sort is stable, so ties keep the order of the actions of the `State`
+        learned.sort_by(|(_, v1), (_, v2)| v1.is_nan().cmp(&v2.is_nan()).then(v2.total_cmp(v1)));
+        learned.truncate(n);
+        learned
+    }
+
+    /// Returns a probability distribution over the actions learned for the given `State`, or
+    /// `None` if no values were learned. The probabilities are the softmax of the learned values
+    /// divided by `temperature`: a lower temperature puts more probability on the best action, a
+    /// higher temperature spreads it more evenly over all actions. A `temperature` of (nearly) 0
+    /// puts all probability on the best action. Actions with a NaN value get probability 0, and
+    /// `None` is returned when all learned values of the `State` are NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let state = MyState(3);
+    /// let best = trainer.best_action(&state).unwrap();
+    /// let warm = trainer.action_distribution(&state, 1.).unwrap();
+    /// let cold = trainer.action_distribution(&state, 0.1).unwrap();
+    /// assert!((warm.values().sum::<f64>() - 1.).abs() < 1e-9);
+    /// assert!((cold.values().sum::<f64>() - 1.).abs() < 1e-9);
+    /// assert!(cold[&best] > warm[&best]);
+    /// assert!(warm[&best] > 0.5);
+    /// assert!(trainer.action_distribution(&MyState(100), 1.).is_none());
+    ///
+    /// // At zero temperature, the best action is always taken.
+    /// let greedy = trainer.action_distribution(&state, 0.).unwrap();
+    /// assert_eq!(greedy[&best], 1.);
+    /// assert_eq!(greedy[&-best], 0.);
+    /// ```
+    ///
+    /// Actions with a NaN value are never taken:
+    ///
+    /// ```
+    /// # use rurel::mdp::State;
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl State for MyState {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+    /// #     fn actions(&self) -> Vec<i32> { vec![-1, 0, 1] }
+    /// # }
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::AgentTrainer;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(HashMap::from([
+    ///     (MyState(0), HashMap::from([(-1, 1.), (0, f64::NAN), (1, 1.)])),
+    ///     (MyState(1), HashMap::from([(-1, f64::NAN), (1, f64::NAN)])),
+    ///     (MyState(2), HashMap::from([(1, f64::NAN)])),
+    /// ]));
+    ///
+    /// let distribution = trainer.action_distribution(&MyState(0), 1.).unwrap();
+    /// assert_eq!(distribution[&0], 0.);
+    /// assert!((distribution[&-1] - 0.5).abs() < 1e-9);
+    /// assert!((distribution[&1] - 0.5).abs() < 1e-9);
+    /// assert!(trainer.action_distribution(&MyState(1), 1.).is_none());
+    /// assert!(trainer.action_distribution(&MyState(2), 1.).is_none());
+    /// ```
+    pub fn action_distribution(&self, state: &S, temperature: f64) -> Option<HashMap<S::A, f64>> {
+        let values = self.q.action_values(state)?;
+        let (best, max) = values
+            .iter()
+            .filter(|(_, value)| !value.is_nan())
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        if values.len() == 1 {
+            // A single action is always taken, whatever its value and the temperature.
+            return Some(HashMap::from([(best.clone(), 1.)]));
+        }
+        let weights: HashMap<S::A, f64> = values
+            .iter()
+            .map(|(action, value)| {
+                let weight = if value.is_nan() {
+                    0.
+                } else if temperature <= f64::EPSILON {
+                    // the limit of the softmax for a temperature approaching 0
+                    if action == best {
+                        1.
+                    } else {
+                        0.
+                    }
+                } else {
+                    ((value - max) / temperature).exp()
+                };
+                (action.clone(), weight)
             })
-            .map(|t| t.0.clone())
+            .collect();
+        let total: f64 = weights.values().sum();
+        Some(
+            weights
+                .into_iter()
+                .map(|(action, weight)| (action, weight / total))
+                .collect(),
+        )
+    }
+
+    /// Returns the Shannon entropy, in nats, of the [action_distribution] of the given `State` at
+    /// the given `temperature`, or `None` if no values were learned. The entropy is highest when
+    /// all actions have the same value, and approaches 0 as the policy becomes deterministic.
+    /// This can be used to detect a policy that converges too early.
+    ///
+    /// [action_distribution]: AgentTrainer::action_distribution
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::MyState;
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::AgentTrainer;
+    ///
+    /// // The gap between the values of the actions grows with the state.
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     (0..3)
+    ///         .map(|x| (MyState(x), HashMap::from([(-1, 0.), (1, x as f64 * 2.)])))
+    ///         .collect(),
+    /// );
+    ///
+    /// let entropies: Vec<f64> =
+    ///     (0..3).map(|x| trainer.policy_entropy(&MyState(x), 1.).unwrap()).collect();
+    /// assert!((entropies[0] - 2f64.ln()).abs() < 1e-9);
+    /// assert!(entropies[0] > entropies[1] && entropies[1] > entropies[2]);
+    /// assert!(trainer.policy_entropy(&MyState(100), 1.).is_none());
+    ///
+    /// let states = [MyState(0), MyState(1), MyState(2), MyState(100)];
+    /// let mean = trainer.mean_policy_entropy(&states, 1.).unwrap();
+    /// assert!((mean - entropies.iter().sum::<f64>() / 3.).abs() < 1e-9);
+    /// ```
+    pub fn policy_entropy(&self, state: &S, temperature: f64) -> Option<f64> {
+        let distribution = self.action_distribution(state, temperature)?;
+        Some(
+            -distribution
+                .values()
+                .filter(|&&p| p > 0.)
+                .map(|p| p * p.ln())
+                .sum::<f64>(),
+        )
+    }
+
+    /// Returns the mean [policy_entropy](AgentTrainer::policy_entropy) of the given states at the
+    /// given `temperature`. States without learned values are left out. Returns `None` if no
+    /// values were learned for any of the states.
+    pub fn mean_policy_entropy(&self, states: &[S], temperature: f64) -> Option<f64> {
+        let (sum, count) = states
+            .iter()
+            .filter_map(|state| self.policy_entropy(state, temperature))
+            .fold((0., 0), |(sum, count), entropy| (sum + entropy, count + 1));
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        }
+    }
+
+    /// Returns the temporal difference error of taking `action` in `state`, arriving in
+    /// `next_state` with `reward`: the received reward plus the discounted best learned value of
+    /// `next_state`, minus the learned value of `action` in `state`. The discount factor and the
+    /// value of unknown actions are taken from the given [QLearning] strategy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Without discounting and with a learning rate of 1, values converge after a single update.
+    /// let learning = QLearning::new(1., 0., 0.);
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &learning,
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let (state, next_state) = (MyState(3), MyState(4));
+    /// assert_eq!(trainer.td_error(&learning, &state, &1, next_state.reward(), &next_state), 0.);
+    /// assert_eq!(trainer.td_error(&learning, &state, &1, next_state.reward() + 1., &next_state), 1.);
+    /// ```
+    pub fn td_error(
+        &self,
+        learning_strategy: &QLearning,
+        state: &S,
+        action: &S::A,
+        reward: f64,
+        next_state: &S,
+    ) -> f64 {
+        let old_value = self.q.get(state, action);
+        let new_action_values = self.q.action_values(next_state);
+        let terminal = next_state.actions().is_empty();
+        learning_strategy.td_error(
+            &new_action_values.as_deref(),
+            &old_value.as_ref(),
+            reward,
+            terminal,
+        )
+    }
+
+    /// Greedily follows the best learned action from the current state of the given agent, for at
+    /// most `max_steps` steps. The rollout stops early when a terminal state is reached: a sink
+    /// state (a `State` without actions), or a `State` the agent marks as
+    /// [terminal](mdp/trait.Agent.html#method.is_terminal). It also stops when no values were
+    /// learned for the current `State`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(100000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// let rollout = trainer.rollout(&mut agent, 30);
+    /// assert_eq!(rollout.actions.len(), 30);
+    /// assert_eq!(rollout.states.len(), 31);
+    /// assert!(rollout.states.iter().any(|s| s.reward() > -1.5));
+    /// ```
+    ///
+    /// The rollout ends in a terminal state:
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::line::MyState;
+    /// struct MyAgent { state: MyState }
+    ///
+    /// impl Agent<MyState> for MyAgent {
+    ///     fn current_state(&self) -> &MyState { &self.state }
+    ///     fn take_action(&mut self, action: &i32) {
+    ///         self.state = MyState(self.state.0 + action)
+    ///     }
+    ///     // The episode ends at 3.
+    ///     fn is_terminal(&self) -> bool { self.state.0 == 3 }
+    /// }
+    ///
+    /// use std::collections::HashMap;
+    ///
+    /// use rurel::AgentTrainer;
+    ///
+    /// // Moving right is learned to be best everywhere.
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.import_state(
+    ///     (-10..10)
+    ///         .map(|x| (MyState(x), HashMap::from([(-1, 0.), (1, 1.)])))
+    ///         .collect(),
+    /// );
+    ///
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let rollout = trainer.rollout(&mut agent, 30);
+    /// assert_eq!(rollout.actions, vec![1, 1, 1]);
+    /// assert!(rollout.states.last() == Some(&MyState(3)));
+    /// ```
+    pub fn rollout(&self, agent: &mut dyn Agent<S>, max_steps: usize) -> RolloutResult<S> {
+        let mut result = RolloutResult {
+            states: vec![agent.current_state().clone()],
+            actions: Vec::new(),
+            total_reward: 0.,
+        };
+        for _ in 0..max_steps {
+            if is_terminal(agent) {
+                break;
+            }
+            let s_t = agent.current_state().clone();
+            let action = match self.best_action(&s_t) {
+                Some(action) => action,
+                None => break,
+            };
+            agent.take_action(&action);
+
+            let s_t_next = agent.current_state();
+            result.total_reward += step_reward(agent, &s_t, &action);
+            result.states.push(s_t_next.clone());
+            result.actions.push(action);
+        }
+        result
+    }
+
+    /// Simulates following the greedy policy from the current state of the given agent, and returns
+    /// the path taken as the visited states with the best action taken in each. This shows how the
+    /// learned values lead from a start state, for example to debug how rewards propagate.
+    ///
+    /// The path stops before a `State` would be visited a second time, so it never contains a
+    /// cycle. It also stops at a terminal `State`, at a `State` without learned values, and after
+    /// `max_steps` steps. The agent is left in the `State` where the path stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use std::collections::HashSet;
+    ///
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(100000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// let trajectory = trainer.greedy_trajectory(&mut agent, 100);
+    /// assert_eq!(trajectory[0].0, MyState { x: 0, y: 0 });
+    /// let states: HashSet<_> = trajectory.iter().map(|(state, _)| state).collect();
+    /// assert_eq!(states.len(), trajectory.len());
+    /// // The path ends by stepping back and forth at the target, which would be a cycle.
+    /// let (last, _) = trajectory.last().unwrap();
+    /// assert!(last.reward() >= -1.);
+    /// ```
+    pub fn greedy_trajectory(&self, agent: &mut dyn Agent<S>, max_steps: usize) -> Vec<(S, S::A)> {
+        let mut trajectory = Vec::new();
+        let mut visited = HashSet::new();
+        for _ in 0..max_steps {
+            let state = agent.current_state().clone();
+            if is_terminal(agent) || !visited.insert(state.clone()) {
+                break;
+            }
+            let action = match self.best_action(&state) {
+                Some(action) => action,
+                None => break,
+            };
+            agent.take_action(&action);
+            trajectory.push((state, action));
+        }
+        trajectory
+    }
+
+    /// Evaluates the learned policy by following it greedily for `episodes` rollouts, and returns
+    /// the mean total reward of the rollouts, see [rollout](AgentTrainer::rollout). Every rollout
+    /// starts from a new agent returned by `make_agent`, so the policy can be evaluated over a
+    /// distribution of start states. A rollout takes at most `max_steps` steps. Returns NaN if
+    /// `episodes` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState { x: i32, y: i32 }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction { dx: i32, dy: i32 }
+    /// // Rewards getting close to (10, 10).
+    /// impl State for MyState {
+    ///     type A = MyAction;
+    ///     fn reward(&self) -> f64 {
+    ///         20. - (((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt()
+    ///     }
+    /// #     fn actions(&self) -> Vec<MyAction> {
+    /// #         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+    /// #              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 }]
+    /// #     }
+    /// }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState {
+    /// #             x: (((self.state.x + action.dx) % 21) + 21) % 21,
+    /// #             y: (((self.state.y + action.dy) % 21) + 21) % 21,
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Starts at a random position on the grid.
+    /// let make_agent = || -> Box<dyn Agent<MyState>> {
+    ///     let (x, y) = (rand::random::<u32>() % 21, rand::random::<u32>() % 21);
+    ///     Box::new(MyAgent { state: MyState { x: x as i32, y: y as i32 } })
+    /// };
+    ///
+    /// let untrained = AgentTrainer::new();
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(100000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// assert!(trainer.evaluate_policy(make_agent, 50, 30) > untrained.evaluate_policy(make_agent, 50, 30));
+    /// ```
+    pub fn evaluate_policy(
+        &self,
+        make_agent: impl Fn() -> Box<dyn Agent<S>>,
+        episodes: usize,
+        max_steps: usize,
+    ) -> f64 {
+        let total: f64 = (0..episodes)
+            .map(|_| self.rollout(make_agent().as_mut(), max_steps).total_reward)
+            .sum();
+        total / episodes as f64
+    }
+
+    /// Performs a single update of the learned value of taking `action` in `state`, arriving in
+    /// `next_state` with `reward`, in the same way as a step of [train](AgentTrainer::train). This
+    /// allows driving training from an external loop, such as the loop of a game, one observed
+    /// transition at a time. `next_state` is terminal when it is a sink state (a `State` without
+    /// actions).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let learning = QLearning::new(0.2, 0.01, 2.);
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let mut trajectory = Vec::new();
+    /// trainer.train_with_callback(
+    ///     &mut agent,
+    ///     &learning,
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    ///     &mut |step| {
+    ///         trajectory.push((step.state.clone(), *step.action, step.reward, step.next_state.clone()))
+    ///     },
+    /// );
+    ///
+    /// // Updating with the same transitions learns the same values.
+    /// let mut updated = AgentTrainer::new();
+    /// for (state, action, reward, next_state) in &trajectory {
+    ///     updated.update(state, action, *reward, next_state, &learning);
+    /// }
+    /// assert!(updated.learned_values() == trainer.learned_values());
+    /// ```
+    pub fn update(
+        &mut self,
+        state: &S,
+        action: &S::A,
+        reward: f64,
+        next_state: &S,
+        learning_strategy: &dyn LearningStrategy<S>,
+    ) {
+        self.record_visit(state);
+        let r_learned = self.observe_reward(self.training_reward(state, next_state, reward));
+        let terminal = next_state.actions().is_empty();
+        let v = self.learned_value(
+            learning_strategy,
+            state,
+            action,
+            r_learned,
+            next_state,
+            terminal,
+        );
+        self.q.insert(state.clone(), action.clone(), v);
     }
 
     /// Trains this [AgentTrainer] using the given [ExplorationStrategy], [LearningStrategy] and
@@ -160,30 +2121,2038 @@ where
         termination_strategy: &mut dyn TerminationStrategy<S>,
         exploration_strategy: &dyn ExplorationStrategy<S>,
     ) {
+        self.train_reporting(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), and returns a [TrainReport]
+    /// of the number of iterations and episodes that were run, and the time it took. This is
+    /// useful when the termination strategy does not end training after a known number of
+    /// iterations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let report = trainer.train_reporting(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    /// assert_eq!(report.iterations, 1000);
+    /// assert_eq!(report.episodes, 0);
+    /// ```
+    pub fn train_reporting(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) -> TrainReport {
+        let started = Instant::now();
+        self.train_with_callback(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            &mut |_| {},
+        );
+        let stats = self.stats.as_ref().expect("training records stats");
+        TrainReport {
+            iterations: stats.steps,
+            episodes: stats.episodes,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), until either the termination
+    /// strategy ends training or `cancel` is set, for example from another thread when the user
+    /// interrupts a long training run. The flag is checked after every step, so training stops
+    /// promptly, and the values learned so far can be queried as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let canceller = {
+    ///     let cancel = Arc::clone(&cancel);
+    ///     std::thread::spawn(move || {
+    ///         std::thread::sleep(Duration::from_millis(200));
+    ///         cancel.store(true, Ordering::Relaxed);
+    ///     })
+    /// };
+    ///
+    /// let started = Instant::now();
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train_cancellable(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(u32::MAX),
+    ///     &RandomExploration::new(),
+    ///     &cancel,
+    /// );
+    /// canceller.join().unwrap();
+    ///
+    /// // Training stopped long before its termination strategy would have ended it.
+    /// assert!(started.elapsed() < Duration::from_secs(10));
+    /// assert!(trainer.last_stats().unwrap().steps < u32::MAX as u64);
+    /// assert_eq!(trainer.best_action(&MyState(3)), Some(-1));
+    /// ```
+    pub fn train_cancellable(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        cancel: &AtomicBool,
+    ) {
+        self.train(
+            agent,
+            learning_strategy,
+            &mut Cancellable {
+                termination_strategy,
+                cancel,
+            },
+            exploration_strategy,
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), drawing all random decisions
+    /// of the exploration strategy from a random number generator seeded with `seed`, through
+    /// [ExplorationStrategy::pick_action_with]. Training the same agent from the same starting
+    /// state with the same seed and the same strategies then learns bit-identical values in every
+    /// run, which makes training reproducible, for example in tests. The built-in exploration
+    /// strategies take the seed into account, and [GreedyExploration] breaks ties between equally
+    /// valued actions in the order of [State::actions] rather than in the order the values are
+    /// stored in. Random decisions made by the agent itself, or by a [State] that overrides
+    /// [State::random_action_with], are not covered by the seed.
+    ///
+    /// The generator is a [StdRng], whose values are the same on every platform, but may change
+    /// between versions of `rand`.
+    ///
+    /// [GreedyExploration]: strategy::explore::GreedyExploration
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::q::NextValueAggregator;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The learned values as bits, ordered by state and action.
+    /// let train = |seed| {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    ///     trainer.train_seeded(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.9, 0.).with_aggregator(NextValueAggregator::Mean),
+    ///         &mut FixedIterations::new(10000),
+    ///         &RandomExploration::new(),
+    ///         seed,
+    ///     );
+    ///     let mut values: Vec<_> = trainer
+    ///         .iter_values()
+    ///         .map(|(s, a, value)| ((s.x, s.y, a.dx, a.dy), value.to_bits()))
+    ///         .collect();
+    ///     values.sort();
+    ///     values
+    /// };
+    /// assert_eq!(train(42), train(42));
+    /// assert_ne!(train(42), train(43));
+    /// ```
+    pub fn train_seeded(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        seed: u64,
+    ) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut stats = TrainingStats::new();
+        self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_with(agent, step, episode, &mut rng)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), with mutable access to the
+    /// exploration strategy. Actions are picked through [ExplorationStrategy::pick_action_mut],
+    /// and the end of an episode is signalled through [ExplorationStrategy::on_episode_end_mut].
+    /// A strategy can then keep state, such as a decaying epsilon, in plain fields, and the same
+    /// strategy can be passed to several training runs that continue where the previous one left
+    /// off, for example in curriculum learning. The indices of the steps and episodes passed to
+    /// the strategy start at 0 in every training run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl State for MyState {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { -((5 - self.0).abs() as f64) }
+    /// #     fn actions(&self) -> Vec<i32> { vec![-1, 1] }
+    /// # }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &i32) {
+    /// #         self.state = MyState((self.state.0 + action).clamp(-5, 5))
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::ExplorationStrategy;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Moves to the right, except with a probability epsilon that decays with every step.
+    /// struct DecayingEpsilon { epsilon: f64, decay: f64, steps: u64 }
+    ///
+    /// impl ExplorationStrategy<MyState> for DecayingEpsilon {
+    ///     fn pick_action(&self, agent: &mut dyn Agent<MyState>) -> i32 {
+    ///         let action = if rand::random::<f64>() < self.epsilon { -1 } else { 1 };
+    ///         agent.take_action(&action);
+    ///         action
+    ///     }
+    ///
+    ///     fn pick_action_mut(&mut self, agent: &mut dyn Agent<MyState>, _: u64, _: u64) -> i32 {
+    ///         self.steps += 1;
+    ///         self.epsilon *= self.decay;
+    ///         self.pick_action(agent)
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let mut exploration = DecayingEpsilon { epsilon: 1., decay: 0.5, steps: 0 };
+    /// let learning = QLearning::new(0.2, 0.01, 2.);
+    /// trainer.train_mut(&mut agent, &learning, &mut FixedIterations::new(10), &mut exploration);
+    /// assert_eq!(exploration.steps, 10);
+    ///
+    /// // The second run continues with the decayed epsilon.
+    /// trainer.train_mut(&mut agent, &learning, &mut FixedIterations::new(10), &mut exploration);
+    /// assert_eq!(exploration.steps, 20);
+    /// assert_eq!(exploration.epsilon, 0.5f64.powi(20));
+    /// assert_eq!(trainer.last_stats().unwrap().steps, 10);
+    /// ```
+    pub fn train_mut(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &mut dyn ExplorationStrategy<S>,
+    ) {
+        // shared by picking actions and ending episodes, which never happen at the same time
+        let exploration_strategy = RefCell::new(exploration_strategy);
+        let mut stats = TrainingStats::new();
+        self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy
+                    .borrow_mut()
+                    .pick_action_mut(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.borrow_mut().on_episode_end_mut();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), calling `callback` with a
+    /// [TrainingStep] after every value update. This can be used to observe training as it
+    /// happens, for example to plot learning curves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let mut rewards = Vec::new();
+    /// trainer.train_with_callback(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    ///     &mut |step| rewards.push(step.reward),
+    /// );
+    /// assert_eq!(rewards.len(), 1000);
+    /// ```
+    pub fn train_with_callback(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        callback: &mut dyn FnMut(&TrainingStep<S>),
+    ) {
+        self.train_inspecting(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            &mut |_, step| callback(step),
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), passing the metrics of every
+    /// step and every episode to `reporter`. An episode ends at a step that arrives in a terminal
+    /// `State`: a sink state (a `State` without actions), or a `State` the agent marks as
+    /// [terminal](mdp/trait.Agent.html#method.is_terminal).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// // A corridor of 10 states which is walked to the right, with a reward at the end.
+    /// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    /// struct Corridor(i32);
+    /// # impl State for Corridor {
+    /// #     type A = ();
+    /// #     fn reward(&self) -> f64 { if self.0 == 9 { 1.0 } else { 0.0 } }
+    /// #     fn actions(&self) -> Vec<()> { vec![()] }
+    /// # }
+    /// # struct Walker { state: Corridor }
+    /// # impl Agent<Corridor> for Walker {
+    /// #     fn current_state(&self) -> &Corridor { &self.state }
+    /// #     fn take_action(&mut self, _: &()) { self.state = Corridor((self.state.0 + 1) % 10) }
+    /// #     fn is_terminal(&self) -> bool { self.state.0 == 0 }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::report::Reporter;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A reporter that records all metrics.
+    /// #[derive(Default)]
+    /// struct RecordingReporter {
+    ///     steps: Vec<(u64, Corridor, f64, f64)>,
+    ///     episodes: Vec<(u64, f64)>,
+    /// }
+    ///
+    /// impl Reporter<Corridor> for RecordingReporter {
+    ///     fn on_step(&mut self, step: u64, state: &Corridor, _: &(), reward: f64, q_value: f64) {
+    ///         self.steps.push((step, state.clone(), reward, q_value));
+    ///     }
+    ///     fn on_episode_end(&mut self, episode: u64, total_reward: f64) {
+    ///         self.episodes.push((episode, total_reward));
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = Walker { state: Corridor(0) };
+    /// let mut reporter = RecordingReporter::default();
+    /// trainer.train_with_reporter(
+    ///     &mut agent,
+    ///     &QLearning::new(1., 0., 0.),
+    ///     &mut FixedIterations::new(30),
+    ///     &RandomExploration::new(),
+    ///     &mut reporter,
+    /// );
+    ///
+    /// // Every walk through the corridor is an episode that ends back at the start. The first walk
+    /// // learns the initial value of 0, and later walks learn the reward.
+    /// let expected_steps: Vec<_> = (0..30)
+    ///     .map(|step| {
+    ///         let reward = if step % 10 == 8 { 1. } else { 0. };
+    ///         let value = if step < 10 { 0. } else { reward };
+    ///         (step, Corridor(step as i32 % 10), reward, value)
+    ///     })
+    ///     .collect();
+    /// assert!(reporter.steps == expected_steps);
+    /// assert_eq!(reporter.episodes, vec![(0, 1.), (1, 1.), (2, 1.)]);
+    /// ```
+    pub fn train_with_reporter(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        reporter: &mut dyn Reporter<S>,
+    ) {
+        let mut episode = 0;
+        let mut episode_reward = 0.;
+        self.train_with_callback(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            exploration_strategy,
+            &mut |step| {
+                reporter.on_step(
+                    step.iteration,
+                    step.state,
+                    step.action,
+                    step.reward,
+                    step.value,
+                );
+                episode_reward += step.reward;
+                if step.terminal {
+                    reporter.on_episode_end(episode, episode_reward);
+                    episode += 1;
+                    episode_reward = 0.;
+                }
+            },
+        );
+    }
+
+    /// Trains this [AgentTrainer] like [train_with_callback](AgentTrainer::train_with_callback),
+    /// additionally passing a read-only view of the trainer to `callback`. The view already holds
+    /// the value learned in the step, so the callback can query the trainer as it is being
+    /// trained, for example to follow how the best action of a `State` evolves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// let mut best_actions = Vec::new();
+    /// trainer.train_inspecting(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    ///     &mut |trainer, step| {
+    ///         assert_eq!(trainer.expected_value(step.state, step.action), Some(step.value));
+    ///         best_actions.push(trainer.best_action(&MyState(3)));
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(best_actions.len(), 10000);
+    /// assert_eq!(best_actions[0], None);
+    /// assert_eq!(best_actions.last(), Some(&Some(-1)));
+    /// ```
+    pub fn train_inspecting(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        callback: &mut StepCallback<S, T>,
+    ) {
+        let mut stats = TrainingStats::new();
+        self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |trainer, step| {
+                callback(trainer, step);
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), taking the agent and the
+    /// strategies as generic parameters instead of trait objects. The training loop is then
+    /// monomorphized for the given types, so that the compiler can inline the calls to the agent
+    /// and the strategies of every step. The agent is still passed to the exploration strategy as a
+    /// trait object, as [ExplorationStrategy] requires.
+    ///
+    /// Training is the same as with [train](AgentTrainer::train): the same seeded run learns the
+    /// same values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut dynamic = AgentTrainer::new();
+    /// dynamic.train(
+    ///     &mut MyAgent { state: MyState(0) },
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::with_seed(7),
+    /// );
+    ///
+    /// let mut fast = AgentTrainer::new();
+    /// fast.train_static(
+    ///     &mut MyAgent { state: MyState(0) },
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::with_seed(7),
+    /// );
+    ///
+    /// assert_eq!(fast.last_stats().unwrap().steps, 10000);
+    /// assert!(fast.learned_values() == dynamic.learned_values());
+    /// ```
+    pub fn train_static<A, L, X, E>(
+        &mut self,
+        agent: &mut A,
+        learning_strategy: &L,
+        termination_strategy: &mut X,
+        exploration_strategy: &E,
+    ) where
+        A: Agent<S>,
+        L: LearningStrategy<S>,
+        X: TerminationStrategy<S>,
+        E: ExplorationStrategy<S>,
+    {
+        let mut stats = TrainingStats::new();
+        self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), additionally ending training
+    /// when greedy validation rollouts stop improving, as decided by the given
+    /// [ValidationPlateau](strategy/terminate/struct.ValidationPlateau.html). Training also ends
+    /// when the termination strategy decides so.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::{FixedIterations, ValidationPlateau};
+    ///
+    /// let mut validation = ValidationPlateau::new(
+    ///     vec![MyState { x: 0, y: 0 }, MyState { x: 20, y: 20 }],
+    ///     |state: &MyState| -> Box<dyn Agent<MyState>> {
+    ///         Box::new(MyAgent { state: state.clone() })
+    ///     },
+    ///     1000,
+    ///     30,
+    ///     0.01,
+    ///     5,
+    /// );
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train_with_validation(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000000),
+    ///     &RandomExploration::new(),
+    ///     &mut validation,
+    /// );
+    ///
+    /// let steps = trainer.last_stats().unwrap().steps;
+    /// assert!(steps < 1000000);
+    /// assert_eq!(steps % 1000, 0);
+    /// ```
+    pub fn train_with_validation<F>(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        validation: &mut ValidationPlateau<S, F>,
+    ) where
+        F: Fn(&S) -> Box<dyn Agent<S>>,
+    {
+        let mut stats = TrainingStats::new();
+        while !self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            validation.eval_interval(),
+        ) && !validation.evaluate(self)
+        {}
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), in episodes that end when
+    /// the given [EpisodeBoundary] decides so. After every episode, `reset` is called to reset the
+    /// agent to the start of a new episode. Training ends when the termination strategy decides
+    /// so. This allows training in processes without sink states, which would otherwise continue
+    /// a single episode forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// // A walk to the right that never ends, with a reward of 1 for every step.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Walk(i32);
+    /// # impl State for Walk {
+    /// #     type A = ();
+    /// #     fn reward(&self) -> f64 { 1. }
+    /// #     fn actions(&self) -> Vec<()> { vec![()] }
+    /// # }
+    /// # struct Walker { state: Walk }
+    /// # impl Agent<Walk> for Walker {
+    /// #     fn current_state(&self) -> &Walk { &self.state }
+    /// #     fn take_action(&mut self, _: &()) { self.state = Walk(self.state.0 + 1) }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::episode::MaxEpisodeSteps;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = Walker { state: Walk(0) };
+    /// trainer.train_episodes(
+    ///     &mut agent,
+    ///     &QLearning::new(1., 0.5, 0.),
+    ///     &mut FixedIterations::new(100),
+    ///     &RandomExploration::new(),
+    ///     &mut MaxEpisodeSteps::new(5),
+    ///     &mut |agent| agent.state = Walk(0),
+    /// );
+    ///
+    /// // Only the first 5 states of the walk are visited, and their values are the discounted
+    /// // rewards until the end of the episode.
+    /// assert_eq!(trainer.num_states(), 5);
+    /// assert_eq!(trainer.expected_value(&Walk(4), &()), Some(1.));
+    /// assert_eq!(trainer.expected_value(&Walk(3), &()), Some(1.5));
+    /// assert_eq!(trainer.expected_value(&Walk(0), &()), Some(1.9375));
+    /// ```
+    pub fn train_episodes<A: Agent<S>>(
+        &mut self,
+        agent: &mut A,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        episode_boundary: &mut dyn EpisodeBoundary<S>,
+        reset: &mut dyn FnMut(&mut A),
+    ) {
+        let mut stats = TrainingStats::new();
+        let mut termination = EpisodeTermination {
+            termination_strategy,
+            episode_boundary,
+            stopped: false,
+        };
+        loop {
+            self.train_steps(
+                agent,
+                learning_strategy,
+                &mut termination,
+                &mut |_, agent, step, episode| {
+                    exploration_strategy.pick_action_at(agent, step, episode)
+                },
+                &mut |_, step| {
+                    if step.terminal {
+                        exploration_strategy.on_episode_end();
+                    }
+                },
+                &mut stats,
+                u64::MAX,
+            );
+            if termination.stopped {
+                break;
+            }
+            // episodes ending in a terminal state were already counted, and the exploration
+            // strategy was already notified of their end
+            if !is_terminal(agent) {
+                exploration_strategy.on_episode_end();
+                stats.episodes += 1;
+            }
+            reset(agent);
+        }
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), for the given number of
+    /// episodes. Before every episode, the agent is [reset](mdp::ResettableAgent::reset). An
+    /// episode ends when it arrives at a terminal state: a sink state (a `State` without
+    /// actions), or a `State` the agent marks as
+    /// [terminal](mdp/trait.Agent.html#method.is_terminal). An episode that does not arrive at a
+    /// terminal state ends after `max_episode_steps` steps. The learned values carry over from one
+    /// episode to the next.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// // A walk between 0 and 3, where 3 is the goal. There are no actions at the goal.
+    /// impl State for MyState {
+    ///     type A = i32;
+    ///     fn reward(&self) -> f64 { if self.0 == 3 { 1. } else { 0. } }
+    ///     fn actions(&self) -> Vec<i32> { if self.0 == 3 { vec![] } else { vec![-1, 1] } }
+    /// }
+    ///
+    /// struct MyAgent { state: MyState, resets: usize }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &i32) {
+    /// #         self.state = MyState((self.state.0 + action).max(0))
+    /// #     }
+    /// # }
+    /// use rurel::mdp::ResettableAgent;
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    ///
+    /// impl ResettableAgent<MyState> for MyAgent {
+    ///     fn reset(&mut self) {
+    ///         self.state = MyState(0);
+    ///         self.resets += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(2), resets: 0 };
+    /// let learning = QLearning::new(0.5, 1., 0.);
+    /// trainer.train_for_episodes(&mut agent, &learning, &RandomExploration::new(), 50, u64::MAX);
+    ///
+    /// // Every episode started at 0 and ended at the goal.
+    /// assert_eq!(agent.resets, 50);
+    /// assert!(agent.state == MyState(3));
+    /// assert_eq!(trainer.last_stats().unwrap().episodes, 50);
+    /// // Arriving at the goal was learned in every episode.
+    /// assert!(trainer.expected_value(&MyState(2), &1).unwrap() > 0.99);
+    ///
+    /// // Episodes of a single step do not reach the goal.
+    /// trainer.train_for_episodes(&mut agent, &learning, &RandomExploration::new(), 10, 1);
+    /// assert_eq!(agent.resets, 60);
+    /// assert_eq!(trainer.last_stats().unwrap().episodes, 10);
+    /// assert_eq!(trainer.last_stats().unwrap().steps, 10);
+    /// ```
+    pub fn train_for_episodes<A: ResettableAgent<S>>(
+        &mut self,
+        agent: &mut A,
+        learning_strategy: &dyn LearningStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        episodes: usize,
+        max_episode_steps: u64,
+    ) {
+        let mut stats = TrainingStats::new();
+        let terminal = Cell::new(false);
+        for _ in 0..episodes {
+            agent.reset();
+            terminal.set(false);
+            self.train_steps(
+                agent,
+                learning_strategy,
+                &mut TerminalStates(&terminal),
+                &mut |_, agent, step, episode| {
+                    exploration_strategy.pick_action_at(agent, step, episode)
+                },
+                &mut |_, step| terminal.set(step.terminal),
+                &mut stats,
+                max_episode_steps,
+            );
+            exploration_strategy.on_episode_end();
+            // episodes ending in a terminal state are already counted
+            if !is_terminal(agent) {
+                stats.episodes += 1;
+            }
+        }
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), using [SarsaLambda] learning
+    /// instead of a [LearningStrategy]. The value of an action is learned after the next action
+    /// was picked by the exploration strategy, together with the values of the earlier actions of
+    /// the episode that still have a trace. All traces are cleared when an episode ends in a
+    /// terminal state: a sink state (a `State` without actions), or a `State` the agent marks as
+    /// [terminal](mdp/trait.Agent.html#method.is_terminal). Since no next action is picked after
+    /// the last step of training, that step is not learned from, unless it ends in a terminal
+    /// state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState { x: i32, y: i32 }
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction { dx: i32, dy: i32 }
+    /// // A 21x21 grid, where the agent has to reach (10, 10).
+    /// impl State for MyState {
+    ///     type A = MyAction;
+    ///     fn reward(&self) -> f64 {
+    ///         -((((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt())
+    ///     }
+    ///     fn actions(&self) -> Vec<MyAction> {
+    ///         if (self.x, self.y) == (10, 10) {
+    ///             return vec![];
+    ///         }
+    ///         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+    ///              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 }]
+    ///     }
+    /// }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState {
+    /// #             x: (((self.state.x + action.dx) % 21) + 21) % 21,
+    /// #             y: (((self.state.y + action.dy) % 21) + 21) % 21,
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::{QLearning, SarsaLambda, TraceMode};
+    /// use rurel::strategy::terminate::SinkStates;
+    ///
+    /// // Trains for episodes from different starting points, until `steps` steps were taken.
+    /// fn train(steps: u64, mut episode: impl FnMut(&mut MyAgent) -> u64) {
+    ///     let mut taken = 0;
+    ///     for i in 0.. {
+    ///         let mut agent = MyAgent { state: MyState { x: i % 21, y: (i * 7) % 21 } };
+    ///         taken += episode(&mut agent);
+    ///         if taken >= steps {
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut sarsa = AgentTrainer::new();
+    /// train(50000, |agent| {
+    ///     sarsa.train_sarsa_lambda(
+    ///         agent,
+    ///         &SarsaLambda::new(0.3, 0.8, 0.9, 0., TraceMode::Replacing),
+    ///         &mut SinkStates {},
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     sarsa.last_stats().unwrap().steps
+    /// });
+    /// let mut q_learning = AgentTrainer::new();
+    /// train(50000, |agent| {
+    ///     q_learning.train(
+    ///         agent,
+    ///         &QLearning::new(0.3, 0.8, 0.),
+    ///         &mut SinkStates {},
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     q_learning.last_stats().unwrap().steps
+    /// });
+    ///
+    /// // The number of steps it takes to reach the target from (0, 0) following the best actions,
+    /// // or 100 if the target is not reached.
+    /// let steps_to_target = |trainer: &AgentTrainer<MyState>| {
+    ///     let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    ///     for steps in 0..100 {
+    ///         if agent.current_state().actions().is_empty() {
+    ///             return steps;
+    ///         }
+    ///         match trainer.best_action(agent.current_state()) {
+    ///             Some(action) => agent.take_action(&action),
+    ///             None => break,
+    ///         }
+    ///     }
+    ///     100
+    /// };
+    /// // The shortest path takes 20 steps. The traces spread the rewards of an episode over all
+    /// // of its steps, so SARSA(λ) learns at least as good a path as Q Learning.
+    /// assert!(steps_to_target(&sarsa) <= 22);
+    /// assert!(steps_to_target(&sarsa) <= steps_to_target(&q_learning) + 2);
+    /// ```
+    pub fn train_sarsa_lambda(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &SarsaLambda,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        let mut stats = TrainingStats::new();
+        let mut traces = HashMap::new();
+        // the last step, which is learned from once the next action is picked
+        let mut pending: Option<(S, S::A, f64)> = None;
+        self.run_steps(
+            agent,
+            &mut |trainer: &mut Self, step: &Step<S>| {
+                if let Some((state, last_action, reward)) = pending.take() {
+                    let next_value = trainer.q.get(step.state, step.action);
+                    trainer.learn_traces(
+                        learning_strategy,
+                        &mut traces,
+                        state,
+                        last_action,
+                        reward,
+                        next_value,
+                        false,
+                    );
+                }
+                if step.terminal {
+                    trainer.learn_traces(
+                        learning_strategy,
+                        &mut traces,
+                        step.state.clone(),
+                        step.action.clone(),
+                        step.learned_reward,
+                        None,
+                        true,
+                    );
+                    traces.clear();
+                } else {
+                    pending = Some((step.state.clone(), step.action.clone(), step.learned_reward));
+                }
+                trainer.current_value(step)
+            },
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), using [MonteCarlo] learning
+    /// instead of a [LearningStrategy]. The steps of an episode are kept until the episode ends
+    /// in a terminal state: a sink state (a `State` without actions), or a `State` the agent
+    /// marks as [terminal](mdp/trait.Agent.html#method.is_terminal). Then the values of all
+    /// actions taken during the episode are learned from the returns that followed them. The
+    /// steps of an episode that did not end when training ends are not learned from.
+    pub fn train_monte_carlo(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &MonteCarlo,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        let mut stats = TrainingStats::new();
+        let mut episode = Vec::new();
+        self.run_steps(
+            agent,
+            &mut |trainer: &mut Self, step: &Step<S>| {
+                episode.push((step.state.clone(), step.action.clone(), step.learned_reward));
+                if step.terminal {
+                    trainer.learn_episode(learning_strategy, &mut episode);
+                }
+                trainer.current_value(step)
+            },
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), using n-step [TreeBackup]
+    /// learning instead of a [LearningStrategy]. The last `n` steps are kept, and every step is
+    /// learned from once the `n - 1` steps that follow it were taken, so the learned values lag
+    /// `n - 1` steps behind. When an episode ends in a terminal state, all kept steps are learned
+    /// from with the rewards up to the end of the episode. When training ends, the kept steps
+    /// are learned from with the steps taken so far, bootstrapping from the last state.
+    ///
+    /// Every step is backed up along the steps that follow it for as long as the exploration
+    /// strategy takes the best action, see [TreeBackup]. A mostly greedy exploration strategy
+    /// therefore backs up over more steps than a random one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// // A corridor of 4 states which can be walked in both directions, with a reward at the end.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Corridor(i32);
+    /// # impl State for Corridor {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { if self.0 == 3 { 1.0 } else { 0.0 } }
+    /// #     fn actions(&self) -> Vec<i32> { if self.0 == 3 { vec![] } else { vec![-1, 1] } }
+    /// # }
+    /// # struct Walker { state: Corridor }
+    /// # impl Agent<Corridor> for Walker {
+    /// #     fn current_state(&self) -> &Corridor { &self.state }
+    /// #     fn take_action(&mut self, action: &i32) { self.state = Corridor((self.state.0 + action).max(0)) }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::TreeBackup;
+    /// use rurel::strategy::terminate::SinkStates;
+    ///
+    /// let gamma: f64 = 0.9;
+    /// let mut trainer = AgentTrainer::new();
+    /// for _ in 0..200 {
+    ///     let mut agent = Walker { state: Corridor(0) };
+    ///     trainer.train_tree_backup(
+    ///         &mut agent,
+    ///         &TreeBackup::new(1., gamma, 0., 3),
+    ///         &mut SinkStates {},
+    ///         &RandomExploration::new(),
+    ///     );
+    /// }
+    ///
+    /// // The optimal values: walking right reaches the end of the corridor in 3 - x steps, and
+    /// // walking left first takes two more steps, except at the start of the corridor.
+    /// let optimal = |x: i32, action: i32| match action {
+    ///     1 => gamma.powi(2 - x),
+    ///     _ => gamma.powi(2 - (x - 1).max(0) + 1),
+    /// };
+    /// for x in 0..3 {
+    ///     for action in [-1, 1] {
+    ///         let value = trainer.expected_value(&Corridor(x), &action).unwrap();
+    ///         assert!((value - optimal(x, action)).abs() < 1e-9);
+    ///     }
+    /// }
+    /// ```
+    pub fn train_tree_backup(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &TreeBackup,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        let mut stats = TrainingStats::new();
+        let mut steps = VecDeque::new();
+        self.run_steps(
+            agent,
+            &mut |trainer: &mut Self, step: &Step<S>| {
+                steps.push_back(Transition {
+                    state: step.state.clone(),
+                    action: step.action.clone(),
+                    reward: step.learned_reward,
+                    next_state: step.next_state.clone(),
+                    terminal: step.terminal,
+                });
+                if steps.len() == learning_strategy.n() {
+                    trainer.learn_tree_backup(learning_strategy, &mut steps);
+                }
+                if step.terminal {
+                    while !steps.is_empty() {
+                        trainer.learn_tree_backup(learning_strategy, &mut steps);
+                    }
+                }
+                trainer.current_value(step)
+            },
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        while !steps.is_empty() {
+            self.learn_tree_backup(learning_strategy, &mut steps);
+        }
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), using an exploration
+    /// strategy that is controlled by a parameter, such as the epsilon of
+    /// [EpsilonGreedyExploration](strategy/explore/struct.EpsilonGreedyExploration.html). Before
+    /// every step, the parameter is computed by calling `schedule` with the index of the step,
+    /// which allows for example decaying the amount of exploration during training.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use std::cell::RefCell;
+    ///
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::{EpsilonGreedyExploration, ParameterizedExplorationStrategy};
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Records for every step whether the best learned action was taken.
+    /// struct Recording { greedy: RefCell<Vec<bool>> }
+    ///
+    /// impl ParameterizedExplorationStrategy<MyState> for Recording {
+    ///     fn pick_action(
+    ///         &self,
+    ///         agent: &mut dyn Agent<MyState>,
+    ///         epsilon: f64,
+    ///         best_action: Option<MyAction>,
+    ///     ) -> MyAction {
+    ///         let action = EpsilonGreedyExploration::new().pick_action(
+    ///             agent,
+    ///             epsilon,
+    ///             best_action.clone(),
+    ///         );
+    ///         self.greedy.borrow_mut().push(best_action == Some(action.clone()));
+    ///         action
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// let exploration = Recording { greedy: RefCell::new(vec![]) };
+    /// trainer.train_with_schedule(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &exploration,
+    ///     &|step| 1. - step as f64 / 10000.,
+    /// );
+    ///
+    /// let greedy = exploration.greedy.into_inner();
+    /// let count = |steps: &[bool]| steps.iter().filter(|&&g| g).count();
+    /// assert!(count(&greedy[..5000]) < count(&greedy[5000..]));
+    /// ```
+    pub fn train_with_schedule(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ParameterizedExplorationStrategy<S>,
+        schedule: &dyn Fn(u64) -> f64,
+    ) {
+        let mut stats = TrainingStats::new();
+        self.train_steps(
+            agent,
+            learning_strategy,
+            termination_strategy,
+            &mut |trainer, agent, step, _| {
+                let best_action = trainer.best_action(agent.current_state());
+                let parameter = schedule(step);
+                #[cfg(feature = "logging")]
+                if step.is_multiple_of(logging::LOG_INTERVAL) {
+                    log::debug!("step {}: exploration parameter {}", step, parameter);
+                }
+                exploration_strategy.pick_action(agent, parameter, best_action)
+            },
+            &mut |_, _| {},
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Trains this [AgentTrainer] like [train](AgentTrainer::train), additionally storing every
+    /// observed transition in `buffer` and replaying `replays_per_step` transitions sampled from
+    /// it after every step. This reuses past experience and reduces the correlation between
+    /// consecutive updates.
+    ///
+    /// Replayed transitions are passed to [LearningStrategy::value] in the same way as new
+    /// transitions: with the currently learned values of the next state and the currently learned
+    /// value of the action, so they take into account everything learned since they were
+    /// observed.
+    ///
+    /// Every replayed update is scaled by the importance-sampling weight returned by
+    /// [ReplayMemory::sample_index]: the learned value moves from the current value towards the
+    /// value given by the learning strategy by this weight, which is always 1 for a [ReplayBuffer].
+    /// After every replayed step, the priority of the transition is updated with the size of this
+    /// change before weighting, which for [QLearning] is its TD error
+    /// scaled by the learning rate. A [PrioritizedReplayBuffer](replay::PrioritizedReplayBuffer)
+    /// uses it to replay transitions with a large TD error more often.
+    ///
+    /// [ReplayBuffer]: replay::ReplayBuffer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// // A corridor of 10 states which is walked to the right, with a reward at the end.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Corridor(i32);
+    /// # impl State for Corridor {
+    /// #     type A = ();
+    /// #     fn reward(&self) -> f64 { if self.0 == 9 { 1.0 } else { 0.0 } }
+    /// #     fn actions(&self) -> Vec<()> { vec![()] }
+    /// # }
+    /// # struct Walker { state: Corridor }
+    /// # impl Agent<Corridor> for Walker {
+    /// #     fn current_state(&self) -> &Corridor { &self.state }
+    /// #     fn take_action(&mut self, _: &()) { self.state = Corridor((self.state.0 + 1) % 10) }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::replay::ReplayBuffer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let learning = QLearning::new(0.5, 0.9, 0.);
+    ///
+    /// // Without replay, the reward propagates back a single state per walk through the corridor.
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = Walker { state: Corridor(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &learning,
+    ///     &mut FixedIterations::new(50),
+    ///     &RandomExploration::new(),
+    /// );
+    /// assert_eq!(trainer.expected_value(&Corridor(0), &()), Some(0.));
+    ///
+    /// // With replay, it reaches the start of the corridor in the same number of iterations.
+    /// let mut replay_trainer = AgentTrainer::new();
+    /// let mut agent = Walker { state: Corridor(0) };
+    /// replay_trainer.train_with_replay(
+    ///     &mut agent,
+    ///     &learning,
+    ///     &mut FixedIterations::new(50),
+    ///     &RandomExploration::new(),
+    ///     &mut ReplayBuffer::new(100),
+    ///     50,
+    /// );
+    /// assert!(replay_trainer.expected_value(&Corridor(0), &()).unwrap() > 0.);
+    /// ```
+    pub fn train_with_replay(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        buffer: &mut dyn ReplayMemory<S>,
+        replays_per_step: usize,
+    ) {
+        let mut stats = TrainingStats::new();
+        self.run_steps(
+            agent,
+            &mut |trainer: &mut Self, step: &Step<S>| {
+                let v = trainer.learn_step(learning_strategy, step);
+                buffer.push(Transition {
+                    state: step.state.clone(),
+                    action: step.action.clone(),
+                    reward: step.reward,
+                    next_state: step.next_state.clone(),
+                    terminal: step.terminal,
+                });
+                for _ in 0..replays_per_step {
+                    trainer.replay(learning_strategy, buffer);
+                }
+                v
+            },
+            termination_strategy,
+            &mut |_, agent, step, episode| {
+                exploration_strategy.pick_action_at(agent, step, episode)
+            },
+            &mut |_, step| {
+                if step.terminal {
+                    exploration_strategy.on_episode_end();
+                }
+            },
+            &mut stats,
+            u64::MAX,
+        );
+        self.stats = Some(stats);
+    }
+
+    /// Runs the training loop for at most `max_steps` steps, recording into `stats`, learning the
+    /// value of every step with `learning_strategy`. See [run_steps](AgentTrainer::run_steps).
+    #[allow(clippy::too_many_arguments)]
+    fn train_steps<A, L, X, P, C>(
+        &mut self,
+        agent: &mut A,
+        learning_strategy: &L,
+        termination_strategy: &mut X,
+        pick_action: &mut P,
+        callback: &mut C,
+        stats: &mut TrainingStats,
+        max_steps: u64,
+    ) -> bool
+    where
+        A: Agent<S> + ?Sized,
+        L: LearningStrategy<S> + ?Sized,
+        X: TerminationStrategy<S> + ?Sized,
+        P: FnMut(&Self, &mut A, u64, u64) -> S::A + ?Sized,
+        C: FnMut(&Self, &TrainingStep<S>) + ?Sized,
+    {
+        self.run_steps(
+            agent,
+            &mut |trainer: &mut Self, step: &Step<S>| trainer.learn_step(learning_strategy, step),
+            termination_strategy,
+            pick_action,
+            callback,
+            stats,
+            max_steps,
+        )
+    }
+
+    /// Runs the training loop for at most `max_steps` steps, recording into `stats`. Actions are
+    /// picked by `pick_action`, which is given this trainer, the agent and the indices of the step
+    /// and the episode. Every step is passed to `learn`, which learns from it and returns the
+    /// value of the action taken in the step, as passed to `callback`. Returns whether the
+    /// termination strategy ended training.
+    ///
+    /// The loop is generic, so that it is monomorphized for concrete agents and strategies, and
+    /// dispatches dynamically when it is given trait objects.
+    #[allow(clippy::too_many_arguments)]
+    fn run_steps<A, F, X, P, C>(
+        &mut self,
+        agent: &mut A,
+        learn: &mut F,
+        termination_strategy: &mut X,
+        pick_action: &mut P,
+        callback: &mut C,
+        stats: &mut TrainingStats,
+        max_steps: u64,
+    ) -> bool
+    where
+        A: Agent<S> + ?Sized,
+        F: FnMut(&mut Self, &Step<S>) -> f64 + ?Sized,
+        X: TerminationStrategy<S> + ?Sized,
+        P: FnMut(&Self, &mut A, u64, u64) -> S::A + ?Sized,
+        C: FnMut(&Self, &TrainingStep<S>) + ?Sized,
+    {
+        let start = stats.steps;
+        #[cfg(feature = "logging")]
+        let started = Instant::now();
         loop {
             let s_t = agent.current_state().clone();
-            let action = exploration_strategy.pick_action(agent);
+            self.record_visit(&s_t);
+            let action = pick_action(self, agent, stats.steps, stats.episodes);
 
             // current action value
             let s_t_next = agent.current_state();
-            let r_t_next = s_t_next.reward();
+            let r_t_next = step_reward(agent, &s_t, &action);
 
-            let v = {
-                let old_value = self.q.get(&s_t).and_then(|m| m.get(&action));
-                learning_strategy.value(&self.q.get(s_t_next), &old_value, r_t_next)
-            };
+            let terminal = is_terminal(agent);
+            let r_learned = self.observe_reward(self.training_reward(&s_t, s_t_next, r_t_next));
+            let v = learn(
+                self,
+                &Step {
+                    state: &s_t,
+                    action: &action,
+                    next_state: s_t_next,
+                    reward: r_t_next,
+                    learned_reward: r_learned,
+                    terminal,
+                },
+            );
 
-            self.q.entry(s_t).or_default().insert(action, v);
+            callback(
+                self,
+                &TrainingStep {
+                    iteration: stats.steps,
+                    state: &s_t,
+                    action: &action,
+                    next_state: s_t_next,
+                    reward: r_t_next,
+                    value: v,
+                    terminal,
+                },
+            );
+            stats.record(r_t_next);
+            #[cfg(feature = "logging")]
+            self.log_progress(stats, stats.steps - start, started);
 
-            if termination_strategy.should_stop(s_t_next) {
-                break;
+            let stopped = termination_strategy.should_stop_full(s_t_next, Some(&action), r_t_next);
+            if terminal {
+                stats.episodes += 1;
+            }
+            if stopped || stats.steps - start >= max_steps {
+                #[cfg(feature = "logging")]
+                Self::log_finished(stats, stats.steps - start, started);
+                return stopped;
+            }
+        }
+    }
+
+    /// Logs the progress of training every [LOG_INTERVAL](logging::LOG_INTERVAL) steps, given the
+    /// number of steps taken since `started`.
+    #[cfg(feature = "logging")]
+    fn log_progress(&self, stats: &TrainingStats, steps: u64, started: Instant) {
+        if stats.steps.is_multiple_of(logging::LOG_INTERVAL) && log::log_enabled!(log::Level::Debug)
+        {
+            log::debug!(
+                "step {}: {:.0} steps/s, mean learned value {}",
+                stats.steps,
+                logging::steps_per_sec(steps, started),
+                logging::mean(self.q.iter().map(|(_, _, value)| value)),
+            );
+        }
+    }
+
+    /// Logs the end of a training run, given the number of steps taken since `started`.
+    #[cfg(feature = "logging")]
+    fn log_finished(stats: &TrainingStats, steps: u64, started: Instant) {
+        log::info!(
+            "trained {} steps in {:.2}s ({:.0} steps/s), total reward {}",
+            steps,
+            started.elapsed().as_secs_f64(),
+            logging::steps_per_sec(steps, started),
+            stats.total_reward,
+        );
+    }
+
+    /// Returns the reward to learn from for arriving in `next_state` from `state` with the given
+    /// reward, when rewards are clipped or shaped.
+    fn training_reward(&self, state: &S, next_state: &S, reward: f64) -> f64 {
+        let reward = match self.reward_clip {
+            Some((min, max)) => reward.clamp(min, max),
+            None => reward,
+        };
+        match &self.shaper {
+            Some(shaper) => shaper.shape(state, next_state, reward),
+            None => reward,
+        }
+    }
+
+    /// Observes the given reward when rewards are normalized, and returns the reward to learn from.
+    fn observe_reward(&mut self, reward: f64) -> f64 {
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.observe(reward);
+        }
+        self.normalized_reward(reward)
+    }
+
+    /// Returns the reward to learn from for the given reward.
+    fn normalized_reward(&self, reward: f64) -> f64 {
+        match &self.normalizer {
+            Some(normalizer) => normalizer.normalize(reward),
+            None => reward,
+        }
+    }
+
+    /// Counts a visit of the given `State`, if visits are tracked.
+    fn record_visit(&mut self, state: &S) {
+        if let Some(visits) = &mut self.visits {
+            *visits.entry(state.clone()).or_default() += 1;
+        }
+    }
+
+    /// Learns the value of the action taken in `step` with `learning_strategy`, and returns it.
+    fn learn_step<L: LearningStrategy<S> + ?Sized>(
+        &mut self,
+        learning_strategy: &L,
+        step: &Step<S>,
+    ) -> f64 {
+        let v = self.learned_value(
+            learning_strategy,
+            step.state,
+            step.action,
+            step.learned_reward,
+            step.next_state,
+            step.terminal,
+        );
+        self.q.insert(step.state.clone(), step.action.clone(), v);
+        v
+    }
+
+    /// Returns the value learned so far for the action taken in `step`, or NaN if none was
+    /// learned yet, for learners that learn from a step only after later steps.
+    fn current_value(&self, step: &Step<S>) -> f64 {
+        self.q.get(step.state, step.action).unwrap_or(f64::NAN)
+    }
+
+    /// Replays a transition sampled from `buffer`, scaling the update by its importance-sampling
+    /// weight, and updates its priority with the size of the update before weighting.
+    fn replay(
+        &mut self,
+        learning_strategy: &dyn LearningStrategy<S>,
+        buffer: &mut dyn ReplayMemory<S>,
+    ) {
+        let Some((index, weight)) = buffer.sample_index() else {
+            return;
+        };
+        let t = buffer.transition(index);
+        let v = self.learned_value(
+            learning_strategy,
+            &t.state,
+            &t.action,
+            self.normalized_reward(self.training_reward(&t.state, &t.next_state, t.reward)),
+            &t.next_state,
+            t.terminal,
+        );
+        let (v, td_error) = match self.q.get(&t.state, &t.action) {
+            Some(old) => (old + weight * (v - old), v - old),
+            None => (v, v),
+        };
+        self.q.insert(t.state.clone(), t.action.clone(), v);
+        buffer.update_priority(index, td_error.abs());
+    }
+
+    /// Updates the values of all actions with a trace by the temporal difference error of taking
+    /// `action` in `state`, after first visiting the trace of `action`. Traces decay afterwards,
+    /// and traces that decayed below [MIN_TRACE] are dropped.
+    #[allow(clippy::too_many_arguments)]
+    fn learn_traces(
+        &mut self,
+        learning_strategy: &SarsaLambda,
+        traces: &mut HashMap<(S, S::A), f64>,
+        state: S,
+        action: S::A,
+        reward: f64,
+        next_value: Option<f64>,
+        terminal: bool,
+    ) {
+        let td_error =
+            learning_strategy.td_error(self.q.get(&state, &action), reward, next_value, terminal);
+        let trace = traces.entry((state, action)).or_insert(0.);
+        *trace = learning_strategy.visit(*trace);
+        let decay = learning_strategy.decay();
+        let q = &mut self.q;
+        traces.retain(|(state, action), trace| {
+            let v = learning_strategy.value(q.get(state, action), td_error, *trace);
+            q.insert(state.clone(), action.clone(), v);
+            *trace *= decay;
+            *trace >= MIN_TRACE
+        });
+    }
+
+    /// Learns the values of the actions taken during the given episode of `(state, action,
+    /// reward)` steps from the returns that followed them, and clears the episode.
+    fn learn_episode(&mut self, learning_strategy: &MonteCarlo, episode: &mut Vec<(S, S::A, f64)>) {
+        let rewards: Vec<f64> = episode.iter().map(|(_, _, reward)| *reward).collect();
+        let returns = learning_strategy.returns(&rewards);
+        let mut visited = HashSet::new();
+        for ((state, action, _), ret) in episode.drain(..).zip(returns) {
+            if learning_strategy.visits() == VisitMode::FirstVisit
+                && !visited.insert((state.clone(), action.clone()))
+            {
+                continue;
             }
+            let v = learning_strategy.value(self.q.get(&state, &action), ret);
+            self.q.insert(state, action, v);
         }
     }
+
+    /// Learns the value of the first of the given consecutive steps from the tree backup along all
+    /// of them, and removes it.
+    fn learn_tree_backup(
+        &mut self,
+        learning_strategy: &TreeBackup,
+        steps: &mut VecDeque<Transition<S>>,
+    ) {
+        // the action taken after a step, and the return that followed it
+        let mut following: Option<(&S::A, f64)> = None;
+        for step in steps.iter().rev() {
+            let actions = if step.terminal {
+                Vec::new()
+            } else {
+                step.next_state.actions()
+            };
+            let next_values: Vec<Option<f64>> = actions
+                .iter()
+                .map(|action| self.q.get(&step.next_state, action))
+                .collect();
+            let continuation = following.and_then(|(next_action, ret)| {
+                let next = actions.iter().position(|action| action == next_action)?;
+                Some((next, ret))
+            });
+            let ret = learning_strategy.backup(step.reward, &next_values, continuation);
+            following = Some((&step.action, ret));
+        }
+        let ret = following.map(|(_, ret)| ret);
+        if let (Some(step), Some(ret)) = (steps.pop_front(), ret) {
+            let v = learning_strategy.value(self.q.get(&step.state, &step.action), ret);
+            self.q.insert(step.state, step.action, v);
+        }
+    }
+
+    /// Learns the value of taking `action` in `state`, arriving in `next_state` with `reward`.
+    /// `terminal` is `true` when `next_state` is terminal.
+    pub(crate) fn learn(
+        &mut self,
+        learning_strategy: &dyn LearningStrategy<S>,
+        state: S,
+        action: S::A,
+        reward: f64,
+        next_state: &S,
+        terminal: bool,
+    ) {
+        let v = self.learned_value(
+            learning_strategy,
+            &state,
+            &action,
+            reward,
+            next_state,
+            terminal,
+        );
+        self.q.insert(state, action, v);
+    }
+
+    /// Calculates the newly learned value for taking `action` in `state`, arriving in
+    /// `next_state` with `reward`. `terminal` is `true` when `next_state` is terminal.
+    fn learned_value<L: LearningStrategy<S> + ?Sized>(
+        &self,
+        learning_strategy: &L,
+        state: &S,
+        action: &S::A,
+        reward: f64,
+        next_state: &S,
+        terminal: bool,
+    ) -> f64 {
+        let old_value = self.q.get(state, action);
+        let new_action_values = self.q.action_values(next_state);
+        learning_strategy.value(
+            state,
+            action,
+            &new_action_values.as_deref(),
+            &old_value.as_ref(),
+            reward,
+            terminal,
+        )
+    }
 }
 
+#[cfg(feature = "std")]
 impl<S: State> Default for AgentTrainer<S> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Cloning an `AgentTrainer` copies its learned values, so that the clone can be trained further
+/// without affecting the original, for example to branch experiments from a snapshot. The reward
+/// shaper and the state similarity are shared with the clone.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::line::{MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let train = |trainer: &mut AgentTrainer<MyState>| {
+///     let mut agent = MyAgent { state: MyState(0) };
+///     trainer.train(
+///         &mut agent,
+///         &QLearning::new(0.2, 0.01, 2.),
+///         &mut FixedIterations::new(1000),
+///         &RandomExploration::new(),
+///     );
+/// };
+///
+/// let mut trainer = AgentTrainer::new();
+/// train(&mut trainer);
+/// let mut snapshot = trainer.clone();
+/// assert!(snapshot == trainer);
+/// for x in -5..=5 {
+///     for action in [-1, 1] {
+///         let state = MyState(x);
+///         assert_eq!(snapshot.expected_value(&state, &action), trainer.expected_value(&state, &action));
+///     }
+/// }
+///
+/// // Training the clone does not change the original.
+/// let original = trainer.export_learned_values();
+/// train(&mut snapshot);
+/// assert!(snapshot != trainer);
+/// assert!(trainer.export_learned_values() == original);
+/// ```
+#[cfg(feature = "std")]
+impl<S, T> Clone for AgentTrainer<S, T>
+where
+    S: State,
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        AgentTrainer {
+            q: self.q.clone(),
+            stats: self.stats.clone(),
+            visits: self.visits.clone(),
+            normalizer: self.normalizer.clone(),
+            shaper: self.shaper.clone(),
+            similarity: self.similarity.clone(),
+            reward_clip: self.reward_clip,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Two `AgentTrainer`s are equal when their learned values are equal.
+#[cfg(feature = "std")]
+impl<S, T> PartialEq for AgentTrainer<S, T>
+where
+    S: State,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.q == other.q
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<S> AgentTrainer<S>
+where
+    S: State + serde::Serialize + serde::de::DeserializeOwned,
+    S::A: serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Saves the learned values to the file at `path` in the bincode format. Both `S` and
+    /// `S::A` must implement `serde::Serialize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let path = std::env::temp_dir().join("rurel_bincode_doctest.bin");
+    /// trainer.export_learned_values_to_bincode(&path).unwrap();
+    /// let mut loaded = AgentTrainer::new();
+    /// loaded.import_state_from_bincode(&path).unwrap();
+    /// for x in -5..=5 {
+    ///     for action in [-1, 1] {
+    ///         let state = MyState(x);
+    ///         assert_eq!(
+    ///             loaded.expected_value(&state, &action),
+    ///             trainer.expected_value(&state, &action)
+    ///         );
+    ///     }
+    /// }
+    /// ```
+    pub fn export_learned_values_to_bincode<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> bincode::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.export_learned_values_to_writer(std::io::BufWriter::new(file))
+    }
+
+    /// Imports learned values from the bincode file at `path`, completely replacing any learned
+    /// progress. Both `S` and `S::A` must implement `serde::Deserialize`.
+    pub fn import_state_from_bincode<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> bincode::Result<()> {
+        let file = std::fs::File::open(path)?;
+        self.import_state_from_reader(std::io::BufReader::new(file))
+    }
+
+    /// Writes the learned values to `writer` in the bincode format. This allows saving to
+    /// in-memory buffers, sockets or compressed streams.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// trainer.export_learned_values_to_writer(&mut buffer).unwrap();
+    /// let mut loaded = AgentTrainer::new();
+    /// loaded.import_state_from_reader(buffer.as_slice()).unwrap();
+    /// assert!(loaded.learned_values() == trainer.learned_values());
+    /// ```
+    pub fn export_learned_values_to_writer<W: std::io::Write>(
+        &self,
+        writer: W,
+    ) -> bincode::Result<()> {
+        bincode::serialize_into(writer, &self.q)
+    }
+
+    /// Reads learned values in the bincode format from `reader`, completely replacing any learned
+    /// progress.
+    pub fn import_state_from_reader<R: std::io::Read>(&mut self, reader: R) -> bincode::Result<()> {
+        self.q = bincode::deserialize_from(reader)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<S> AgentTrainer<S>
+where
+    S: State,
+{
+    /// Writes the learned values to `writer` as CSV, with one row per (state, action, value)
+    /// triple. Since states and actions are not inherently columnar, `state_columns` and
+    /// `action_columns` flatten them into columns, which are named by `state_headers` and
+    /// `action_headers` respectively. The value is written in a final `q_value` column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// let mut csv = Vec::new();
+    /// trainer
+    ///     .export_policy_to_csv(
+    ///         &mut csv,
+    ///         &["x", "y"],
+    ///         &["dx", "dy"],
+    ///         |s| vec![s.x.to_string(), s.y.to_string()],
+    ///         |a| vec![a.dx.to_string(), a.dy.to_string()],
+    ///     )
+    ///     .unwrap();
+    /// let csv = String::from_utf8(csv).unwrap();
+    /// let rows: usize = trainer.learned_values().values().map(|m| m.len()).sum();
+    /// assert_eq!(csv.lines().next(), Some("x,y,dx,dy,q_value"));
+    /// assert_eq!(csv.lines().count(), rows + 1);
+    /// ```
+    pub fn export_policy_to_csv<W, FS, FA>(
+        &self,
+        writer: W,
+        state_headers: &[&str],
+        action_headers: &[&str],
+        state_columns: FS,
+        action_columns: FA,
+    ) -> csv::Result<()>
+    where
+        W: std::io::Write,
+        FS: Fn(&S) -> Vec<String>,
+        FA: Fn(&S::A) -> Vec<String>,
+    {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(
+            state_headers
+                .iter()
+                .chain(action_headers)
+                .chain(std::iter::once(&"q_value")),
+        )?;
+        for (state, actions) in &self.q {
+            let state_record = state_columns(state);
+            for (action, value) in actions {
+                writer.write_record(
+                    state_record
+                        .iter()
+                        .cloned()
+                        .chain(action_columns(action))
+                        .chain(std::iter::once(value.to_string())),
+                )?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<S> AgentTrainer<S>
+where
+    S: State + Send + Sync,
+    S::A: Send + Sync,
+{
+    /// Trains this [AgentTrainer] using `workers` independent training runs in parallel. Every run
+    /// trains a clone of this trainer, with its learned values and its configuration such as
+    /// reward clipping, shaping and normalization, on a clone of `agent` using a clone of
+    /// `termination_strategy`. Afterwards, the learned values of all runs are combined using
+    /// `merge_strategy`, while the visit counts, the reward normalization statistics and the
+    /// [TrainingStats] of all runs are added up.
+    ///
+    /// Note that the result is not deterministic: the runs explore independently, and the merged
+    /// values depend on which state-action pairs each run happened to visit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use std::sync::{Arc, Mutex};
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::line::MyState;
+    /// // An agent that records all states it took an action from, shared between its clones.
+    /// #[derive(Clone)]
+    /// struct MyAgent {
+    ///     state: MyState,
+    ///     visited: Arc<Mutex<HashSet<MyState>>>,
+    /// }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &i32) {
+    /// #         self.visited.lock().unwrap().insert(self.state.clone());
+    /// #         self.state = MyState(self.state.0 + action)
+    /// #     }
+    /// # }
+    /// use rurel::{AgentTrainer, MergeStrategy};
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let visited = Arc::new(Mutex::new(HashSet::new()));
+    /// let agent = MyAgent { state: MyState(0), visited: visited.clone() };
+    /// let mut trainer = AgentTrainer::new();
+    /// trainer.train_parallel(
+    ///     4,
+    ///     &agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &FixedIterations::new(100),
+    ///     &RandomExploration::new(),
+    ///     MergeStrategy::Average,
+    /// );
+    ///
+    /// for state in visited.lock().unwrap().iter() {
+    ///     assert!(trainer.expected_values(state).is_some());
+    /// }
+    /// assert_eq!(trainer.last_stats().unwrap().steps, 400);
+    ///
+    /// // The runs keep the configuration of the trainer.
+    /// let mut trainer = AgentTrainer::new().with_reward_clip(-1., 1.);
+    /// trainer.train_parallel(
+    ///     4,
+    ///     &agent,
+    ///     &QLearning::new(1., 0., 0.),
+    ///     &FixedIterations::new(100),
+    ///     &RandomExploration::new(),
+    ///     MergeStrategy::Average,
+    /// );
+    /// assert!(trainer.iter_values().all(|(_, _, value)| (-1. ..=1.).contains(&value)));
+    /// ```
+    pub fn train_parallel<A, T>(
+        &mut self,
+        workers: usize,
+        agent: &A,
+        learning_strategy: &(dyn LearningStrategy<S> + Sync),
+        termination_strategy: &T,
+        exploration_strategy: &(dyn ExplorationStrategy<S> + Sync),
+        merge_strategy: MergeStrategy,
+    ) where
+        A: Agent<S> + Clone + Sync,
+        T: TerminationStrategy<S> + Clone + Sync,
+    {
+        use rayon::prelude::*;
+
+        let base: &AgentTrainer<S> = self;
+        let runs: Vec<AgentTrainer<S>> = (0..workers)
+            .into_par_iter()
+            .map(|_| {
+                let mut trainer = base.clone();
+                trainer.train(
+                    &mut agent.clone(),
+                    learning_strategy,
+                    &mut termination_strategy.clone(),
+                    exploration_strategy,
+                );
+                trainer
+            })
+            .collect();
+
+        // every run starts with the visits and rewards observed so far
+        let visits_before = self.visits.clone();
+        let normalizer_before = self.normalizer.clone();
+        let mut stats = TrainingStats::new();
+        let mut tables = Vec::with_capacity(runs.len());
+        for run in runs {
+            if let (Some(visits), Some(before), Some(run_visits)) =
+                (&mut self.visits, &visits_before, &run.visits)
+            {
+                for (state, &count) in run_visits {
+                    let earlier = before.get(state).copied().unwrap_or(0);
+                    *visits.entry(state.clone()).or_default() += count - earlier;
+                }
+            }
+            if let (Some(normalizer), Some(before), Some(run_normalizer)) =
+                (&mut self.normalizer, &normalizer_before, &run.normalizer)
+            {
+                normalizer.merge(&run_normalizer.since(before));
+            }
+            if let Some(run_stats) = &run.stats {
+                stats.merge(run_stats);
+            }
+            tables.push(run.q);
+        }
+        self.q = merge_tables(tables, merge_strategy);
+        self.stats = Some(stats);
+    }
+}
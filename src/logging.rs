@@ -0,0 +1,75 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module describing the progress messages that are logged during training when the `logging`
+//! feature is enabled. Messages are emitted through the [log](https://docs.rs/log) crate, so any
+//! logger implementation can be used to show or record them. Without the feature, no logging code
+//! is compiled in.
+//!
+//! The following messages are logged:
+//!
+//! * At the `info` level, at the end of every training run of an
+//!   [AgentTrainer](../struct.AgentTrainer.html) or a `DQNAgentTrainer`: the number of steps, the
+//!   steps per second, and for the `AgentTrainer` the total reward.
+//! * At the `debug` level, every [LOG_INTERVAL] steps: the steps per second so far, and for the
+//!   `AgentTrainer` the mean learned value, and the exploration parameter when training with a
+//!   schedule.
+//! * At the `debug` level, for every batch a `DQNAgentTrainer` is trained on: the loss and the
+//!   mean Q-value of the last gradient step.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rurel::fixtures::line::{MyAgent, MyState};
+//! use std::sync::Mutex;
+//!
+//! use rurel::AgentTrainer;
+//! use rurel::strategy::explore::RandomExploration;
+//! use rurel::strategy::learn::QLearning;
+//! use rurel::strategy::terminate::FixedIterations;
+//!
+//! // A logger that captures all messages.
+//! struct CapturingLogger(Mutex<Vec<String>>);
+//!
+//! impl log::Log for CapturingLogger {
+//!     fn enabled(&self, _metadata: &log::Metadata) -> bool { true }
+//!     fn log(&self, record: &log::Record) {
+//!         self.0.lock().unwrap().push(format!("{}", record.args()));
+//!     }
+//!     fn flush(&self) {}
+//! }
+//!
+//! static LOGGER: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+//! log::set_logger(&LOGGER).unwrap();
+//! log::set_max_level(log::LevelFilter::Debug);
+//!
+//! let mut trainer = AgentTrainer::new();
+//! let mut agent = MyAgent { state: MyState(0) };
+//! trainer.train(
+//!     &mut agent,
+//!     &QLearning::new(0.2, 0.01, 2.),
+//!     &mut FixedIterations::new(20000),
+//!     &RandomExploration::new(),
+//! );
+//!
+//! let messages = LOGGER.0.lock().unwrap();
+//! assert!(messages.iter().any(|m| m.contains("mean learned value")));
+//! assert!(messages.iter().any(|m| m.starts_with("trained 20000 steps")));
+//! ```
+
+use std::time::Instant;
+
+/// The number of steps between two progress messages at the `debug` level.
+pub const LOG_INTERVAL: u64 = 10_000;
+
+/// Returns the number of steps per second, for the given number of steps taken since `started`.
+pub(crate) fn steps_per_sec(steps: u64, started: Instant) -> f64 {
+    steps as f64 / started.elapsed().as_secs_f64()
+}
+
+/// Returns the mean of the given values, or NaN if there are none.
+pub(crate) fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| (sum + value, count + 1));
+    sum / count as f64
+}
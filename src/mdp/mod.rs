@@ -4,6 +4,10 @@
 
 use std::hash::Hash;
 
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
 /// A `State` is something which has a reward, and has a certain set of actions associated with it.
 /// The type of the actions must be defined as the associated type `A`.
 pub trait State: Eq + Hash + Clone {
@@ -12,15 +16,235 @@ pub trait State: Eq + Hash + Clone {
 
     /// The reward for when an `Agent` arrives at this `State`.
     fn reward(&self) -> f64;
-    /// The set of actions that can be taken from this `State`, to arrive in another `State`.
+    /// The reward for taking the given action from this `State` and arriving at `next`. The
+    /// default implementation returns the reward of `next`. Override it when the reward depends on
+    /// the transition, for example to add a cost to certain actions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyAction { dx: i32, dy: i32 }
+    /// # struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, action: &MyAction) {
+    /// #         self.state = MyState {
+    /// #             x: (((self.state.x + action.dx) % 21) + 21) % 21,
+    /// #             y: (((self.state.y + action.dy) % 21) + 21) % 21,
+    /// #             diagonal_cost: self.state.diagonal_cost,
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState { x: i32, y: i32, diagonal_cost: i32 }
+    ///
+    /// impl State for MyState {
+    ///     type A = MyAction;
+    ///     fn reward(&self) -> f64 {
+    ///         -((((10 - self.x).pow(2) + (10 - self.y).pow(2)) as f64).sqrt())
+    ///     }
+    ///     fn transition_reward(&self, action: &MyAction, next: &Self) -> f64 {
+    ///         let cost = if action.dx != 0 && action.dy != 0 { self.diagonal_cost } else { 0 };
+    ///         next.reward() - cost as f64
+    ///     }
+    ///     fn actions(&self) -> Vec<MyAction> {
+    ///         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+    ///              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 },
+    ///              MyAction { dx: 1, dy: 1 }]
+    ///     }
+    /// }
+    ///
+    /// let diagonal = MyAction { dx: 1, dy: 1 };
+    /// for diagonal_cost in [0, 3] {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = MyAgent { state: MyState { x: 0, y: 0, diagonal_cost } };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(100000),
+    ///         &RandomExploration::new(),
+    ///     );
+    ///     // The diagonal leads straight to the target, unless it costs more than a detour.
+    ///     let best = trainer.best_action(&MyState { x: 9, y: 9, diagonal_cost });
+    ///     assert_eq!(best == Some(diagonal.clone()), diagonal_cost == 0);
+    /// }
+    /// ```
+    fn transition_reward(&self, _action: &Self::A, next: &Self) -> f64 {
+        next.reward()
+    }
+    /// The set of actions that can be taken from this `State`, to arrive in another `State`. The
+    /// set may differ per `State`, to forbid actions that are not allowed in certain states. The
+    /// provided exploration strategies only take actions from this set, and the trainers only
+    /// learn and choose among the actions that were taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(i32);
+    ///
+    /// impl State for MyState {
+    ///     type A = i32;
+    ///     fn reward(&self) -> f64 { self.0 as f64 }
+    ///     // Moving up is not allowed from the top.
+    ///     fn actions(&self) -> Vec<i32> {
+    ///         if self.0 == 5 { vec![-1] } else { vec![-1, 1] }
+    ///     }
+    /// }
+    ///
+    /// struct MyAgent { state: MyState }
+    ///
+    /// impl Agent<MyState> for MyAgent {
+    ///     fn current_state(&self) -> &MyState { &self.state }
+    ///     fn take_action(&mut self, action: &i32) {
+    ///         assert!(self.state.actions().contains(action));
+    ///         self.state = MyState((self.state.0 + action).max(0));
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    /// assert_eq!(trainer.best_action(&MyState(4)), Some(1));
+    /// assert_eq!(trainer.best_action(&MyState(5)), Some(-1));
+    /// assert_eq!(trainer.expected_value(&MyState(5), &1), None);
+    /// ```
     fn actions(&self) -> Vec<Self::A>;
-    /// Selects a random action that can be taken from this `State`. The default implementation
-    /// takes a uniformly distributed random action from the defined set of actions. You may want
-    /// to improve the performance by only generating the necessary action.
-    fn random_action(&self) -> Self::A {
+    /// The weights with which random actions are selected from this `State`, in the order of
+    /// [actions](State::actions), or `None` to select them uniformly. An action is selected with a
+    /// probability proportional to its weight, which lets prior knowledge about good actions bias
+    /// random exploration. The weights must not be negative, and not all be zero. The default
+    /// implementation returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState;
+    ///
+    /// impl State for MyState {
+    ///     type A = usize;
+    ///     fn reward(&self) -> f64 { 0. }
+    ///     fn actions(&self) -> Vec<usize> { vec![0, 1, 2] }
+    ///     fn action_weights(&self) -> Option<Vec<f64>> { Some(vec![8., 1., 1.]) }
+    /// }
+    ///
+    /// let mut counts = [0; 3];
+    /// for _ in 0..10000 {
+    ///     counts[MyState.random_action().unwrap()] += 1;
+    /// }
+    /// assert!((7500..8500).contains(&counts[0]));
+    /// assert!(counts[0] > 5 * (counts[1] + counts[2]) / 2);
+    /// ```
+    fn action_weights(&self) -> Option<Vec<f64>> {
+        None
+    }
+    /// A compact id of this `State`, or `None` to identify it by the full `State`. An
+    /// [IdTable](../table/struct.IdTable.html) stores the learned values of `State`s with an id
+    /// keyed on the id, which avoids repeatedly hashing and comparing `State`s that are expensive
+    /// to hash. Different `State`s must have different ids. The default implementation returns
+    /// `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    ///
+    /// // A board of 16 cells, each holding one of 4 pieces.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Board { cells: Vec<u8> }
+    ///
+    /// impl State for Board {
+    ///     type A = (usize, u8);
+    ///     fn reward(&self) -> f64 { 0. }
+    ///     fn actions(&self) -> Vec<(usize, u8)> {
+    ///         (0..16).flat_map(|cell| (0..4).map(move |piece| (cell, piece))).collect()
+    ///     }
+    ///     fn id(&self) -> Option<u64> {
+    ///         // Every cell takes 2 bits.
+    ///         Some(self.cells.iter().fold(0, |id, &piece| id << 2 | piece as u64))
+    ///     }
+    /// }
+    ///
+    /// let empty = Board { cells: vec![0; 16] };
+    /// let mut one = empty.clone();
+    /// one.cells[15] = 1;
+    /// assert_eq!(empty.id(), Some(0));
+    /// assert_eq!(one.id(), Some(1));
+    /// ```
+    fn id(&self) -> Option<u64> {
+        None
+    }
+    /// Selects a random action that can be taken from this `State`, or `None` if no actions can be
+    /// taken. The default implementation takes a random action from the defined set of actions,
+    /// like [random_action_with](State::random_action_with). You may want to improve the
+    /// performance by only generating the necessary action.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::mdp::State;
+    ///
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct MyState(usize);
+    ///
+    /// impl State for MyState {
+    ///     type A = usize;
+    ///     fn reward(&self) -> f64 { 0. }
+    ///     fn actions(&self) -> Vec<usize> { (0..self.0).collect() }
+    /// }
+    ///
+    /// let mut counts = [0; 3];
+    /// for _ in 0..30000 {
+    ///     counts[MyState(3).random_action().unwrap()] += 1;
+    /// }
+    /// assert!(counts.iter().all(|&count| (9500..10500).contains(&count)));
+    ///
+    /// assert_eq!(MyState(0).random_action(), None);
+    /// ```
+    fn random_action(&self) -> Option<Self::A> {
+        self.random_action_with(&mut rand::thread_rng())
+    }
+    /// Selects a random action that can be taken from this `State` using the given random number
+    /// generator, or `None` if no actions can be taken. The default implementation takes a
+    /// random action from the defined set of actions, weighted by the
+    /// [action weights](State::action_weights) if there are any, and uniformly distributed
+    /// otherwise.
+    fn random_action_with(&self, rng: &mut dyn RngCore) -> Option<Self::A> {
         let actions = self.actions();
-        let a_t = rand::random::<usize>() % actions.len();
-        actions[a_t].clone()
+        if let [action] = actions.as_slice() {
+            return Some(action.clone());
+        }
+        match self.action_weights() {
+            Some(weights) if !actions.is_empty() => {
+                debug_assert_eq!(weights.len(), actions.len(), "an action has no weight");
+                let index = WeightedIndex::new(weights)
+                    .expect("invalid action weights")
+                    .sample(rng);
+                Some(actions[index].clone())
+            }
+            _ => actions.choose(rng).cloned(),
+        }
     }
 }
 
@@ -31,12 +255,123 @@ pub trait Agent<S: State> {
     fn current_state(&self) -> &S;
     /// Takes the given action, possibly mutating the current `State`.
     fn take_action(&mut self, action: &S::A);
-    /// Takes a random action from the set of possible actions from this `State`. The default
-    /// implementation uses [State::random_action()](trait.State.html#method.random_action) to
-    /// determine the action to be taken.
-    fn pick_random_action(&mut self) -> S::A {
-        let action = self.current_state().random_action();
+    /// Returns `true` if the current `State` of this `Agent` is terminal, such as a goal that was
+    /// reached. No future rewards follow a terminal state, so the trainers do not bootstrap from
+    /// its learned values, even though it may still have actions. The default implementation
+    /// returns `false`, in which case only sink states (states without actions) are terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState(i32);
+    /// # impl State for MyState {
+    /// #     type A = i32;
+    /// #     fn reward(&self) -> f64 { if self.0 == 3 { 1. } else { 0. } }
+    /// #     fn actions(&self) -> Vec<i32> { vec![-1, 1] }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A walk between 0 and 3, where 3 is the goal.
+    /// struct MyAgent { state: MyState }
+    ///
+    /// impl Agent<MyState> for MyAgent {
+    ///     fn current_state(&self) -> &MyState { &self.state }
+    ///     fn take_action(&mut self, action: &i32) {
+    ///         self.state = MyState((self.state.0 + action).clamp(0, 3))
+    ///     }
+    ///     fn is_terminal(&self) -> bool { self.state.0 == 3 }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(1., 0.9, 10.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// // Arriving at the goal is only worth its reward, not the initial value of the goal.
+    /// assert_eq!(trainer.expected_value(&MyState(2), &1), Some(1.));
+    /// assert_eq!(trainer.expected_value(&MyState(3), &1), Some(1.));
+    /// ```
+    fn is_terminal(&self) -> bool {
+        false
+    }
+    /// Returns the reward received for the last action taken, or `None` to use the reward of the
+    /// transition as determined by the `State`, which is
+    /// [State::transition_reward()](trait.State.html#method.transition_reward). This lets an
+    /// `Agent` supply rewards that its `State` cannot encode, such as rewards returned by the step
+    /// function of a simulator. The default implementation returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::line::MyState;
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // A simulator that rewards the distance from 0, where the `State` rewards being close to it.
+    /// struct MyAgent { state: MyState, reward: Option<f64> }
+    ///
+    /// impl Agent<MyState> for MyAgent {
+    ///     fn current_state(&self) -> &MyState { &self.state }
+    ///     fn take_action(&mut self, action: &i32) {
+    ///         self.state = MyState((self.state.0 + action).clamp(-5, 5));
+    ///         self.reward = Some(self.state.0.abs() as f64);
+    ///     }
+    ///     fn observe_reward(&self) -> Option<f64> { self.reward }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0), reward: None };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(10000),
+    ///     &RandomExploration::new(),
+    /// );
+    ///
+    /// // The learned policy moves away from 0, following the rewards of the simulator.
+    /// assert_eq!(trainer.best_action(&MyState(3)), Some(1));
+    /// assert_eq!(trainer.best_action(&MyState(-3)), Some(-1));
+    /// ```
+    fn observe_reward(&self) -> Option<f64> {
+        None
+    }
+    /// Takes a random action from the set of possible actions from this `State`, or returns `None`
+    /// without taking an action if no actions can be taken. The default implementation uses
+    /// [State::random_action()](trait.State.html#method.random_action) to determine the action to
+    /// be taken.
+    fn pick_random_action(&mut self) -> Option<S::A> {
+        let action = self.current_state().random_action()?;
+        self.take_action(&action);
+        Some(action)
+    }
+    /// Takes a random action from the set of possible actions from this `State` using the given
+    /// random number generator, or returns `None` without taking an action if no actions can be
+    /// taken. The default implementation uses
+    /// [State::random_action_with()](trait.State.html#method.random_action_with) to determine the
+    /// action to be taken.
+    fn pick_random_action_with(&mut self, rng: &mut dyn RngCore) -> Option<S::A> {
+        let action = self.current_state().random_action_with(rng)?;
         self.take_action(&action);
-        action
+        Some(action)
     }
 }
+
+/// An [Agent] that can return to the start of a new episode by itself, so that it can be trained
+/// for many episodes without being recreated. See
+/// [AgentTrainer::train_for_episodes](../struct.AgentTrainer.html#method.train_for_episodes).
+pub trait ResettableAgent<S: State>: Agent<S> {
+    /// Resets this `Agent` to an initial `State`, which may be chosen at random.
+    fn reset(&mut self);
+}
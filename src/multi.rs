@@ -0,0 +1,149 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing training of multiple agents that take turns.
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::LearningStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+use crate::{is_terminal, AgentTrainer};
+
+/// A `MultiAgentTrainer` trains one [AgentTrainer](../struct.AgentTrainer.html) per player of a
+/// turn-based process, such as a two-player game. The [Agent](../mdp/trait.Agent.html) represents
+/// the whole process, and its `State` determines which player is to move.
+///
+/// Rewards are attributed per player: after every move, each player receives the reward of the
+/// resulting `State` from its own perspective, as computed by the `reward` function passed to
+/// [train](MultiAgentTrainer::train). The rewards a player receives between two of its turns are
+/// summed, so a player is also rewarded or punished for the moves of the other players that
+/// follow its own move. A player learns the value of its move once it is its turn again, or when
+/// training ends.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A hunter (player 0) and a prey (player 1) take turns moving on a line of 5 cells.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Game { positions: [i32; 2], turn: usize }
+///
+/// impl State for Game {
+///     type A = i32;
+///     fn reward(&self) -> f64 { 0. }
+///     fn actions(&self) -> Vec<i32> {
+///         if self.positions[0] == self.positions[1] { vec![] } else { vec![-1, 1] }
+///     }
+/// }
+/// # struct GameAgent { state: Game }
+/// # impl Agent<Game> for GameAgent {
+/// #     fn current_state(&self) -> &Game { &self.state }
+/// #     fn take_action(&mut self, action: &i32) {
+/// #         let mut positions = self.state.positions;
+/// #         positions[self.state.turn] = (positions[self.state.turn] + action).clamp(0, 4);
+/// #         self.state = Game { positions, turn: 1 - self.state.turn };
+/// #     }
+/// # }
+/// use rurel::multi::MultiAgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// let mut trainer = MultiAgentTrainer::new(2);
+/// for _ in 0..100 {
+///     let mut agent = GameAgent { state: Game { positions: [0, 4], turn: 0 } };
+///     trainer.train(
+///         &mut agent,
+///         &|state| state.turn,
+///         // Capturing is worth 1 to the hunter and -1 to the prey.
+///         &|state, player| match (state.positions[0] == state.positions[1], player) {
+///             (true, 0) => 1.,
+///             (true, _) => -1.,
+///             (false, _) => 0.,
+///         },
+///         &QLearning::new(0.2, 0.9, 0.),
+///         &mut SinkStates {},
+///         &RandomExploration::new(),
+///     );
+/// }
+///
+/// assert!(trainer.trainer(0).num_states() > 0);
+/// assert!(trainer.trainer(1).num_states() > 0);
+/// // The hunter only learns in states where it is to move, and so does the prey.
+/// assert!(trainer.trainer(0).iter_values().all(|(state, _, _)| state.turn == 0));
+/// assert!(trainer.trainer(1).iter_values().all(|(state, _, _)| state.turn == 1));
+/// ```
+pub struct MultiAgentTrainer<S>
+where
+    S: State,
+{
+    trainers: Vec<AgentTrainer<S>>,
+}
+
+impl<S> MultiAgentTrainer<S>
+where
+    S: State,
+{
+    /// Creates a `MultiAgentTrainer` for the given number of players.
+    pub fn new(players: usize) -> MultiAgentTrainer<S> {
+        MultiAgentTrainer {
+            trainers: (0..players).map(|_| AgentTrainer::new()).collect(),
+        }
+    }
+
+    /// Returns the trainer holding the values learned by the given player.
+    pub fn trainer(&self, player: usize) -> &AgentTrainer<S> {
+        &self.trainers[player]
+    }
+
+    /// Returns the trainers of all players, indexed by player.
+    pub fn trainers(&self) -> &[AgentTrainer<S>] {
+        &self.trainers
+    }
+
+    /// Trains all players by letting them take turns. `player` returns the player that is to move
+    /// in a `State`, and `reward` returns the reward of arriving in a `State` for a player.
+    pub fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        player: &dyn Fn(&S) -> usize,
+        reward: &dyn Fn(&S, usize) -> f64,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        // the last move of every player, with the rewards it received since
+        let mut pending: Vec<Option<(S, S::A, f64)>> = vec![None; self.trainers.len()];
+        loop {
+            let s_t = agent.current_state().clone();
+            let p = player(&s_t);
+            if let Some((state, action, r)) = pending[p].take() {
+                let terminal = is_terminal(agent);
+                self.trainers[p].learn(learning_strategy, state, action, r, &s_t, terminal);
+            }
+
+            let action = exploration_strategy.pick_action(agent);
+            let s_t_next = agent.current_state();
+
+            for (i, pending) in pending.iter_mut().enumerate() {
+                if let Some((_, _, r)) = pending {
+                    *r += reward(s_t_next, i);
+                }
+            }
+            let r_t_next = reward(s_t_next, p);
+            let stopped = termination_strategy.should_stop_full(s_t_next, Some(&action), r_t_next);
+            pending[p] = Some((s_t, action, r_t_next));
+
+            if stopped {
+                let terminal = is_terminal(agent);
+                for (trainer, pending) in self.trainers.iter_mut().zip(pending) {
+                    if let Some((state, action, r)) = pending {
+                        trainer.learn(learning_strategy, state, action, r, s_t_next, terminal);
+                    }
+                }
+                break;
+            }
+        }
+    }
+}
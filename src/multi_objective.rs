@@ -0,0 +1,238 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for multi-objective learning, where a state has a reward for each of `N` objectives.
+//! The rewards are learned separately as vectors of values, and are only combined into a single
+//! value by a scalarization function to choose between actions. This way, the objectives can be
+//! kept apart during learning and weighed against each other afterwards.
+//!
+//! This module is enabled with the `multi_objective` feature, and does not change the scalar
+//! [AgentTrainer](../struct.AgentTrainer.html).
+
+use std::collections::HashMap;
+
+use crate::compare_values;
+use crate::is_terminal;
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// A `State` with a reward for each of `N` objectives. The scalar
+/// [reward](../mdp/trait.State.html#tymethod.reward) of the `State` is not used by the
+/// [MultiObjectiveTrainer].
+pub trait MultiObjectiveState<const N: usize>: State {
+    /// The rewards for when an `Agent` arrives at this `State`, one for each objective.
+    fn rewards(&self) -> [f64; N];
+
+    /// The rewards for taking the given action from this `State` and arriving at `next`, one for
+    /// each objective. The default implementation returns the rewards of `next`.
+    fn transition_rewards(&self, _action: &Self::A, next: &Self) -> [f64; N] {
+        next.rewards()
+    }
+}
+
+/// Combines the values of the objectives into a single value.
+type Scalarization<const N: usize> = dyn Fn(&[f64; N]) -> f64 + Send + Sync;
+
+/// Returns the scalarization that weighs the values of the objectives by the given weights, and
+/// sums them.
+pub fn linear_scalarization<const N: usize>(
+    weights: [f64; N],
+) -> impl Fn(&[f64; N]) -> f64 + Send + Sync + 'static {
+    move |values| values.iter().zip(weights.iter()).map(|(v, w)| v * w).sum()
+}
+
+/// The Q Learning strategy for vectors of values. Every objective is updated towards its reward
+/// and the discounted value of the best action in the new state, where the best action is chosen
+/// by the scalarization of the trainer.
+pub struct MultiObjectiveQLearning {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+}
+
+impl MultiObjectiveQLearning {
+    /// Constructs the multi-objective Q Learning strategy, with learning rate `alpha`, discount
+    /// factor `gamma` and the initial value of every objective `initial_value`.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64) -> MultiObjectiveQLearning {
+        MultiObjectiveQLearning {
+            alpha,
+            gamma,
+            initial_value,
+        }
+    }
+
+    /// Calculates the learned values of the action which was taken, from the current values
+    /// (`current_values`), the values of the best action in the new state (`best_next_values`),
+    /// and the rewards that were received after taking the action. When the new state is
+    /// `terminal`, it is not bootstrapped from.
+    fn values<const N: usize>(
+        &self,
+        current_values: Option<&[f64; N]>,
+        best_next_values: Option<&[f64; N]>,
+        rewards: [f64; N],
+        terminal: bool,
+    ) -> [f64; N] {
+        let Some(current_values) = current_values else {
+            return [self.initial_value; N];
+        };
+        let mut values = *current_values;
+        for (i, value) in values.iter_mut().enumerate() {
+            let next = if terminal {
+                0.
+            } else {
+                best_next_values.map_or(self.initial_value, |v| v[i])
+            };
+            *value = (*value + self.alpha * (rewards[i] + self.gamma * next - *value))
+                .clamp(f64::MIN, f64::MAX);
+        }
+        values
+    }
+}
+
+/// A `MultiObjectiveTrainer` learns a vector of values, one for each of `N` objectives, for
+/// every action in every `State`. The given scalarization combines the values of an action into
+/// a single value, which is used to choose the best action, both during learning and when
+/// querying the trainer.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// # impl State for MyState {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 { 0. }
+/// #     fn actions(&self) -> Vec<i32> { vec![-1, 1] }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &i32) {
+/// #         self.state = MyState((self.state.0 + action).clamp(-5, 5))
+/// #     }
+/// # }
+/// use rurel::multi_objective::{
+///     linear_scalarization, MultiObjectiveQLearning, MultiObjectiveState, MultiObjectiveTrainer,
+/// };
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// // One objective rewards being far to the left, and the other being far to the right.
+/// impl MultiObjectiveState<2> for MyState {
+///     fn rewards(&self) -> [f64; 2] { [-self.0 as f64, self.0 as f64] }
+/// }
+///
+/// let train = |weights: [f64; 2]| {
+///     let mut trainer = MultiObjectiveTrainer::new(linear_scalarization(weights));
+///     let mut agent = MyAgent { state: MyState(0) };
+///     trainer.train(
+///         &mut agent,
+///         &MultiObjectiveQLearning::new(0.2, 0.5, 0.),
+///         &mut FixedIterations::new(10000),
+///         &RandomExploration::new(),
+///     );
+///     trainer
+/// };
+///
+/// // The weights of the objectives decide which way to go.
+/// let left = train([0.8, 0.2]);
+/// let right = train([0.2, 0.8]);
+/// for x in -4..=4 {
+///     assert_eq!(left.best_action(&MyState(x)), Some(-1));
+///     assert_eq!(right.best_action(&MyState(x)), Some(1));
+/// }
+/// ```
+pub struct MultiObjectiveTrainer<S, const N: usize>
+where
+    S: MultiObjectiveState<N>,
+{
+    q: HashMap<S, HashMap<S::A, [f64; N]>>,
+    scalarization: Box<Scalarization<N>>,
+}
+
+impl<S, const N: usize> MultiObjectiveTrainer<S, N>
+where
+    S: MultiObjectiveState<N>,
+{
+    /// Creates a `MultiObjectiveTrainer` that chooses between actions using the given
+    /// scalarization of their values, such as a [linear_scalarization].
+    pub fn new(
+        scalarization: impl Fn(&[f64; N]) -> f64 + Send + Sync + 'static,
+    ) -> MultiObjectiveTrainer<S, N> {
+        MultiObjectiveTrainer {
+            q: HashMap::new(),
+            scalarization: Box::new(scalarization),
+        }
+    }
+
+    /// Replaces the scalarization that is used to choose between actions. The learned values are
+    /// kept, but were learned while following the best actions of the previous scalarization.
+    pub fn set_scalarization(
+        &mut self,
+        scalarization: impl Fn(&[f64; N]) -> f64 + Send + Sync + 'static,
+    ) {
+        self.scalarization = Box::new(scalarization);
+    }
+
+    /// Fetches the learned values for the given state, by `Action`, or `None` if no value was
+    /// learned.
+    pub fn expected_values(&self, state: &S) -> Option<&HashMap<S::A, [f64; N]>> {
+        self.q.get(state)
+    }
+
+    /// Fetches the learned values of every objective for the given `Action` in the given `State`,
+    /// or `None` if no value was learned.
+    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<[f64; N]> {
+        self.q.get(state).and_then(|m| m.get(action)).copied()
+    }
+
+    /// Returns the learned values of the best action for the given `State` according to the
+    /// scalarization, or `None` if no values were learned.
+    fn best_values(&self, state: &S) -> Option<(&S::A, &[f64; N])> {
+        self.q.get(state).and_then(|m| {
+            m.iter().max_by(|(_, v1), (_, v2)| {
+                compare_values((self.scalarization)(v1), (self.scalarization)(v2))
+            })
+        })
+    }
+
+    /// Returns the best action for the given `State` according to the scalarization, or `None`
+    /// if no values were learned.
+    pub fn best_action(&self, state: &S) -> Option<S::A> {
+        self.best_values(state).map(|(action, _)| action.clone())
+    }
+
+    /// Trains this [MultiObjectiveTrainer] using the given [ExplorationStrategy],
+    /// [MultiObjectiveQLearning] and [Agent] until the [TerminationStrategy] decides to stop.
+    pub fn train(
+        &mut self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &MultiObjectiveQLearning,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+    ) {
+        let mut step = 0;
+        loop {
+            let s_t = agent.current_state().clone();
+            let action = exploration_strategy.pick_action_at(agent, step, 0);
+            step += 1;
+
+            let s_t_next = agent.current_state();
+            let rewards = s_t.transition_rewards(&action, s_t_next);
+            let values = learning_strategy.values(
+                self.expected_value(&s_t, &action).as_ref(),
+                self.best_values(s_t_next).map(|(_, values)| values),
+                rewards,
+                is_terminal(agent),
+            );
+            self.q.entry(s_t).or_default().insert(action, values);
+
+            if termination_strategy.should_stop(agent.current_state()) {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing reward and state normalization.
+
+/// Standardizes rewards using the running mean and variance of all observed rewards, so that
+/// learning is not destabilized by rewards of a large magnitude.
+///
+/// # Examples
+///
+/// ```
+/// use rurel::normalize::RewardNormalizer;
+///
+/// let mut normalizer = RewardNormalizer::new();
+/// for reward in [10., 20., 30.] {
+///     normalizer.observe(reward);
+/// }
+/// assert_eq!(normalizer.mean(), 20.);
+/// assert_eq!(normalizer.normalize(20.), 0.);
+/// assert_eq!(normalizer.normalize(30.), 1.);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct RewardNormalizer {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl RewardNormalizer {
+    pub fn new() -> RewardNormalizer {
+        RewardNormalizer {
+            count: 0,
+            mean: 0.,
+            m2: 0.,
+        }
+    }
+
+    /// Updates the running mean and variance with the given reward.
+    pub fn observe(&mut self, reward: f64) {
+        self.count += 1;
+        let delta = reward - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (reward - self.mean);
+    }
+
+    /// The mean of all observed rewards.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// The sample standard deviation of all observed rewards, or 1 if it is not known yet.
+    pub fn std_dev(&self) -> f64 {
+        if self.count < 2 || self.m2 == 0. {
+            1.
+        } else {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        }
+    }
+
+    /// Standardizes the given reward using the rewards observed so far.
+    pub fn normalize(&self, reward: f64) -> f64 {
+        (reward - self.mean) / self.std_dev()
+    }
+
+    /// Adds the rewards observed by `other` to the rewards observed by this normalizer.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn merge(&mut self, other: &RewardNormalizer) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    /// Returns a normalizer of the rewards this normalizer observed after it was a clone of
+    /// `earlier`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn since(&self, earlier: &RewardNormalizer) -> RewardNormalizer {
+        let count = self.count - earlier.count;
+        if count == 0 {
+            return RewardNormalizer::new();
+        }
+        let mean =
+            (self.mean * self.count as f64 - earlier.mean * earlier.count as f64) / count as f64;
+        let delta = mean - earlier.mean;
+        let m2 = self.m2
+            - earlier.m2
+            - delta * delta * (earlier.count * count) as f64 / self.count as f64;
+        RewardNormalizer {
+            count,
+            mean,
+            m2: m2.max(0.),
+        }
+    }
+}
+
+impl Default for RewardNormalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The type of the features of a state that a [StateNormalizer] normalizes: `f32` or `f64`. The
+/// statistics are kept in `f64` either way.
+pub trait Feature: Copy {
+    /// Converts this feature to `f64`.
+    fn to_f64(self) -> f64;
+    /// Converts the given value to a feature, rounding it if needed.
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Feature for f32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl Feature for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+/// Standardizes every feature of a state of `N` features using the running mean and variance of
+/// that feature over all observed states. Unlike standardizing every state on its own, the same
+/// state is always mapped to the same input, so states remain comparable to each other.
+///
+/// The statistics can be saved and restored with [count](StateNormalizer::count),
+/// [mean](StateNormalizer::mean), [variance](StateNormalizer::variance) and
+/// [from_statistics](StateNormalizer::from_statistics).
+///
+/// # Examples
+///
+/// ```
+/// use rurel::normalize::StateNormalizer;
+///
+/// let mut normalizer = StateNormalizer::new();
+/// for state in [[10., 1.], [20., 1.], [30., 1.]] {
+///     normalizer.observe(&state);
+/// }
+/// assert_eq!(normalizer.mean(), [20., 1.]);
+/// assert_eq!(normalizer.normalize([30., 1.]), [1., 0.]);
+///
+/// let restored =
+///     StateNormalizer::from_statistics(normalizer.count(), normalizer.mean(), &normalizer.variance());
+/// assert_eq!(restored, normalizer);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateNormalizer<const N: usize> {
+    count: u64,
+    // kept on the heap, so large states do not overflow the stack
+    mean: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl<const N: usize> StateNormalizer<N> {
+    pub fn new() -> StateNormalizer<N> {
+        StateNormalizer {
+            count: 0,
+            mean: vec![0.; N],
+            m2: vec![0.; N],
+        }
+    }
+
+    /// Creates a `StateNormalizer` from the number of observed states, and the mean and sample
+    /// variance of every feature, as returned by [count](StateNormalizer::count),
+    /// [mean](StateNormalizer::mean) and [variance](StateNormalizer::variance).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mean` or `variance` does not have `N` values.
+    pub fn from_statistics(count: u64, mean: &[f64], variance: &[f64]) -> StateNormalizer<N> {
+        assert!(
+            mean.len() == N && variance.len() == N,
+            "the statistics do not have a value for every feature"
+        );
+        StateNormalizer {
+            count,
+            mean: mean.to_vec(),
+            m2: variance
+                .iter()
+                .map(|v| v * count.saturating_sub(1) as f64)
+                .collect(),
+        }
+    }
+
+    /// Updates the running mean and variance of every feature with the given state.
+    pub fn observe<F: Feature>(&mut self, state: &[F; N]) {
+        self.count += 1;
+        for (i, &x) in state.iter().enumerate() {
+            let x = x.to_f64();
+            let delta = x - self.mean[i];
+            self.mean[i] += delta / self.count as f64;
+            self.m2[i] += delta * (x - self.mean[i]);
+        }
+    }
+
+    /// The number of observed states.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The mean of every feature over all observed states.
+    pub fn mean(&self) -> &[f64] {
+        &self.mean
+    }
+
+    /// The sample variance of every feature over all observed states, or 0 if it is not known
+    /// yet.
+    pub fn variance(&self) -> Vec<f64> {
+        if self.count < 2 {
+            vec![0.; N]
+        } else {
+            self.m2
+                .iter()
+                .map(|m2| m2 / (self.count - 1) as f64)
+                .collect()
+        }
+    }
+
+    /// The sample standard deviation of every feature over all observed states, or 1 for a
+    /// feature whose standard deviation is not known yet or is 0.
+    pub fn std_dev(&self) -> Vec<f64> {
+        self.variance()
+            .into_iter()
+            .map(|v| if v == 0. { 1. } else { v.sqrt() })
+            .collect()
+    }
+
+    /// Standardizes every feature of the given state using the states observed so far. Before any
+    /// state is observed, the state is returned as is.
+    pub fn normalize<F: Feature>(&self, state: [F; N]) -> [F; N] {
+        let std_dev = self.std_dev();
+        let mut normalized = state;
+        for (i, x) in normalized.iter_mut().enumerate() {
+            *x = F::from_f64((x.to_f64() - self.mean[i]) / std_dev[i]);
+        }
+        normalized
+    }
+}
+
+impl<const N: usize> Default for StateNormalizer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,141 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing read-only policies for following a learned policy without further learning.
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::compare_values;
+#[cfg(feature = "std")]
+use crate::mdp::State;
+
+/// A read-only table of learned values, to follow a learned policy where training is not possible,
+/// such as on an embedded target without an allocator. It does not need `std`, `alloc` or `rand`,
+/// and borrows its values from a slice of `(state, action, value)` entries, sorted by state. Such
+/// entries are exported by
+/// [AgentTrainer::export_policy_entries](../struct.AgentTrainer.html#method.export_policy_entries),
+/// and can for example be written to a static array that is compiled into the target.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::line::{MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::policy::PolicyTable;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(10000),
+///     &RandomExploration::new(),
+/// );
+///
+/// let entries = trainer.export_policy_entries();
+/// let policy = PolicyTable::new(&entries);
+/// for x in -5..=5 {
+///     let state = MyState(x);
+///     let best = policy.best_action(&state).unwrap();
+///     let (_, best_value) = trainer.best_action_with_value(&state).unwrap();
+///     assert_eq!(policy.expected_value(&state, &best), Some(best_value));
+///     assert_eq!(policy.expected_value(&state, &1), trainer.expected_value(&state, &1));
+/// }
+/// assert_eq!(policy.best_action(&MyState(100)), None);
+/// ```
+///
+/// Only `core` is needed to query a table, as on a `no_std` target:
+///
+/// ```
+/// use rurel::policy::PolicyTable;
+///
+/// // Entries as exported after training, with states and actions as plain numbers.
+/// static ENTRIES: [(i8, i8, f64); 4] = [(0, -1, -2.5), (0, 1, -0.5), (1, -1, -1.0), (1, 1, -3.0)];
+///
+/// let policy = PolicyTable::new(&ENTRIES);
+/// assert_eq!(policy.best_action(&0), Some(1));
+/// assert_eq!(policy.best_action(&1), Some(-1));
+/// assert_eq!(policy.best_action(&2), None);
+/// assert_eq!(policy.expected_value(&1, &1), Some(-3.0));
+/// ```
+pub struct PolicyTable<'a, S, A> {
+    entries: &'a [(S, A, f64)],
+}
+
+impl<'a, S, A> PolicyTable<'a, S, A>
+where
+    S: Ord,
+    A: Clone + PartialEq,
+{
+    /// Creates a `PolicyTable` from the given entries of a state, an action and its learned value.
+    /// The entries must be sorted by state.
+    pub fn new(entries: &'a [(S, A, f64)]) -> PolicyTable<'a, S, A> {
+        debug_assert!(
+            entries.windows(2).all(|w| w[0].0 <= w[1].0),
+            "the entries are not sorted by state"
+        );
+        PolicyTable { entries }
+    }
+
+    /// Returns the entries of the given `State`.
+    fn state_entries(&self, state: &S) -> &'a [(S, A, f64)] {
+        let start = self.entries.partition_point(|(s, _, _)| s < state);
+        let len = self.entries[start..].partition_point(|(s, _, _)| s == state);
+        &self.entries[start..start + len]
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
+    /// value was learned.
+    pub fn expected_value(&self, state: &S, action: &A) -> Option<f64> {
+        self.state_entries(state)
+            .iter()
+            .find(|(_, a, _)| a == action)
+            .map(|(_, _, value)| *value)
+    }
+
+    /// Returns the best action for the given `State`, or `None` if no values were learned. Actions
+    /// with a NaN value are only returned when all values of the `State` are NaN.
+    pub fn best_action(&self, state: &S) -> Option<A> {
+        self.state_entries(state)
+            .iter()
+            .max_by(|(_, _, v1), (_, _, v2)| compare_values(*v1, *v2))
+            .map(|(_, action, _)| action.clone())
+    }
+}
+
+/// A read-only, deterministic policy that takes the best action of every learned `State`, as
+/// exported by
+/// [AgentTrainer::export_policy](../struct.AgentTrainer.html#method.export_policy). It does not
+/// learn: a `State` that was not learned has no action.
+#[cfg(feature = "std")]
+pub struct GreedyPolicy<S: State> {
+    actions: HashMap<S, S::A>,
+}
+
+#[cfg(feature = "std")]
+impl<S: State> GreedyPolicy<S> {
+    /// Creates a `GreedyPolicy` from the given best action of every `State`.
+    pub fn import_policy(actions: HashMap<S, S::A>) -> GreedyPolicy<S> {
+        GreedyPolicy { actions }
+    }
+
+    /// Returns the action to take in the given `State`, or `None` if the `State` was not learned.
+    pub fn best_action(&self, state: &S) -> Option<&S::A> {
+        self.actions.get(state)
+    }
+
+    /// Returns the number of states that have an action.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns `true` if no `State` has an action.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
@@ -0,0 +1,404 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing experience replay buffers.
+
+use rand::Rng;
+
+use crate::mdp::State;
+
+/// A single transition observed during training: taking `action` in `state` resulted in arriving
+/// in `next_state`, receiving `reward`. `terminal` is `true` when `next_state` is terminal.
+pub struct Transition<S>
+where
+    S: State,
+{
+    pub state: S,
+    pub action: S::A,
+    pub reward: f64,
+    pub next_state: S,
+    pub terminal: bool,
+}
+
+/// A ring buffer holding the last `capacity` observed transitions, from which transitions can be
+/// sampled uniformly at random. Used by
+/// [AgentTrainer::train_with_replay()](../struct.AgentTrainer.html#method.train_with_replay).
+pub struct ReplayBuffer<S>
+where
+    S: State,
+{
+    capacity: usize,
+    transitions: Vec<Transition<S>>,
+    next: usize,
+}
+
+impl<S> ReplayBuffer<S>
+where
+    S: State,
+{
+    /// Constructs an empty replay buffer that holds at most `capacity` transitions.
+    pub fn new(capacity: usize) -> ReplayBuffer<S> {
+        ReplayBuffer {
+            capacity,
+            transitions: Vec::with_capacity(capacity),
+            next: 0,
+        }
+    }
+
+    /// The maximum number of transitions this buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of transitions currently in this buffer.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns `true` if this buffer holds no transitions.
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Adds a transition to this buffer, replacing the oldest transition if the buffer is full.
+    pub fn push(&mut self, transition: Transition<S>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+        } else {
+            self.transitions[self.next] = transition;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Returns a uniformly sampled transition from this buffer, or `None` if it is empty.
+    pub fn sample(&self) -> Option<&Transition<S>> {
+        if self.transitions.is_empty() {
+            None
+        } else {
+            let i = rand::thread_rng().gen_range(0..self.transitions.len());
+            Some(&self.transitions[i])
+        }
+    }
+}
+
+/// A memory of observed transitions that can be replayed by
+/// [AgentTrainer::train_with_replay()](../struct.AgentTrainer.html#method.train_with_replay),
+/// such as a [ReplayBuffer] or a [PrioritizedReplayBuffer].
+pub trait ReplayMemory<S: State> {
+    /// Stores an observed transition.
+    fn push(&mut self, transition: Transition<S>);
+    /// Samples a transition to replay, and returns its index together with the importance-sampling
+    /// weight to scale its update by, or `None` if no transitions are stored.
+    fn sample_index(&self) -> Option<(usize, f64)>;
+    /// Returns the stored transition at the given index, as returned by
+    /// [sample_index](ReplayMemory::sample_index).
+    fn transition(&self, index: usize) -> &Transition<S>;
+    /// Updates the priority of the transition at the given index after it was replayed, given the
+    /// size of its TD error. The default implementation does nothing, as for uniform sampling.
+    fn update_priority(&mut self, _index: usize, _td_error: f64) {}
+}
+
+impl<S: State> ReplayMemory<S> for ReplayBuffer<S> {
+    fn push(&mut self, transition: Transition<S>) {
+        ReplayBuffer::push(self, transition);
+    }
+
+    fn sample_index(&self) -> Option<(usize, f64)> {
+        if self.transitions.is_empty() {
+            None
+        } else {
+            Some((rand::thread_rng().gen_range(0..self.transitions.len()), 1.))
+        }
+    }
+
+    fn transition(&self, index: usize) -> &Transition<S> {
+        &self.transitions[index]
+    }
+}
+
+/// The priority added to the size of every TD error, so that no transition has a priority of 0
+/// and is never replayed again.
+const MIN_PRIORITY: f64 = 1e-6;
+
+/// A ring buffer holding the last `capacity` observed transitions, from which transitions are
+/// sampled with a probability proportional to their priority, using a sum tree. Transitions with a
+/// large TD error are replayed more often, which focuses learning on the transitions that are
+/// learned the least.
+///
+/// A transition with priority `p` is sampled with probability `p^alpha / sum(p^alpha)`, so
+/// `alpha` sets how strongly sampling is prioritized, from uniform sampling at 0 to fully
+/// prioritized sampling at 1. Since prioritized sampling changes how often transitions are
+/// learned from, the update of a replayed transition is scaled by its importance-sampling weight
+/// `(len * P(i))^-beta`, divided by the largest weight in the buffer so that updates are only
+/// ever scaled down. A `beta` of 1 fully corrects for the prioritization.
+///
+/// A new transition gets the largest priority seen so far, so that it is replayed at least once
+/// soon. After a transition is replayed, its priority is set to the size of its TD error, plus a
+/// small constant so that every transition can still be sampled. A TD error that is not finite,
+/// for example after a NaN reward, gets the largest priority seen so far instead, so that it does
+/// not break sampling.
+///
+/// # Examples
+///
+/// A transition with a large TD error is replayed more often, but with a smaller weight:
+///
+/// ```
+/// # use rurel::mdp::State;
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct Corridor(i32);
+/// # impl State for Corridor {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { 0. }
+/// #     fn actions(&self) -> Vec<()> { vec![()] }
+/// # }
+/// use rurel::replay::{PrioritizedReplayBuffer, ReplayMemory, Transition};
+///
+/// let mut buffer = PrioritizedReplayBuffer::new(100, 0.6, 0.4);
+/// for i in 0..10 {
+///     buffer.push(Transition {
+///         state: Corridor(i),
+///         action: (),
+///         reward: 0.,
+///         next_state: Corridor(i + 1),
+///         terminal: false,
+///     });
+///     buffer.update_priority(i as usize, if i == 3 { 10. } else { 0.1 });
+/// }
+///
+/// let mut counts = [0; 10];
+/// let mut weights = [0.; 10];
+/// for _ in 0..10000 {
+///     let (index, weight) = buffer.sample_index().unwrap();
+///     counts[index] += 1;
+///     weights[index] = weight;
+/// }
+/// // Transition 3 has a probability of about 0.64, the others of about 0.04 each.
+/// assert!(counts[3] > 6000);
+/// assert!(counts.iter().enumerate().all(|(i, &count)| i == 3 || count < 600));
+/// assert!(weights[3] < 0.5);
+/// assert_eq!(weights[0], 1.);
+/// ```
+///
+/// Replaying with priorities still converges to the learned values of uniform replay:
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A corridor of 10 states which is walked to the right, with a reward at the end.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Corridor(i32);
+/// # impl State for Corridor {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { if self.0 == 9 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<()> { vec![()] }
+/// # }
+/// # struct Walker { state: Corridor }
+/// # impl Agent<Corridor> for Walker {
+/// #     fn current_state(&self) -> &Corridor { &self.state }
+/// #     fn take_action(&mut self, _: &()) { self.state = Corridor((self.state.0 + 1) % 10) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::replay::PrioritizedReplayBuffer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = Walker { state: Corridor(0) };
+/// trainer.train_with_replay(
+///     &mut agent,
+///     &QLearning::new(0.5, 0.9, 0.),
+///     &mut FixedIterations::new(2000),
+///     &RandomExploration::new(),
+///     &mut PrioritizedReplayBuffer::new(100, 0.6, 0.4),
+///     10,
+/// );
+///
+/// // Walking into the reward at the end of every walk of 10 states is worth 1 / (1 - 0.9^10).
+/// let value_at_end = 1. / (1. - 0.9f64.powi(10));
+/// for i in 0..9 {
+///     let value = trainer.expected_value(&Corridor(i), &()).unwrap();
+///     assert!((value - 0.9f64.powi(8 - i) * value_at_end).abs() < 1e-3);
+/// }
+/// ```
+///
+/// A NaN reward does not break sampling:
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A corridor of 10 states which is walked to the right, with a NaN reward at the end.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Corridor(i32);
+/// # impl State for Corridor {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { if self.0 == 9 { f64::NAN } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<()> { vec![()] }
+/// # }
+/// # struct Walker { state: Corridor }
+/// # impl Agent<Corridor> for Walker {
+/// #     fn current_state(&self) -> &Corridor { &self.state }
+/// #     fn take_action(&mut self, _: &()) { self.state = Corridor((self.state.0 + 1) % 10) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::replay::{PrioritizedReplayBuffer, ReplayMemory};
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = Walker { state: Corridor(0) };
+/// let mut buffer = PrioritizedReplayBuffer::new(100, 0.6, 0.4);
+/// trainer.train_with_replay(
+///     &mut agent,
+///     &QLearning::new(0.5, 0.9, 0.),
+///     &mut FixedIterations::new(500),
+///     &RandomExploration::new(),
+///     &mut buffer,
+///     10,
+/// );
+///
+/// assert!(trainer.expected_value(&Corridor(8), &()).unwrap().is_nan());
+/// let (_, weight) = buffer.sample_index().unwrap();
+/// assert!(weight.is_finite());
+/// ```
+pub struct PrioritizedReplayBuffer<S>
+where
+    S: State,
+{
+    capacity: usize,
+    transitions: Vec<Transition<S>>,
+    next: usize,
+    alpha: f64,
+    beta: f64,
+    max_priority: f64,
+    // Sum and minimum trees of the prioritized priorities (`p^alpha`): node `i` has the children
+    // `2i` and `2i + 1`, and the priority of transition `j` is leaf `capacity + j`.
+    sums: Vec<f64>,
+    mins: Vec<f64>,
+}
+
+impl<S> PrioritizedReplayBuffer<S>
+where
+    S: State,
+{
+    /// Constructs an empty prioritized replay buffer that holds at most `capacity` transitions,
+    /// with the prioritization exponent `alpha` and the importance-sampling exponent `beta`.
+    pub fn new(capacity: usize, alpha: f64, beta: f64) -> PrioritizedReplayBuffer<S> {
+        PrioritizedReplayBuffer {
+            capacity,
+            transitions: Vec::with_capacity(capacity),
+            next: 0,
+            alpha,
+            beta,
+            max_priority: 1.,
+            sums: vec![0.; 2 * capacity],
+            mins: vec![f64::INFINITY; 2 * capacity],
+        }
+    }
+
+    /// The maximum number of transitions this buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of transitions currently in this buffer.
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns `true` if this buffer holds no transitions.
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// The exponent that sets how strongly sampling is prioritized.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// The exponent of the importance-sampling weights.
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// Sets the exponent of the importance-sampling weights, for example to anneal it towards 1
+    /// over the course of training.
+    pub fn set_beta(&mut self, beta: f64) {
+        self.beta = beta;
+    }
+
+    /// Returns the priority of the transition at the given index.
+    pub fn priority(&self, index: usize) -> f64 {
+        self.sums[self.capacity + index].powf(1. / self.alpha)
+    }
+
+    /// Sets the priority of the transition at the given index, and updates the trees.
+    fn set_priority(&mut self, index: usize, priority: f64) {
+        let mut node = self.capacity + index;
+        self.sums[node] = priority.powf(self.alpha);
+        self.mins[node] = self.sums[node];
+        while node > 1 {
+            node /= 2;
+            self.sums[node] = self.sums[2 * node] + self.sums[2 * node + 1];
+            self.mins[node] = self.mins[2 * node].min(self.mins[2 * node + 1]);
+        }
+    }
+}
+
+impl<S: State> ReplayMemory<S> for PrioritizedReplayBuffer<S> {
+    /// Adds a transition to this buffer with the largest priority seen so far, replacing the
+    /// oldest transition if the buffer is full.
+    fn push(&mut self, transition: Transition<S>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+        } else {
+            self.transitions[self.next] = transition;
+        }
+        self.set_priority(self.next, self.max_priority);
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    fn sample_index(&self) -> Option<(usize, f64)> {
+        if self.transitions.is_empty() {
+            return None;
+        }
+        let total = self.sums[1];
+        let mut u = rand::thread_rng().gen_range(0. ..total);
+        let mut node = 1;
+        while node < self.capacity {
+            if u < self.sums[2 * node] {
+                node *= 2;
+            } else {
+                u -= self.sums[2 * node];
+                node = 2 * node + 1;
+            }
+        }
+        // Rounding may end up past the stored transitions, whose priorities are 0.
+        let index = (node - self.capacity).min(self.transitions.len() - 1);
+        let len = self.transitions.len() as f64;
+        let weight = |p: f64| (len * p / total).powf(-self.beta);
+        let max_weight = weight(self.mins[1]);
+        Some((index, weight(self.sums[self.capacity + index]) / max_weight))
+    }
+
+    fn transition(&self, index: usize) -> &Transition<S> {
+        &self.transitions[index]
+    }
+
+    fn update_priority(&mut self, index: usize, td_error: f64) {
+        if !td_error.is_finite() {
+            let priority = self.max_priority;
+            self.set_priority(index, priority);
+            return;
+        }
+        let priority = td_error.abs() + MIN_PRIORITY;
+        self.max_priority = self.max_priority.max(priority);
+        self.set_priority(index, priority);
+    }
+}
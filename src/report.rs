@@ -0,0 +1,126 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing reporters of training metrics.
+
+use std::fmt::Debug;
+
+use crate::mdp::State;
+
+/// A reporter receives the metrics of a training run as it happens, for example to print them or
+/// to write them to a file for plotting. It is driven by
+/// [AgentTrainer::train_with_reporter()](../struct.AgentTrainer.html#method.train_with_reporter).
+///
+/// Reporters compose: a mutable reference to a reporter is a reporter, and so is a pair of
+/// reporters, which passes every metric to both.
+pub trait Reporter<S: State> {
+    /// Called after every value update, with the index of the step starting at 0, the `State` the
+    /// action was taken from, the action that was taken, the reward received for it, and the newly
+    /// learned value for taking `action` in `state`.
+    fn on_step(&mut self, step: u64, state: &S, action: &S::A, reward: f64, q_value: f64);
+    /// Called at the end of every episode, with the index of the episode starting at 0 and the sum
+    /// of all rewards received during it.
+    fn on_episode_end(&mut self, episode: u64, total_reward: f64);
+}
+
+impl<S: State, R: Reporter<S> + ?Sized> Reporter<S> for &mut R {
+    fn on_step(&mut self, step: u64, state: &S, action: &S::A, reward: f64, q_value: f64) {
+        (**self).on_step(step, state, action, reward, q_value);
+    }
+
+    fn on_episode_end(&mut self, episode: u64, total_reward: f64) {
+        (**self).on_episode_end(episode, total_reward);
+    }
+}
+
+impl<S: State, R1: Reporter<S>, R2: Reporter<S>> Reporter<S> for (R1, R2) {
+    fn on_step(&mut self, step: u64, state: &S, action: &S::A, reward: f64, q_value: f64) {
+        self.0.on_step(step, state, action, reward, q_value);
+        self.1.on_step(step, state, action, reward, q_value);
+    }
+
+    fn on_episode_end(&mut self, episode: u64, total_reward: f64) {
+        self.0.on_episode_end(episode, total_reward);
+        self.1.on_episode_end(episode, total_reward);
+    }
+}
+
+/// The reporter that ignores all metrics.
+pub struct NullReporter;
+
+impl<S: State> Reporter<S> for NullReporter {
+    fn on_step(&mut self, _step: u64, _state: &S, _action: &S::A, _reward: f64, _q_value: f64) {}
+
+    fn on_episode_end(&mut self, _episode: u64, _total_reward: f64) {}
+}
+
+/// The reporter that prints the total reward of every episode to stdout, and optionally every
+/// step at a fixed interval.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::line::{MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::report::StdoutReporter;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// // Prints every 100th step, such as "step 0: MyState(0) -> 1, reward -1, value ...", and the
+/// // total reward of every episode.
+/// trainer.train_with_reporter(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(1000),
+///     &RandomExploration::new(),
+///     &mut StdoutReporter::new().with_step_interval(100),
+/// );
+/// ```
+pub struct StdoutReporter {
+    step_interval: Option<u64>,
+}
+
+impl StdoutReporter {
+    /// Constructs a reporter that prints the total reward of every episode.
+    pub fn new() -> StdoutReporter {
+        StdoutReporter {
+            step_interval: None,
+        }
+    }
+
+    /// Additionally prints every `interval`th step, starting at the first.
+    pub fn with_step_interval(mut self, interval: u64) -> Self {
+        assert!(interval > 0, "step interval must be positive");
+        self.step_interval = Some(interval);
+        self
+    }
+}
+
+impl Default for StdoutReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Reporter<S> for StdoutReporter
+where
+    S: State + Debug,
+    S::A: Debug,
+{
+    fn on_step(&mut self, step: u64, state: &S, action: &S::A, reward: f64, q_value: f64) {
+        if self
+            .step_interval
+            .is_some_and(|interval| step.is_multiple_of(interval))
+        {
+            println!("step {step}: {state:?} -> {action:?}, reward {reward}, value {q_value}");
+        }
+    }
+
+    fn on_episode_end(&mut self, episode: u64, total_reward: f64) {
+        println!("episode {episode}: total reward {total_reward}");
+    }
+}
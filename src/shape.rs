@@ -0,0 +1,125 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing reward shaping.
+
+use crate::mdp::State;
+
+/// A reward shaper changes the rewards that are learned from during training, for example to
+/// guide learning towards states that are known to be good. It is enabled using
+/// [AgentTrainer::with_reward_shaping()](../struct.AgentTrainer.html#method.with_reward_shaping).
+pub trait RewardShaper<S: State> {
+    /// Returns the reward to learn from for arriving in `next_state` from `state`, given the
+    /// reward `base_reward` of the transition.
+    fn shape(&self, state: &S, next_state: &S, base_reward: f64) -> f64;
+}
+
+/// The potential-based reward shaper, which adds `gamma * phi(next_state) - phi(state)` to the
+/// reward for a potential function `phi`. A potential that is higher for states that are closer
+/// to the goal, such as the negative distance to the goal, can speed up learning considerably.
+/// Potential-based shaping does not change the optimal policy, as long as `gamma` is the discount
+/// factor that is learned with. The potential of a sink state (a `State` without actions) is taken
+/// to be 0.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState { x: i32, y: i32 }
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyAction { dx: i32, dy: i32 }
+/// # impl State for MyState {
+/// #     type A = MyAction;
+/// #     fn reward(&self) -> f64 { -distance(self) }
+/// #     fn actions(&self) -> Vec<MyAction> {
+/// #         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+/// #              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 }]
+/// #     }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &MyAction) {
+/// #         self.state = MyState {
+/// #             x: (((self.state.x + action.dx) % 21) + 21) % 21,
+/// #             y: (((self.state.y + action.dy) % 21) + 21) % 21,
+/// #         }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::shape::PotentialShaper;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// // The distance to the target at (10, 10).
+/// fn distance(state: &MyState) -> f64 {
+///     (((10 - state.x).pow(2) + (10 - state.y).pow(2)) as f64).sqrt()
+/// }
+///
+/// // The number of states in which the best action moves closer to the target.
+/// let moves_closer = |trainer: &AgentTrainer<MyState>| {
+///     (0..21 * 21)
+///         .map(|i| MyState { x: i % 21, y: i / 21 })
+///         .filter(|state| {
+///             let mut agent = MyAgent { state: state.clone() };
+///             trainer.best_action(state).map(|action| agent.take_action(&action));
+///             distance(&agent.state) < distance(state)
+///         })
+///         .count()
+/// };
+///
+/// let learning = QLearning::new(0.2, 0.5, 0.);
+/// let mut trainer = AgentTrainer::new();
+/// let mut shaped_trainer =
+///     AgentTrainer::new().with_reward_shaping(PotentialShaper::new(0.5, |s: &MyState| -distance(s)));
+/// let mut train = |trainer: &mut AgentTrainer<MyState>, iterations| {
+///     let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+///     trainer.train(
+///         &mut agent,
+///         &learning,
+///         &mut FixedIterations::new(iterations),
+///         &RandomExploration::new(),
+///     );
+/// };
+///
+/// // With shaping, the best action moves closer to the target in more states early on.
+/// train(&mut trainer, 20000);
+/// train(&mut shaped_trainer, 20000);
+/// assert!(moves_closer(&shaped_trainer) > moves_closer(&trainer));
+///
+/// // After training longer, both move closer in every state except the target.
+/// train(&mut trainer, 80000);
+/// train(&mut shaped_trainer, 80000);
+/// assert_eq!(moves_closer(&trainer), 21 * 21 - 1);
+/// assert_eq!(moves_closer(&shaped_trainer), 21 * 21 - 1);
+/// ```
+pub struct PotentialShaper<F> {
+    gamma: f64,
+    potential: F,
+}
+
+impl<F> PotentialShaper<F> {
+    /// Constructs a potential-based reward shaper with discount factor `gamma` and the potential
+    /// function `potential`.
+    pub fn new(gamma: f64, potential: F) -> PotentialShaper<F> {
+        PotentialShaper { gamma, potential }
+    }
+}
+
+impl<S, F> RewardShaper<S> for PotentialShaper<F>
+where
+    S: State,
+    F: Fn(&S) -> f64,
+{
+    fn shape(&self, state: &S, next_state: &S, base_reward: f64) -> f64 {
+        let next_potential = if next_state.actions().is_empty() {
+            0.
+        } else {
+            (self.potential)(next_state)
+        };
+        base_reward + self.gamma * next_potential - (self.potential)(state)
+    }
+}
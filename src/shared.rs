@@ -0,0 +1,175 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing a trainer that can be queried while it is being trained.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::ExplorationStrategy;
+use crate::strategy::learn::LearningStrategy;
+use crate::strategy::terminate::TerminationStrategy;
+use crate::table::QTable;
+use crate::{AgentTrainer, TrainingStats};
+
+/// A `SharedTrainer` shares an [AgentTrainer] between threads, so that it can be queried from one
+/// thread while it is trained in another. Cloning a `SharedTrainer` gives another handle to the
+/// same trainer.
+///
+/// Training takes a write lock on the trainer for every batch of value updates, and queries take
+/// a read lock. A query therefore never observes a partially trained batch: it sees the learned
+/// values after some number of whole batches. Two subsequent queries may see the values after a
+/// different number of batches, so use [read](SharedTrainer::read) to make multiple queries
+/// against the same values. A larger batch reduces the locking overhead of training, but makes
+/// queries wait longer.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::line::{MyAgent, MyState};
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::thread;
+///
+/// use rurel::shared::SharedTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let trainer = SharedTrainer::new();
+/// let done = AtomicBool::new(false);
+/// thread::scope(|s| {
+///     let writer = s.spawn(|| {
+///         let mut agent = MyAgent { state: MyState(0) };
+///         trainer.train(
+///             &mut agent,
+///             &QLearning::new(0.2, 0.01, 2.),
+///             &mut FixedIterations::new(100000),
+///             &RandomExploration::new(),
+///             1000,
+///         );
+///         done.store(true, Ordering::SeqCst);
+///     });
+///     let reader = s.spawn(|| {
+///         let mut learned = 0;
+///         while !done.load(Ordering::SeqCst) {
+///             let _ = trainer.best_action(&MyState(3));
+///             // Learned values are never lost while training.
+///             let num_state_actions = trainer.read().num_state_actions();
+///             assert!(num_state_actions >= learned);
+///             learned = num_state_actions;
+///         }
+///     });
+///     writer.join().unwrap();
+///     reader.join().unwrap();
+/// });
+///
+/// assert_eq!(trainer.read().last_stats().unwrap().steps, 100000);
+/// assert_eq!(trainer.best_action(&MyState(3)), Some(-1));
+/// assert_eq!(trainer.best_action(&MyState(-3)), Some(1));
+/// ```
+pub struct SharedTrainer<S, T = HashMap<S, HashMap<<S as State>::A, f64>>>
+where
+    S: State,
+{
+    trainer: Arc<RwLock<AgentTrainer<S, T>>>,
+}
+
+impl<S> SharedTrainer<S>
+where
+    S: State,
+{
+    /// Creates a `SharedTrainer` around a new [AgentTrainer].
+    pub fn new() -> SharedTrainer<S> {
+        SharedTrainer::from(AgentTrainer::new())
+    }
+}
+
+impl<S> Default for SharedTrainer<S>
+where
+    S: State,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, T> From<AgentTrainer<S, T>> for SharedTrainer<S, T>
+where
+    S: State,
+{
+    fn from(trainer: AgentTrainer<S, T>) -> Self {
+        SharedTrainer {
+            trainer: Arc::new(RwLock::new(trainer)),
+        }
+    }
+}
+
+impl<S, T> Clone for SharedTrainer<S, T>
+where
+    S: State,
+{
+    fn clone(&self) -> Self {
+        SharedTrainer {
+            trainer: Arc::clone(&self.trainer),
+        }
+    }
+}
+
+impl<S, T> SharedTrainer<S, T>
+where
+    S: State,
+    T: QTable<S>,
+{
+    /// Takes a read lock on the trainer, to query it while no batch is being trained.
+    pub fn read(&self) -> RwLockReadGuard<'_, AgentTrainer<S, T>> {
+        self.trainer.read().unwrap()
+    }
+
+    /// Returns the best action for the given `State`, or `None` if no values were learned, see
+    /// [AgentTrainer::best_action].
+    pub fn best_action(&self, state: &S) -> Option<S::A> {
+        self.read().best_action(state)
+    }
+
+    /// Fetches the learned value for the given `Action` in the given `State`, or `None` if no
+    /// value was learned, see [AgentTrainer::expected_value].
+    pub fn expected_value(&self, state: &S, action: &S::A) -> Option<f64> {
+        self.read().expected_value(state, action)
+    }
+
+    /// Trains the trainer like [AgentTrainer::train], taking a write lock for every `batch_size`
+    /// value updates. The lock is released between batches, to let queries in.
+    pub fn train(
+        &self,
+        agent: &mut dyn Agent<S>,
+        learning_strategy: &dyn LearningStrategy<S>,
+        termination_strategy: &mut dyn TerminationStrategy<S>,
+        exploration_strategy: &dyn ExplorationStrategy<S>,
+        batch_size: u64,
+    ) {
+        let mut stats = TrainingStats::new();
+        loop {
+            let mut trainer = self.trainer.write().unwrap();
+            if trainer.train_steps(
+                agent,
+                learning_strategy,
+                termination_strategy,
+                &mut |_, agent, step, episode| {
+                    exploration_strategy.pick_action_at(agent, step, episode)
+                },
+                &mut |_, step| {
+                    if step.terminal {
+                        exploration_strategy.on_episode_end();
+                    }
+                },
+                &mut stats,
+                batch_size,
+            ) {
+                trainer.stats = Some(stats);
+                return;
+            }
+        }
+    }
+}
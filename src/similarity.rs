@@ -0,0 +1,18 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing state similarity, to generalize learned values to unseen states.
+
+use crate::mdp::State;
+
+/// A state similarity finds the states that are similar to a given `State`, so that a `State`
+/// without learned values can borrow the values of its neighbors. This is useful when a
+/// continuous problem is discretized into many states, most of which are rarely visited. It is
+/// enabled using
+/// [AgentTrainer::with_state_similarity()](../struct.AgentTrainer.html#method.with_state_similarity).
+pub trait StateSimilarity<S: State> {
+    /// Returns the neighbors of the given `State`, each with a positive weight that is higher for
+    /// more similar states.
+    fn neighbors(&self, state: &S) -> Vec<(S, f64)>;
+}
@@ -0,0 +1,294 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for saving a trainer together with its training configuration.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::mdp::{Agent, State};
+use crate::normalize::RewardNormalizer;
+use crate::strategy::explore::{ExplorationStrategy, RandomExploration};
+use crate::strategy::learn::q::NextValueAggregator;
+use crate::strategy::learn::{LearningStrategy, Mellowmax, QLearning};
+use crate::strategy::terminate::{
+    FixedIterations, RewardThreshold, SinkStates, TerminationStrategy,
+};
+use crate::AgentTrainer;
+
+/// The configuration of one of the built-in learning strategies, which can be saved in a
+/// [TrainerSnapshot].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LearningConfig {
+    /// [QLearning], with the given aggregator of the values of the new state.
+    QLearning {
+        alpha: f64,
+        gamma: f64,
+        initial_value: f64,
+        aggregator: NextValueAggregator,
+    },
+    /// [Mellowmax].
+    Mellowmax {
+        alpha: f64,
+        gamma: f64,
+        initial_value: f64,
+        omega: f64,
+    },
+}
+
+impl LearningConfig {
+    /// Constructs the configured learning strategy.
+    pub fn build<S: State>(&self) -> Box<dyn LearningStrategy<S>> {
+        match *self {
+            LearningConfig::QLearning {
+                alpha,
+                gamma,
+                initial_value,
+                aggregator,
+            } => Box::new(QLearning::new(alpha, gamma, initial_value).with_aggregator(aggregator)),
+            LearningConfig::Mellowmax {
+                alpha,
+                gamma,
+                initial_value,
+                omega,
+            } => Box::new(Mellowmax::new(alpha, gamma, initial_value, omega)),
+        }
+    }
+}
+
+/// An error that occurs when a learning strategy cannot be described by a [LearningConfig].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LearningConfigError {
+    /// The learning rate follows a schedule set with
+    /// [with_alpha_schedule](QLearning::with_alpha_schedule), which cannot be saved.
+    AlphaSchedule,
+}
+
+impl std::fmt::Display for LearningConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LearningConfigError::AlphaSchedule => {
+                write!(
+                    f,
+                    "the learning rate follows a schedule, which cannot be saved"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LearningConfigError {}
+
+impl TryFrom<&QLearning> for LearningConfig {
+    type Error = LearningConfigError;
+
+    /// The configuration of `learning`, or an error if its learning rate follows a schedule set
+    /// with [with_alpha_schedule](QLearning::with_alpha_schedule).
+    fn try_from(learning: &QLearning) -> Result<Self, LearningConfigError> {
+        if learning.has_alpha_schedule() {
+            return Err(LearningConfigError::AlphaSchedule);
+        }
+        Ok(LearningConfig::QLearning {
+            alpha: learning.alpha(),
+            gamma: learning.gamma(),
+            initial_value: learning.initial_value(),
+            aggregator: learning.aggregator(),
+        })
+    }
+}
+
+/// The configuration of one of the built-in exploration strategies, which can be saved in a
+/// [TrainerSnapshot].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExplorationConfig {
+    /// [RandomExploration], seeded with [RandomExploration::with_seed] when a seed is given.
+    Random { seed: Option<u64> },
+}
+
+impl ExplorationConfig {
+    /// Constructs the configured exploration strategy.
+    pub fn build<S: State>(&self) -> Box<dyn ExplorationStrategy<S>> {
+        match *self {
+            ExplorationConfig::Random { seed: None } => Box::new(RandomExploration::new()),
+            ExplorationConfig::Random { seed: Some(seed) } => {
+                Box::new(RandomExploration::with_seed(seed))
+            }
+        }
+    }
+}
+
+/// The configuration of one of the built-in termination strategies, which can be saved in a
+/// [TrainerSnapshot].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TerminationConfig {
+    /// [FixedIterations].
+    FixedIterations { iters: u32 },
+    /// [SinkStates].
+    SinkStates,
+    /// [RewardThreshold].
+    RewardThreshold { threshold: f64 },
+}
+
+impl TerminationConfig {
+    /// Constructs the configured termination strategy, in its initial state.
+    pub fn build<S: State>(&self) -> Box<dyn TerminationStrategy<S>> {
+        match *self {
+            TerminationConfig::FixedIterations { iters } => Box::new(FixedIterations::new(iters)),
+            TerminationConfig::SinkStates => Box::new(SinkStates {}),
+            TerminationConfig::RewardThreshold { threshold } => {
+                Box::new(RewardThreshold::new(threshold))
+            }
+        }
+    }
+}
+
+/// A `TrainerSnapshot` bundles the learned values of an [AgentTrainer] with the configuration it
+/// is trained with, so that training can be resumed from a single file. Since strategies are trait
+/// objects, only the built-in strategies described by [LearningConfig], [ExplorationConfig] and
+/// [TerminationConfig] can be saved. Reward shapers and state similarities are not saved either.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::line::{MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::snapshot::{
+///     ExplorationConfig, LearningConfig, LearningConfigError, TerminationConfig, TrainerSnapshot,
+/// };
+/// use rurel::strategy::learn::QLearning;
+///
+/// let learning = LearningConfig::try_from(&QLearning::new(0.2, 0.01, 2.)).unwrap();
+/// // A schedule of learning rates cannot be saved.
+/// let scheduled = QLearning::with_alpha_schedule(|n| 1. / (n as f64 + 1.), 0.01, 2.);
+/// assert_eq!(
+///     LearningConfig::try_from(&scheduled),
+///     Err(LearningConfigError::AlphaSchedule)
+/// );
+/// let exploration = ExplorationConfig::Random { seed: Some(7) };
+/// let termination = TerminationConfig::FixedIterations { iters: 1000 };
+///
+/// let mut trainer = AgentTrainer::new().with_reward_normalization();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// TrainerSnapshot::train_configured(
+///     &mut trainer,
+///     &mut agent,
+///     &learning,
+///     &exploration,
+///     &termination,
+/// );
+///
+/// let path = std::env::temp_dir().join("rurel_snapshot_doctest.bin");
+/// let snapshot = TrainerSnapshot::new(&trainer, learning, exploration, termination);
+/// snapshot.save_snapshot(&path).unwrap();
+///
+/// let loaded = TrainerSnapshot::<MyState>::load_snapshot(&path).unwrap();
+/// assert_eq!(loaded, snapshot);
+/// assert_eq!(loaded.learning, learning);
+/// let mut resumed = loaded.trainer();
+/// assert!(resumed == trainer);
+///
+/// // Training continues with the saved configuration.
+/// loaded.train(&mut resumed, &mut agent);
+/// assert!(resumed != trainer);
+/// assert_eq!(resumed.best_action(&MyState(-3)), Some(1));
+/// assert_eq!(resumed.best_action(&MyState(3)), Some(-1));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "S: Serialize, S::A: Serialize",
+    deserialize = "S: DeserializeOwned, S::A: DeserializeOwned"
+))]
+pub struct TrainerSnapshot<S: State> {
+    /// The learned values, as returned by [AgentTrainer::export_learned_values].
+    pub values: HashMap<S, HashMap<S::A, f64>>,
+    /// The reward normalization statistics, if reward normalization is enabled.
+    pub normalizer: Option<RewardNormalizer>,
+    /// The bounds of reward clipping, if enabled.
+    pub reward_clip: Option<(f64, f64)>,
+    /// The learning strategy to train with.
+    pub learning: LearningConfig,
+    /// The exploration strategy to train with.
+    pub exploration: ExplorationConfig,
+    /// The termination strategy to train with.
+    pub termination: TerminationConfig,
+}
+
+impl<S: State> TrainerSnapshot<S> {
+    /// Takes a snapshot of `trainer`, to be trained further with the given configuration.
+    pub fn new(
+        trainer: &AgentTrainer<S>,
+        learning: LearningConfig,
+        exploration: ExplorationConfig,
+        termination: TerminationConfig,
+    ) -> TrainerSnapshot<S> {
+        TrainerSnapshot {
+            values: trainer.export_learned_values(),
+            normalizer: trainer.normalizer.clone(),
+            reward_clip: trainer.reward_clip,
+            learning,
+            exploration,
+            termination,
+        }
+    }
+
+    /// Constructs an [AgentTrainer] with the learned values, reward normalization and reward
+    /// clipping of this snapshot.
+    pub fn trainer(&self) -> AgentTrainer<S> {
+        let mut trainer = AgentTrainer::new();
+        trainer.import_state(self.values.clone());
+        trainer.normalizer = self.normalizer.clone();
+        trainer.reward_clip = self.reward_clip;
+        trainer
+    }
+
+    /// Trains `trainer` on `agent` with the configuration of this snapshot, see
+    /// [AgentTrainer::train]. The termination strategy starts afresh.
+    pub fn train(&self, trainer: &mut AgentTrainer<S>, agent: &mut dyn Agent<S>) {
+        Self::train_configured(
+            trainer,
+            agent,
+            &self.learning,
+            &self.exploration,
+            &self.termination,
+        );
+    }
+
+    /// Trains `trainer` on `agent` with the given configuration, see [AgentTrainer::train].
+    pub fn train_configured(
+        trainer: &mut AgentTrainer<S>,
+        agent: &mut dyn Agent<S>,
+        learning: &LearningConfig,
+        exploration: &ExplorationConfig,
+        termination: &TerminationConfig,
+    ) {
+        trainer.train(
+            agent,
+            learning.build().as_ref(),
+            termination.build().as_mut(),
+            exploration.build().as_ref(),
+        );
+    }
+}
+
+impl<S> TrainerSnapshot<S>
+where
+    S: State + Serialize + DeserializeOwned,
+    S::A: Serialize + DeserializeOwned,
+{
+    /// Saves the snapshot to the file at `path` in the bincode format.
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> bincode::Result<()> {
+        let file = std::fs::File::create(path)?;
+        bincode::serialize_into(std::io::BufWriter::new(file), self)
+    }
+
+    /// Loads a snapshot saved with [save_snapshot](TrainerSnapshot::save_snapshot) from the file
+    /// at `path`.
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> bincode::Result<TrainerSnapshot<S>> {
+        let file = std::fs::File::open(path)?;
+        bincode::deserialize_from(std::io::BufReader::new(file))
+    }
+}
@@ -0,0 +1,35 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the max episode steps boundary.
+
+use crate::mdp::State;
+use crate::strategy::episode::EpisodeBoundary;
+
+/// The episode boundary that ends an episode after a certain number of steps, or earlier when it
+/// arrives at a sink state (a `State` without actions).
+#[derive(Clone)]
+pub struct MaxEpisodeSteps {
+    i: u32,
+    steps: u32,
+}
+
+impl MaxEpisodeSteps {
+    /// Constructs a new episode boundary which ends an episode when `steps` value updates have
+    /// occurred since its start.
+    pub fn new(steps: u32) -> MaxEpisodeSteps {
+        MaxEpisodeSteps { i: 0, steps }
+    }
+}
+
+impl<S: State> EpisodeBoundary<S> for MaxEpisodeSteps {
+    fn should_end_episode(&mut self, state: &S) -> bool {
+        self.i += 1;
+        if self.i >= self.steps || state.actions().is_empty() {
+            self.i = 0;
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,18 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing episode boundaries.
+
+pub use self::max_episode_steps::MaxEpisodeSteps;
+use crate::mdp::State;
+
+pub mod max_episode_steps;
+
+/// An episode boundary decides when an episode ends, after which the agent is reset to start a
+/// new episode. Unlike a [TerminationStrategy](../terminate/trait.TerminationStrategy.html), it
+/// does not end training.
+pub trait EpisodeBoundary<S: State> {
+    /// If `should_end_episode` returns `true`, the current episode ends and the agent is reset.
+    fn should_end_episode(&mut self, state: &S) -> bool;
+}
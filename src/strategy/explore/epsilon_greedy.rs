@@ -0,0 +1,102 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::{take_only_action, ParameterizedExplorationStrategy};
+
+/// The epsilon-greedy exploration strategy. This strategy takes a random action with probability
+/// epsilon, which is given as the parameter, and the best learned action otherwise. When no values
+/// were learned for the current `State`, it takes a random action. In a `State` with a single
+/// action, that action is always taken.
+///
+/// # Panics
+///
+/// Panics when asked to pick an action in a `State` without actions.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// // At the edges, the agent is forced to move back.
+/// impl State for MyState {
+///     type A = i32;
+///     fn reward(&self) -> f64 { -((5 - self.0).abs() as f64) }
+///     fn actions(&self) -> Vec<i32> {
+///         match self.0 {
+///             -5 => vec![1],
+///             5 => vec![-1],
+///             _ => vec![-1, 1],
+///         }
+///     }
+/// }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &i32) { self.state = MyState(self.state.0 + action) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::{EpsilonGreedyExploration, ParameterizedExplorationStrategy};
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// trainer.train_with_schedule(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(10000),
+///     &EpsilonGreedyExploration::new(),
+///     &|_| 0.5,
+/// );
+///
+/// // The value of the forced move is learned like any other value.
+/// assert!((trainer.expected_value(&MyState(5), &-1).unwrap() + 1.).abs() < 0.1);
+/// assert_eq!(trainer.expected_value(&MyState(5), &1), None);
+/// assert_eq!(trainer.best_action(&MyState(5)), Some(-1));
+///
+/// // The forced move is taken regardless of epsilon or the given best action.
+/// let exploration = EpsilonGreedyExploration::new();
+/// for (epsilon, best_action) in [(0., Some(1)), (1., None), (0.5, Some(-1))] {
+///     let mut agent = MyAgent { state: MyState(5) };
+///     assert_eq!(exploration.pick_action(&mut agent, epsilon, best_action), -1);
+///     assert!(agent.state == MyState(4));
+/// }
+/// ```
+pub struct EpsilonGreedyExploration;
+
+impl EpsilonGreedyExploration {
+    pub fn new() -> EpsilonGreedyExploration {
+        EpsilonGreedyExploration
+    }
+}
+
+impl Default for EpsilonGreedyExploration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: State> ParameterizedExplorationStrategy<S> for EpsilonGreedyExploration {
+    fn pick_action(
+        &self,
+        agent: &mut dyn Agent<S>,
+        epsilon: f64,
+        best_action: Option<S::A>,
+    ) -> S::A {
+        if let Some(action) = take_only_action(agent) {
+            return action;
+        }
+        match best_action {
+            Some(action) if rand::random::<f64>() >= epsilon => {
+                agent.take_action(&action);
+                action
+            }
+            _ => agent
+                .pick_random_action()
+                .expect("no actions can be taken from the current State"),
+        }
+    }
+}
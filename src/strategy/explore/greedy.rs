@@ -0,0 +1,112 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use rand::RngCore;
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::{take_only_action, ExplorationStrategy};
+use crate::table::QTable;
+use crate::AgentTrainer;
+
+/// The greedy exploration strategy. This strategy always takes the best action learned by the
+/// given [AgentTrainer](../../struct.AgentTrainer.html). When no values were learned for the
+/// current `State`, it falls back to a random action as defined for the Agent by
+/// [Agent::pick_random_action()](../../mdp/trait.Agent.html#method.pick_random_action).
+///
+/// # Panics
+///
+/// Panics when asked to pick an action in a `State` without actions.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::{ExplorationStrategy, GreedyExploration, RandomExploration};
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(100000),
+///     &RandomExploration::new(),
+/// );
+///
+/// let greedy = GreedyExploration::new(&trainer);
+/// for (x, y) in [(5, 5), (15, 15), (5, 15), (15, 5), (10, 3)] {
+///     let mut agent = MyAgent { state: MyState { x, y } };
+///     let before = agent.current_state().reward();
+///     greedy.pick_action(&mut agent);
+///     assert!(agent.current_state().reward() > before);
+/// }
+/// ```
+pub struct GreedyExploration<'a, S, T>
+where
+    S: State,
+{
+    trainer: &'a AgentTrainer<S, T>,
+}
+
+impl<'a, S, T> GreedyExploration<'a, S, T>
+where
+    S: State,
+    T: QTable<S>,
+{
+    pub fn new(trainer: &'a AgentTrainer<S, T>) -> GreedyExploration<'a, S, T> {
+        GreedyExploration { trainer }
+    }
+}
+
+impl<S, T> ExplorationStrategy<S> for GreedyExploration<'_, S, T>
+where
+    S: State,
+    T: QTable<S>,
+{
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        if let Some(action) = take_only_action(agent) {
+            return action;
+        }
+        match self.trainer.best_action(agent.current_state()) {
+            Some(action) => {
+                agent.take_action(&action);
+                action
+            }
+            None => agent
+                .pick_random_action()
+                .expect("no actions can be taken from the current State"),
+        }
+    }
+
+    /// Takes the best action like [pick_action](ExplorationStrategy::pick_action), but breaks
+    /// ties between equally valued actions, and picks the random fallback action, with `rng`.
+    /// Unlike the order in which the learned values are stored, this does not change between
+    /// runs.
+    fn pick_action_with(
+        &self,
+        agent: &mut dyn Agent<S>,
+        _step: u64,
+        _episode: u64,
+        rng: &mut dyn RngCore,
+    ) -> S::A {
+        if let Some(action) = take_only_action(agent) {
+            return action;
+        }
+        match self
+            .trainer
+            .best_action_random_tiebreak(agent.current_state(), 0., rng)
+        {
+            Some(action) => {
+                agent.take_action(&action);
+                action
+            }
+            None => agent
+                .pick_random_action_with(rng)
+                .expect("no actions can be taken from the current State"),
+        }
+    }
+}
@@ -4,9 +4,17 @@
 
 //! Module containing exploration strategies.
 
+pub use self::epsilon_greedy::EpsilonGreedyExploration;
+pub use self::greedy::GreedyExploration;
+pub use self::per_episode_decay::PerEpisodeDecayEpsilon;
 pub use self::random::RandomExploration;
+use rand::RngCore;
+
 use crate::mdp::{Agent, State};
 
+pub mod epsilon_greedy;
+pub mod greedy;
+pub mod per_episode_decay;
 pub mod random;
 
 /// Trait for exploration strategies. An exploration strategy decides, based on an `Agent`, which
@@ -14,4 +22,180 @@ pub mod random;
 pub trait ExplorationStrategy<S: State> {
     /// Selects the next action to take for this `Agent`.
     fn pick_action(&self, _: &mut dyn Agent<S>) -> S::A;
+
+    /// Selects the next action to take for this `Agent`, given the index of the step and the
+    /// number of episodes that ended so far in the current training run. This lets the choice
+    /// depend on time without keeping a counter in the strategy. The
+    /// [AgentTrainer](../../struct.AgentTrainer.html) picks actions through this method. By
+    /// default, the indices are ignored and [pick_action](ExplorationStrategy::pick_action) is
+    /// called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # use rurel::fixtures::line::{MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::{ExplorationStrategy, RandomExploration};
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Explores randomly for the first 500 steps, and then always moves to the right.
+    /// struct ExploreThenRight;
+    ///
+    /// impl ExplorationStrategy<MyState> for ExploreThenRight {
+    ///     fn pick_action(&self, agent: &mut dyn Agent<MyState>) -> i32 {
+    ///         RandomExploration::new().pick_action(agent)
+    ///     }
+    ///
+    ///     fn pick_action_at(&self, agent: &mut dyn Agent<MyState>, step: u64, _: u64) -> i32 {
+    ///         if step < 500 {
+    ///             return self.pick_action(agent);
+    ///         }
+    ///         agent.take_action(&1);
+    ///         1
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = MyAgent { state: MyState(0) };
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.01, 2.),
+    ///     &mut FixedIterations::new(1000),
+    ///     &ExploreThenRight,
+    /// );
+    /// assert!(agent.state == MyState(5));
+    /// ```
+    fn pick_action_at(&self, agent: &mut dyn Agent<S>, _step: u64, _episode: u64) -> S::A {
+        self.pick_action(agent)
+    }
+
+    /// Selects the next action to take for this `Agent` like
+    /// [pick_action_at](ExplorationStrategy::pick_action_at), drawing any random numbers from
+    /// `rng`. The [AgentTrainer](../../struct.AgentTrainer.html) picks actions through this method
+    /// when training with
+    /// [train_seeded](../../struct.AgentTrainer.html#method.train_seeded), so strategies that
+    /// take random decisions should implement it to make seeded training reproducible. By
+    /// default, `rng` is ignored and [pick_action_at](ExplorationStrategy::pick_action_at) is
+    /// called.
+    fn pick_action_with(
+        &self,
+        agent: &mut dyn Agent<S>,
+        step: u64,
+        episode: u64,
+        _rng: &mut dyn RngCore,
+    ) -> S::A {
+        self.pick_action_at(agent, step, episode)
+    }
+
+    /// Selects the next action to take for this `Agent` like
+    /// [pick_action_at](ExplorationStrategy::pick_action_at), with mutable access to the strategy.
+    /// This lets a strategy keep state, such as the number of steps it took, in plain fields that
+    /// carry over from one training run to the next. The
+    /// [AgentTrainer](../../struct.AgentTrainer.html) picks actions through this method when
+    /// training with [train_mut](../../struct.AgentTrainer.html#method.train_mut). By default,
+    /// [pick_action_at](ExplorationStrategy::pick_action_at) is called.
+    fn pick_action_mut(&mut self, agent: &mut dyn Agent<S>, step: u64, episode: u64) -> S::A {
+        self.pick_action_at(agent, step, episode)
+    }
+
+    /// Called by the [AgentTrainer](../../struct.AgentTrainer.html) when an episode ends, after
+    /// the step that reached a terminal state. This lets a strategy change once per episode
+    /// instead of once per step, see [PerEpisodeDecayEpsilon]. The episode index passed to
+    /// [pick_action_at](ExplorationStrategy::pick_action_at) advances at the same time. Does
+    /// nothing by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// // A corridor of 10 states which is walked to the right, ending an episode at the start.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct Corridor(i32);
+    /// # impl State for Corridor {
+    /// #     type A = ();
+    /// #     fn reward(&self) -> f64 { 0. }
+    /// #     fn actions(&self) -> Vec<()> { vec![()] }
+    /// # }
+    /// # struct Walker { state: Corridor }
+    /// # impl Agent<Corridor> for Walker {
+    /// #     fn current_state(&self) -> &Corridor { &self.state }
+    /// #     fn take_action(&mut self, _: &()) { self.state = Corridor((self.state.0 + 1) % 10) }
+    /// #     fn is_terminal(&self) -> bool { self.state.0 == 0 }
+    /// # }
+    /// use std::cell::{Cell, RefCell};
+    ///
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::ExplorationStrategy;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // Records the episode index of every step, and counts the ends of episodes.
+    /// #[derive(Default)]
+    /// struct Recording { episodes: RefCell<Vec<u64>>, ends: Cell<u64> }
+    ///
+    /// impl ExplorationStrategy<Corridor> for Recording {
+    ///     fn pick_action(&self, agent: &mut dyn Agent<Corridor>) {
+    ///         agent.take_action(&())
+    ///     }
+    ///     fn pick_action_at(&self, agent: &mut dyn Agent<Corridor>, _: u64, episode: u64) {
+    ///         self.episodes.borrow_mut().push(episode);
+    ///         self.pick_action(agent)
+    ///     }
+    ///     fn on_episode_end(&self) {
+    ///         self.ends.set(self.ends.get() + 1);
+    ///     }
+    /// }
+    ///
+    /// let mut trainer = AgentTrainer::new();
+    /// let mut agent = Walker { state: Corridor(0) };
+    /// let exploration = Recording::default();
+    /// trainer.train(
+    ///     &mut agent,
+    ///     &QLearning::new(0.2, 0.9, 0.),
+    ///     &mut FixedIterations::new(30),
+    ///     &exploration,
+    /// );
+    ///
+    /// let expected: Vec<u64> = (0..30).map(|step| step / 10).collect();
+    /// assert_eq!(*exploration.episodes.borrow(), expected);
+    /// assert_eq!(exploration.ends.get(), 3);
+    /// assert_eq!(trainer.last_stats().unwrap().episodes, 3);
+    /// ```
+    fn on_episode_end(&self) {}
+
+    /// Called instead of [on_episode_end](ExplorationStrategy::on_episode_end) when training with
+    /// [train_mut](../../struct.AgentTrainer.html#method.train_mut), with mutable access to the
+    /// strategy. By default, [on_episode_end](ExplorationStrategy::on_episode_end) is called.
+    fn on_episode_end_mut(&mut self) {
+        self.on_episode_end()
+    }
+}
+
+/// Trait for exploration strategies that are controlled by a parameter, which can change during
+/// training. See
+/// [AgentTrainer::train_with_schedule](../../struct.AgentTrainer.html#method.train_with_schedule).
+pub trait ParameterizedExplorationStrategy<S: State> {
+    /// Selects the next action to take for this `Agent`, given the current value of the
+    /// parameter and the best learned action in the current `State`, if any.
+    fn pick_action(
+        &self,
+        agent: &mut dyn Agent<S>,
+        parameter: f64,
+        best_action: Option<S::A>,
+    ) -> S::A;
+}
+
+/// Takes the action of the current `State` of the agent if it is the only action, and returns it.
+/// Strategies use this to pick a forced move without consulting learned values or drawing random
+/// numbers.
+pub(crate) fn take_only_action<S: State>(agent: &mut dyn Agent<S>) -> Option<S::A> {
+    let mut actions = agent.current_state().actions();
+    if actions.len() != 1 {
+        return None;
+    }
+    let action = actions.pop()?;
+    agent.take_action(&action);
+    Some(action)
 }
@@ -0,0 +1,160 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rand::{Rng, RngCore};
+
+use crate::mdp::{Agent, State};
+use crate::strategy::explore::{take_only_action, ExplorationStrategy};
+use crate::table::QTable;
+use crate::AgentTrainer;
+
+/// The epsilon-greedy exploration strategy, with an epsilon that decays once per episode instead
+/// of once per step. With probability epsilon, a random action is taken, and otherwise the best
+/// action of a snapshot of the policy learned by an
+/// [AgentTrainer](../../struct.AgentTrainer.html). When the snapshot has no action for the
+/// current `State`, a random action is taken as well.
+///
+/// Every time an episode ends, epsilon is multiplied by the decay factor, down to a minimum. The
+/// snapshot is taken with [sync](PerEpisodeDecayEpsilon::sync), for example after every episode.
+///
+/// # Panics
+///
+/// Panics when asked to pick an action in a `State` without actions.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinState { balance: i32 }
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinAction { bet: i32 }
+/// # impl State for CoinState {
+/// #     type A = CoinAction;
+/// #     fn reward(&self) -> f64 { if self.balance >= 100 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<CoinAction> {
+/// #         let max_bet = if self.balance < 50 { self.balance } else { 100 - self.balance };
+/// #         (1..max_bet + 1).map(|bet| CoinAction { bet }).collect()
+/// #     }
+/// # }
+/// // A coin that lands heads with a probability of 101/256.
+/// struct CoinAgent { state: CoinState }
+/// # impl Agent<CoinState> for CoinAgent {
+/// #     fn current_state(&self) -> &CoinState { &self.state }
+/// #     fn take_action(&mut self, action: &CoinAction) {
+/// #         self.state = CoinState {
+/// #             balance: if rand::random::<u8>() <= 100 {
+/// #                 self.state.balance + action.bet
+/// #             } else {
+/// #                 self.state.balance - action.bet
+/// #             },
+/// #         }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::PerEpisodeDecayEpsilon;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// let mut exploration = PerEpisodeDecayEpsilon::new(1.0, 0.5, 0.1);
+/// let mut trainer = AgentTrainer::new();
+/// let mut expected = 1.0;
+/// for _ in 0..10 {
+///     // Every episode ends when the game is won or lost.
+///     let mut agent = CoinAgent { state: CoinState { balance: 50 } };
+///     let mut epsilons = Vec::new();
+///     trainer.train_with_callback(
+///         &mut agent,
+///         &QLearning::new(0.2, 1.0, 0.),
+///         &mut SinkStates {},
+///         &exploration,
+///         &mut |_| epsilons.push(exploration.epsilon()),
+///     );
+///     // Epsilon does not change during an episode, and decays when it ends.
+///     assert!(epsilons.iter().all(|&epsilon| epsilon == expected));
+///     expected = f64::max(expected * 0.5, 0.1);
+///     assert_eq!(exploration.epsilon(), expected);
+///     exploration.sync(&trainer);
+/// }
+/// ```
+pub struct PerEpisodeDecayEpsilon<S: State> {
+    epsilon: Mutex<f64>,
+    decay: f64,
+    min_epsilon: f64,
+    policy: HashMap<S, S::A>,
+}
+
+impl<S: State> PerEpisodeDecayEpsilon<S> {
+    /// Constructs the strategy, starting with `epsilon`, which is multiplied by `decay` at the end
+    /// of every episode, but does not decay below `min_epsilon`. Until the first
+    /// [sync](PerEpisodeDecayEpsilon::sync), all actions are random.
+    pub fn new(epsilon: f64, decay: f64, min_epsilon: f64) -> PerEpisodeDecayEpsilon<S> {
+        PerEpisodeDecayEpsilon {
+            epsilon: Mutex::new(epsilon),
+            decay,
+            min_epsilon,
+            policy: HashMap::new(),
+        }
+    }
+
+    /// Returns the current probability of taking a random action.
+    pub fn epsilon(&self) -> f64 {
+        *self.epsilon.lock().unwrap()
+    }
+
+    /// Updates the snapshot of the policy to the current best actions of `trainer`.
+    pub fn sync<T: QTable<S>>(&mut self, trainer: &AgentTrainer<S, T>) {
+        self.policy = trainer.export_policy();
+    }
+}
+
+impl<S: State> ExplorationStrategy<S> for PerEpisodeDecayEpsilon<S> {
+    fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
+        if let Some(action) = take_only_action(agent) {
+            return action;
+        }
+        match self.policy.get(agent.current_state()) {
+            Some(action) if rand::random::<f64>() >= self.epsilon() => {
+                let action = action.clone();
+                agent.take_action(&action);
+                action
+            }
+            _ => agent
+                .pick_random_action()
+                .expect("no actions can be taken from the current State"),
+        }
+    }
+
+    /// Picks an action like [pick_action](ExplorationStrategy::pick_action), drawing whether to
+    /// explore, and the random action, from `rng`.
+    fn pick_action_with(
+        &self,
+        agent: &mut dyn Agent<S>,
+        _step: u64,
+        _episode: u64,
+        rng: &mut dyn RngCore,
+    ) -> S::A {
+        if let Some(action) = take_only_action(agent) {
+            return action;
+        }
+        match self.policy.get(agent.current_state()) {
+            Some(action) if rng.gen::<f64>() >= self.epsilon() => {
+                let action = action.clone();
+                agent.take_action(&action);
+                action
+            }
+            _ => agent
+                .pick_random_action_with(rng)
+                .expect("no actions can be taken from the current State"),
+        }
+    }
+
+    fn on_episode_end(&self) {
+        let mut epsilon = self.epsilon.lock().unwrap();
+        *epsilon = f64::max(*epsilon * self.decay, self.min_epsilon);
+    }
+}
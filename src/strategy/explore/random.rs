@@ -2,17 +2,103 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::sync::Mutex;
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
 use crate::mdp::{Agent, State};
 use crate::strategy::explore::ExplorationStrategy;
 
 /// The random exploration strategy. This strategy always takes a random action, as defined for the
 /// Agent by
-/// [Agent::take_random_action()](../../../mdp/trait.Agent.html#method.take_random_action)
-pub struct RandomExploration;
+/// [Agent::pick_random_action()](../../mdp/trait.Agent.html#method.pick_random_action)
+///
+/// # Panics
+///
+/// Panics when asked to pick an action in a `State` without actions. Use a termination strategy
+/// that stops in such states, like
+/// [SinkStates](../terminate/struct.SinkStates.html).
+pub struct RandomExploration {
+    rng: Option<Mutex<Box<dyn RngCore + Send>>>,
+}
 
 impl RandomExploration {
     pub fn new() -> RandomExploration {
-        RandomExploration
+        RandomExploration { rng: None }
+    }
+
+    /// Creates a `RandomExploration` that draws its actions from a random number generator seeded
+    /// with the given seed, using
+    /// [Agent::pick_random_action_with()](../../mdp/trait.Agent.html#method.pick_random_action_with).
+    /// Training with the same seed from the same starting state learns the same values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// let train = || {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+    ///     trainer.train(
+    ///         &mut agent,
+    ///         &QLearning::new(0.2, 0.01, 2.),
+    ///         &mut FixedIterations::new(10000),
+    ///         &RandomExploration::with_seed(42),
+    ///     );
+    ///     trainer
+    /// };
+    /// assert!(train().learned_values() == train().learned_values());
+    /// ```
+    pub fn with_seed(seed: u64) -> RandomExploration {
+        RandomExploration::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    /// Creates a `RandomExploration` that draws its actions from the given random number
+    /// generator, using
+    /// [Agent::pick_random_action_with()](../../mdp/trait.Agent.html#method.pick_random_action_with).
+    /// This allows controlling the actions that are taken, for example with a mock generator in
+    /// tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rurel::mdp::{Agent, State};
+    /// # #[derive(PartialEq, Eq, Hash, Clone)]
+    /// # struct MyState;
+    /// # impl State for MyState {
+    /// #     type A = u8;
+    /// #     fn reward(&self) -> f64 { 0. }
+    /// #     fn actions(&self) -> Vec<u8> { (0..10).collect() }
+    /// # }
+    /// // An agent that stays in the same state, whatever action it takes.
+    /// struct MyAgent { state: MyState }
+    /// # impl Agent<MyState> for MyAgent {
+    /// #     fn current_state(&self) -> &MyState { &self.state }
+    /// #     fn take_action(&mut self, _action: &u8) {}
+    /// # }
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rurel::strategy::explore::{ExplorationStrategy, RandomExploration};
+    ///
+    /// let actions = |exploration: RandomExploration| {
+    ///     let mut agent = MyAgent { state: MyState };
+    ///     (0..100).map(|_| exploration.pick_action(&mut agent)).collect::<Vec<_>>()
+    /// };
+    /// let first = actions(RandomExploration::with_rng(StdRng::seed_from_u64(1)));
+    /// let second = actions(RandomExploration::with_rng(StdRng::seed_from_u64(1)));
+    /// assert_eq!(first, second);
+    /// assert_ne!(first, actions(RandomExploration::with_rng(StdRng::seed_from_u64(2))));
+    /// ```
+    pub fn with_rng(rng: impl RngCore + Send + 'static) -> RandomExploration {
+        RandomExploration {
+            rng: Some(Mutex::new(Box::new(rng))),
+        }
     }
 }
 
@@ -24,6 +110,26 @@ impl Default for RandomExploration {
 
 impl<S: State> ExplorationStrategy<S> for RandomExploration {
     fn pick_action(&self, agent: &mut dyn Agent<S>) -> S::A {
-        agent.pick_random_action()
+        match &self.rng {
+            Some(rng) => agent.pick_random_action_with(rng.lock().unwrap().as_mut()),
+            None => agent.pick_random_action(),
+        }
+        .expect("no actions can be taken from the current State")
+    }
+
+    /// Takes a random action drawn from the random number generator of this strategy if it has
+    /// one, and from `rng` otherwise.
+    fn pick_action_with(
+        &self,
+        agent: &mut dyn Agent<S>,
+        _step: u64,
+        _episode: u64,
+        rng: &mut dyn RngCore,
+    ) -> S::A {
+        match &self.rng {
+            Some(own) => agent.pick_random_action_with(own.lock().unwrap().as_mut()),
+            None => agent.pick_random_action_with(rng),
+        }
+        .expect("no actions can be taken from the current State")
     }
 }
@@ -0,0 +1,138 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the count-based Q Learning strategy.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::compare_values;
+use crate::mdp::State;
+use crate::strategy::learn::LearningStrategy;
+
+/// The Q Learning strategy with a learning rate per state-action pair, of `1 / N` for the `N`th
+/// update of the pair. The first update of a pair replaces the initial value, and every learned
+/// value is the average of all targets it was updated towards. This lets the learned values
+/// converge when rewards are stochastic.
+///
+/// The update counts are kept by the strategy, so the same `CountBasedQLearning` should be used
+/// for the whole training. Note that a learning rate that decays this fast may slow down early
+/// learning, since targets that are based on poor estimates of the next state are weighted as
+/// much as later, better ones.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinState { balance: i32 }
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinAction { bet: i32 }
+/// # impl State for CoinState {
+/// #     type A = CoinAction;
+/// #     fn reward(&self) -> f64 { if self.balance >= 100 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<CoinAction> {
+/// #         let max_bet = if self.balance < 50 { self.balance } else { 100 - self.balance };
+/// #         (1..max_bet + 1).map(|bet| CoinAction { bet }).collect()
+/// #     }
+/// # }
+/// // A coin that lands heads with a probability of 101/256.
+/// struct CoinAgent { state: CoinState }
+/// # impl Agent<CoinState> for CoinAgent {
+/// #     fn current_state(&self) -> &CoinState { &self.state }
+/// #     fn take_action(&mut self, action: &CoinAction) {
+/// #         self.state = CoinState {
+/// #             balance: if rand::random::<u8>() <= 100 {
+/// #                 self.state.balance + action.bet
+/// #             } else {
+/// #                 self.state.balance - action.bet
+/// #             },
+/// #         }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::CountBasedQLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+///
+/// let learning = CountBasedQLearning::new(0., 0.);
+/// let mut trainer = AgentTrainer::new();
+/// for _ in 0..10000 {
+///     let mut agent = CoinAgent { state: CoinState { balance: 99 } };
+///     trainer.train(
+///         &mut agent,
+///         &learning,
+///         &mut FixedIterations::new(1),
+///         &RandomExploration::new(),
+///     );
+/// }
+///
+/// // Betting 1 from 99 wins the game with the probability of heads.
+/// let (state, action) = (CoinState { balance: 99 }, CoinAction { bet: 1 });
+/// assert_eq!(learning.updates(&state, &action), 9999);
+/// assert!(learning.alpha(&state, &action) < 1e-3);
+/// let value = trainer.expected_value(&state, &action).unwrap();
+/// assert!((value - 101. / 256.).abs() < 0.02);
+/// ```
+pub struct CountBasedQLearning<S: State> {
+    gamma: f64,
+    initial_value: f64,
+    updates: Mutex<HashMap<(S, S::A), u64>>,
+}
+
+impl<S: State> CountBasedQLearning<S> {
+    /// Constructs the count-based Q Learning strategy, with discount factor `gamma` and the
+    /// initial value for Q `initial_value`.
+    pub fn new(gamma: f64, initial_value: f64) -> CountBasedQLearning<S> {
+        CountBasedQLearning {
+            gamma,
+            initial_value,
+            updates: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of times the value of the given action in the given `State` was
+    /// updated. Setting the initial value is not counted as an update.
+    pub fn updates(&self, state: &S, action: &S::A) -> u64 {
+        self.updates
+            .lock()
+            .unwrap()
+            .get(&(state.clone(), action.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the learning rate of the next update of the value of the given action in the given
+    /// `State`.
+    pub fn alpha(&self, state: &S, action: &S::A) -> f64 {
+        1. / (self.updates(state, action) + 1) as f64
+    }
+}
+
+impl<S: State> LearningStrategy<S> for CountBasedQLearning<S> {
+    fn value(
+        &self,
+        state: &S,
+        action: &S::A,
+        new_action_values: &Option<&HashMap<S::A, f64>>,
+        old_value: &Option<&f64>,
+        reward_after_action: f64,
+        terminal: bool,
+    ) -> f64 {
+        let max_next = if terminal {
+            &0.
+        } else {
+            new_action_values
+                .and_then(|m| m.values().max_by(|&&a, &&b| compare_values(a, b)))
+                .unwrap_or(&self.initial_value)
+        };
+        old_value.map_or(self.initial_value, |x| {
+            let mut updates = self.updates.lock().unwrap();
+            let n = updates.entry((state.clone(), action.clone())).or_insert(0);
+            *n += 1;
+            (x + (reward_after_action + self.gamma * max_next - x) / *n as f64)
+                .clamp(f64::MIN, f64::MAX)
+        })
+    }
+}
@@ -0,0 +1,113 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Mellowmax learning strategy.
+
+use std::collections::HashMap;
+
+use crate::mdp::State;
+use crate::strategy::learn::q::NextValueAggregator;
+use crate::strategy::learn::LearningStrategy;
+
+/// The Mellowmax learning strategy, which is Q Learning where the value of the new state is the
+/// mellowmax `ln(mean(exp(omega * Q(s', a)))) / omega` of its learned values, instead of their
+/// maximum. Taking the maximum of noisy learned values overestimates the value of the new state,
+/// which mellowmax does less. With a large `omega`, mellowmax approaches the maximum, and with an
+/// `omega` close to 0, it approaches the mean.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // From the start, the agent moves to a choice between two fair coins, which are worth 1 when
+/// // they land heads and -1 when they land tails. The value of the start is 0.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// enum CoinState { Start, Choice, Coin(bool) }
+/// # impl State for CoinState {
+/// #     type A = u8;
+/// #     fn reward(&self) -> f64 {
+/// #         match self { CoinState::Coin(true) => 1., CoinState::Coin(false) => -1., _ => 0. }
+/// #     }
+/// #     fn actions(&self) -> Vec<u8> {
+/// #         match self { CoinState::Start => vec![0], CoinState::Choice => vec![0, 1], _ => vec![] }
+/// #     }
+/// # }
+/// # struct CoinAgent { state: CoinState }
+/// # impl Agent<CoinState> for CoinAgent {
+/// #     fn current_state(&self) -> &CoinState { &self.state }
+/// #     fn take_action(&mut self, _: &u8) {
+/// #         self.state = match self.state {
+/// #             CoinState::Start => CoinState::Choice,
+/// #             _ => CoinState::Coin(rand::random()),
+/// #         }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::{LearningStrategy, Mellowmax, QLearning};
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// // The mean learned value of the start during training.
+/// let start_value = |learning: &dyn LearningStrategy<CoinState>| {
+///     let mut trainer = AgentTrainer::new();
+///     let mut sum = 0.;
+///     for _ in 0..2000 {
+///         let mut agent = CoinAgent { state: CoinState::Start };
+///         trainer.train(&mut agent, learning, &mut SinkStates {}, &RandomExploration::new());
+///         sum += trainer.expected_value(&CoinState::Start, &0).unwrap();
+///     }
+///     sum / 2000.
+/// };
+///
+/// let q_learning = start_value(&QLearning::new(0.5, 1., 0.));
+/// let mellowmax = start_value(&Mellowmax::new(0.5, 1., 0., 1.));
+/// assert!(q_learning > 0.1);
+/// assert!(mellowmax < q_learning);
+/// ```
+pub struct Mellowmax {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    omega: f64,
+}
+
+impl Mellowmax {
+    /// Constructs the Mellowmax learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the initial value for Q `initial_value`, and the mellowmax parameter `omega`,
+    /// which must be positive.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, omega: f64) -> Mellowmax {
+        Mellowmax {
+            alpha,
+            gamma,
+            initial_value,
+            omega,
+        }
+    }
+}
+
+impl<S: State> LearningStrategy<S> for Mellowmax {
+    fn value(
+        &self,
+        _state: &S,
+        _action: &S::A,
+        new_action_values: &Option<&HashMap<S::A, f64>>,
+        old_value: &Option<&f64>,
+        reward_after_action: f64,
+        terminal: bool,
+    ) -> f64 {
+        let next = if terminal {
+            0.
+        } else {
+            new_action_values
+                .and_then(|m| {
+                    NextValueAggregator::Mellowmax(self.omega).aggregate(m.values().cloned())
+                })
+                .unwrap_or(self.initial_value)
+        };
+        old_value.map_or(self.initial_value, |x| {
+            (x + self.alpha * (reward_after_action + self.gamma * next - x))
+                .clamp(f64::MIN, f64::MAX)
+        })
+    }
+}
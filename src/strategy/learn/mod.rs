@@ -6,22 +6,39 @@
 
 use std::collections::HashMap;
 
+pub use self::count_based::CountBasedQLearning;
+pub use self::mellowmax::Mellowmax;
+pub use self::monte_carlo::{MonteCarlo, VisitMode};
 pub use self::q::QLearning;
+pub use self::sarsa_lambda::{SarsaLambda, TraceMode};
+pub use self::tree_backup::TreeBackup;
 use crate::mdp::State;
 
+pub mod count_based;
+pub mod mellowmax;
+pub mod monte_carlo;
 pub mod q;
+pub mod sarsa_lambda;
+pub mod tree_backup;
 
 /// A learning strategy can calculate a learned value for the action which was taken from the
-/// values for the actions in the new state (`new_action_values`), the current value
-/// (`current_value`), and the reward that was received after taking the action.
+/// state and the action, the values for the actions in the new state (`new_action_values`), the
+/// current value (`current_value`), the reward that was received after taking the action, and
+/// whether the new state is terminal.
 pub trait LearningStrategy<S: State> {
-    /// Calculates a learned value for the action which was taken from the
+    /// Calculates a learned value for the `action` which was taken from `state`, from the
     /// values for the actions in the new state (`new_action_values`), the current value
-    /// (`current_value`), and the reward that was received after taking the action.
+    /// (`current_value`), and the reward that was received after taking the action. `terminal` is
+    /// `true` when the new state is a sink state (a `State` without actions) or when the agent
+    /// marks it as [terminal](../../mdp/trait.Agent.html#method.is_terminal), in which case no
+    /// future rewards follow and the new state should not be bootstrapped from.
     fn value(
         &self,
+        state: &S,
+        action: &S::A,
         new_action_values: &Option<&HashMap<S::A, f64>>,
         current_value: &Option<&f64>,
         received_reward: f64,
+        terminal: bool,
     ) -> f64;
 }
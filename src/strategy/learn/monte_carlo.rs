@@ -0,0 +1,107 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the Monte Carlo learning strategy.
+
+/// Decides which occurrences of an action in a `State` during an episode are learned from by
+/// [MonteCarlo] learning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitMode {
+    /// Only learn from the first time the action is taken in the `State` during an episode.
+    FirstVisit,
+    /// Learn from every time the action is taken in the `State` during an episode.
+    EveryVisit,
+}
+
+/// The Monte Carlo learning strategy, used by
+/// [AgentTrainer::train_monte_carlo()](../../../struct.AgentTrainer.html#method.train_monte_carlo).
+/// Instead of bootstrapping from the learned values of the next state like Q Learning, the
+/// learned values move towards the discounted return that actually followed an action, which is
+/// only known at the end of an episode. This makes the learned values unbiased, at the cost of a
+/// higher variance.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A corridor of 4 states which is walked to the right, with a reward at the end.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Corridor(i32);
+/// # impl State for Corridor {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { if self.0 == 3 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<()> { if self.0 == 3 { vec![] } else { vec![()] } }
+/// # }
+/// # struct Walker { state: Corridor }
+/// # impl Agent<Corridor> for Walker {
+/// #     fn current_state(&self) -> &Corridor { &self.state }
+/// #     fn take_action(&mut self, _: &()) { self.state = Corridor(self.state.0 + 1) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::{MonteCarlo, VisitMode};
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// for visits in [VisitMode::FirstVisit, VisitMode::EveryVisit] {
+///     let mut trainer = AgentTrainer::new();
+///     for _ in 0..100 {
+///         let mut agent = Walker { state: Corridor(0) };
+///         trainer.train_monte_carlo(
+///             &mut agent,
+///             &MonteCarlo::new(0.5, 0.9, 0., visits),
+///             &mut SinkStates {},
+///             &RandomExploration::new(),
+///         );
+///     }
+///
+///     // The learned values are the discounted rewards until the end of the corridor.
+///     for (x, value) in [(0, 0.81), (1, 0.9), (2, 1.)] {
+///         assert!((trainer.expected_value(&Corridor(x), &()).unwrap() - value).abs() < 1e-6);
+///     }
+/// }
+/// ```
+pub struct MonteCarlo {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    visits: VisitMode,
+}
+
+impl MonteCarlo {
+    /// Constructs the Monte Carlo learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, the initial value for Q `initial_value`, and which occurrences of an action in an
+    /// episode to learn from.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, visits: VisitMode) -> MonteCarlo {
+        MonteCarlo {
+            alpha,
+            gamma,
+            initial_value,
+            visits,
+        }
+    }
+
+    /// Returns which occurrences of an action in an episode are learned from.
+    pub fn visits(&self) -> VisitMode {
+        self.visits
+    }
+
+    /// Calculates the discounted return that followed each step of an episode, given the rewards
+    /// received after each step.
+    pub fn returns(&self, rewards: &[f64]) -> Vec<f64> {
+        let mut returns = vec![0.; rewards.len()];
+        let mut ret = 0.;
+        for (i, reward) in rewards.iter().enumerate().rev() {
+            ret = reward + self.gamma * ret;
+            returns[i] = ret;
+        }
+        returns
+    }
+
+    /// Calculates the learned value for an action from its current value, or the initial value
+    /// if no value was learned yet, and the discounted return that followed it.
+    pub fn value(&self, current_value: Option<f64>, ret: f64) -> f64 {
+        let current_value = current_value.unwrap_or(self.initial_value);
+        (current_value + self.alpha * (ret - current_value)).clamp(f64::MIN, f64::MAX)
+    }
+}
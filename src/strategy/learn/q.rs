@@ -5,41 +5,432 @@
 //! Module for the Q Learning strategy.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::compare_values;
 use crate::mdp::State;
 use crate::strategy::learn::LearningStrategy;
 
-/// The Q Learning strategy
+/// The Q Learning strategy. When the action leads to a sink state (a `State` without actions), the
+/// learned value moves towards the received reward only, since no future rewards follow.
+///
+/// Learned values that would be infinite, for example because of an infinite reward, are clamped
+/// to the largest finite values. A NaN reward makes the learned value NaN, but NaN values are
+/// only taken as the best value of a `State` when all its values are NaN.
+///
+/// When the new `State` is terminal, nothing is bootstrapped from it. When it has no learned values
+/// yet, either because it was never visited or because its map of values is empty, its best value
+/// is taken to be the initial value.
+///
+/// The value that is bootstrapped from the new `State` is the best of its values by default, and
+/// can be changed to another [NextValueAggregator] using
+/// [with_aggregator](QLearning::with_aggregator).
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinState { balance: i32 }
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct CoinAction { bet: i32 }
+/// # impl State for CoinState {
+/// #     type A = CoinAction;
+/// #     fn reward(&self) -> f64 { if self.balance >= 100 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<CoinAction> {
+/// #         let max_bet = if self.balance < 50 { self.balance } else { 100 - self.balance };
+/// #         (1..max_bet + 1).map(|bet| CoinAction { bet }).collect()
+/// #     }
+/// # }
+/// // A coin that always lands heads, so every bet is won.
+/// struct CoinAgent { state: CoinState }
+/// # impl Agent<CoinState> for CoinAgent {
+/// #     fn current_state(&self) -> &CoinState { &self.state }
+/// #     fn take_action(&mut self, action: &CoinAction) {
+/// #         self.state = CoinState { balance: self.state.balance + action.bet }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// let mut trainer = AgentTrainer::new();
+/// for trial in 0..100000 {
+///     let mut agent = CoinAgent { state: CoinState { balance: 50 + trial % 50 } };
+///     trainer.train(
+///         &mut agent,
+///         &QLearning::new(1.0, 1.0, 5.0),
+///         &mut SinkStates {},
+///         &RandomExploration::new(),
+///     );
+/// }
+///
+/// // Betting everything wins the game, which is worth the reward of the terminal state.
+/// for balance in 50..100 {
+///     let state = CoinState { balance };
+///     let all_in = CoinAction { bet: 100 - balance };
+///     assert_eq!(trainer.expected_value(&state, &all_in), Some(1.0));
+/// }
+/// ```
+///
+/// The best value of the new `State` that is bootstrapped from, for an empty map of values, a map
+/// with a NaN value and a terminal new `State`:
+///
+/// ```
+/// # use rurel::fixtures::line::MyState;
+/// use std::collections::HashMap;
+///
+/// use rurel::strategy::learn::{LearningStrategy, QLearning};
+///
+/// let learning = QLearning::new(1.0, 0.5, 2.0);
+/// // Learns the value of action 1 in state 0, with a reward of 1 and the given new values.
+/// let learn = |new_values: &HashMap<i32, f64>, terminal: bool| {
+///     learning.value(&MyState(0), &1, &Some(new_values), &Some(&0.), 1., terminal)
+/// };
+///
+/// let empty = HashMap::new();
+/// assert_eq!(learn(&empty, false), 1. + 0.5 * 2.);
+/// assert_eq!(learn(&empty, true), 1.);
+///
+/// let with_nan = HashMap::from([(-1, f64::NAN), (1, 4.)]);
+/// assert_eq!(learn(&with_nan, false), 1. + 0.5 * 4.);
+/// assert_eq!(learn(&with_nan, true), 1.);
+///
+/// let all_nan = HashMap::from([(-1, f64::NAN), (1, f64::NAN)]);
+/// assert!(learn(&all_nan, false).is_nan());
+/// assert_eq!(learn(&all_nan, true), 1.);
+/// ```
 pub struct QLearning {
-    alpha: f64,
+    alpha: Box<dyn Fn(u64) -> f64 + Send + Sync>,
     gamma: f64,
     initial_value: f64,
+    aggregator: NextValueAggregator,
+    updates: AtomicU64,
+    scheduled: bool,
 }
 
+/// The aggregator of the learned values of the new `State` into the value that
+/// [QLearning] bootstraps from. NaN values are ignored, unless all values are NaN, in which case
+/// the aggregate is NaN.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::State;
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyState(i32);
+/// # impl State for MyState {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 { -(self.0.abs() as f64) }
+/// #     fn actions(&self) -> Vec<i32> { vec![-1, 0, 1] }
+/// # }
+/// use std::collections::HashMap;
+///
+/// use rurel::strategy::learn::q::NextValueAggregator;
+/// use rurel::strategy::learn::{LearningStrategy, QLearning};
+///
+/// // Learns the value of action 1 in state 0 with a reward of 0, which for a learning rate and
+/// // discount factor of 1 is the aggregate of the new values.
+/// let new_values = HashMap::from([(-1, 1.), (0, 2.), (1, 6.)]);
+/// let learn = |aggregator| {
+///     let learning = QLearning::new(1., 1., 0.).with_aggregator(aggregator);
+///     learning.value(&MyState(0), &1, &Some(&new_values), &Some(&0.), 0., false)
+/// };
+///
+/// assert_eq!(learn(NextValueAggregator::Max), 6.);
+/// assert_eq!(learn(NextValueAggregator::Mean), 3.);
+///
+/// // Softmax weighs every value by its Boltzmann probability.
+/// let weights = [1f64.exp(), 2f64.exp(), 6f64.exp()];
+/// let softmax = (weights[0] + 2. * weights[1] + 6. * weights[2]) / weights.iter().sum::<f64>();
+/// assert!((learn(NextValueAggregator::Softmax(1.)) - softmax).abs() < 1e-12);
+/// // Without weighting, softmax is the mean.
+/// assert!((learn(NextValueAggregator::Softmax(0.)) - 3.).abs() < 1e-12);
+///
+/// // Mellowmax is the log of the mean exponentiated value.
+/// let mellowmax = (weights.iter().sum::<f64>() / 3.).ln();
+/// assert!((learn(NextValueAggregator::Mellowmax(1.)) - mellowmax).abs() < 1e-12);
+///
+/// // Both approach the maximum as their parameter grows.
+/// assert!((learn(NextValueAggregator::Softmax(100.)) - 6.).abs() < 1e-12);
+/// assert!((learn(NextValueAggregator::Mellowmax(1000.)) - 6.).abs() < 1e-2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub enum NextValueAggregator {
+    /// The best value, as in Q Learning.
+    Max,
+    /// The mean of all values, the expected value under a uniformly random policy.
+    Mean,
+    /// The expected value under a Boltzmann policy with inverse temperature `beta`: the mean of
+    /// all values, each weighted by `exp(beta * value)`. A `beta` of 0 gives the mean, and a
+    /// large `beta` approaches the best value.
+    Softmax(f64),
+    /// The mellowmax operator `log(mean(exp(omega * value))) / omega`, with `omega` positive,
+    /// like the [Mellowmax](super::Mellowmax) learning strategy.
+    Mellowmax(f64),
+}
+
+impl NextValueAggregator {
+    /// Aggregates the given values, or returns `None` if there are no values.
+    pub fn aggregate(&self, values: impl IntoIterator<Item = f64>) -> Option<f64> {
+        let mut values = values.into_iter().peekable();
+        values.peek()?;
+        let mut values: Vec<f64> = values.filter(|value| !value.is_nan()).collect();
+        // Sum in a fixed order, so that the aggregate does not depend on the order of the values.
+        values.sort_by(f64::total_cmp);
+        // The maximum is subtracted before exponentiating, so that large values do not overflow.
+        let Some(max) = values.iter().cloned().reduce(f64::max) else {
+            return Some(f64::NAN);
+        };
+        let aggregate = match *self {
+            NextValueAggregator::Max => max,
+            NextValueAggregator::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            NextValueAggregator::Softmax(beta) => {
+                let (weighted, total) = values.iter().fold((0., 0.), |(weighted, total), value| {
+                    let weight = (beta * (value - max)).exp();
+                    (weighted + weight * value, total + weight)
+                });
+                weighted / total
+            }
+            NextValueAggregator::Mellowmax(omega) => {
+                let sum: f64 = values
+                    .iter()
+                    .map(|value| (omega * (value - max)).exp())
+                    .sum();
+                max + (sum / values.len() as f64).ln() / omega
+            }
+        };
+        Some(aggregate)
+    }
+}
+
+/// An error that occurs when constructing a [QLearning] strategy with invalid parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QLearningError {
+    /// The learning rate is not between 0 and 1.
+    InvalidAlpha,
+    /// The discount factor is not between 0 and 1.
+    InvalidGamma,
+    /// The initial value is not finite.
+    InvalidInitialValue,
+}
+
+impl std::fmt::Display for QLearningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QLearningError::InvalidAlpha => write!(f, "the learning rate is not between 0 and 1"),
+            QLearningError::InvalidGamma => {
+                write!(f, "the discount factor is not between 0 and 1")
+            }
+            QLearningError::InvalidInitialValue => write!(f, "the initial value is not finite"),
+        }
+    }
+}
+
+impl std::error::Error for QLearningError {}
+
 impl QLearning {
     /// Constructs the Q Learning strategy, with learning rate `alpha`, discount factor `gamma` and
-    /// the initial value for Q `initial_value`.
+    /// the initial value for Q `initial_value`. The parameters are not validated, see
+    /// [try_new](QLearning::try_new).
     pub fn new(alpha: f64, gamma: f64, initial_value: f64) -> QLearning {
         QLearning {
-            alpha,
+            scheduled: false,
+            ..QLearning::with_alpha_schedule(move |_| alpha, gamma, initial_value)
+        }
+    }
+
+    /// Constructs the Q Learning strategy like [new](QLearning::new), or returns an error if
+    /// `alpha` or `gamma` is not between 0 and 1, or if `initial_value` is not finite. A discount
+    /// factor above 1 makes the learned values diverge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rurel::strategy::learn::q::QLearningError;
+    /// use rurel::strategy::learn::QLearning;
+    ///
+    /// let learning = QLearning::try_new(0.2, 0.01, 2.).unwrap();
+    /// assert_eq!(learning.alpha(), 0.2);
+    /// assert_eq!(learning.gamma(), 0.01);
+    /// assert_eq!(learning.initial_value(), 2.);
+    ///
+    /// assert_eq!(QLearning::try_new(0.2, 2., 0.01).err(), Some(QLearningError::InvalidGamma));
+    /// assert_eq!(QLearning::try_new(1.5, 0.5, 0.).err(), Some(QLearningError::InvalidAlpha));
+    /// assert_eq!(
+    ///     QLearning::try_new(0.2, 0.5, f64::NAN).err(),
+    ///     Some(QLearningError::InvalidInitialValue)
+    /// );
+    /// ```
+    pub fn try_new(
+        alpha: f64,
+        gamma: f64,
+        initial_value: f64,
+    ) -> Result<QLearning, QLearningError> {
+        if !(0. ..=1.).contains(&alpha) {
+            return Err(QLearningError::InvalidAlpha);
+        }
+        if !(0. ..=1.).contains(&gamma) {
+            return Err(QLearningError::InvalidGamma);
+        }
+        if !initial_value.is_finite() {
+            return Err(QLearningError::InvalidInitialValue);
+        }
+        Ok(QLearning::new(alpha, gamma, initial_value))
+    }
+
+    /// Returns the learning rate of the next update.
+    pub fn alpha(&self) -> f64 {
+        (self.alpha)(self.updates.load(Ordering::Relaxed))
+    }
+
+    /// Returns whether the learning rate follows a schedule set with
+    /// [with_alpha_schedule](QLearning::with_alpha_schedule).
+    pub fn has_alpha_schedule(&self) -> bool {
+        self.scheduled
+    }
+
+    /// Returns the discount factor.
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    /// Returns the initial value for Q.
+    pub fn initial_value(&self) -> f64 {
+        self.initial_value
+    }
+
+    /// Constructs the Q Learning strategy with a learning rate that depends on the number of
+    /// updates done by this strategy so far, with discount factor `gamma` and the initial value
+    /// for Q `initial_value`. The learning rate of the first update is `schedule(0)`. The count is
+    /// kept across trainings, as long as the same `QLearning` is used.
+    ///
+    /// A learning rate that decays slowly enough, such as `1 / (n + 1)`, lets the learned values
+    /// converge when rewards are stochastic, where a fixed learning rate keeps them fluctuating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rand::Rng;
+    /// # use rurel::mdp::{Agent, State};
+    /// // Every action flips a fair coin, which is worth 1 when it lands heads.
+    /// #[derive(PartialEq, Eq, Hash, Clone)]
+    /// struct CoinState { heads: bool }
+    /// # impl State for CoinState {
+    /// #     type A = ();
+    /// #     fn reward(&self) -> f64 { if self.heads { 1. } else { 0. } }
+    /// #     fn actions(&self) -> Vec<()> { vec![()] }
+    /// # }
+    /// # struct CoinAgent { state: CoinState }
+    /// # impl Agent<CoinState> for CoinAgent {
+    /// #     fn current_state(&self) -> &CoinState { &self.state }
+    /// #     fn take_action(&mut self, _action: &()) {
+    /// #         self.state = CoinState { heads: rand::thread_rng().gen() }
+    /// #     }
+    /// # }
+    /// use rurel::AgentTrainer;
+    /// use rurel::strategy::explore::RandomExploration;
+    /// use rurel::strategy::learn::QLearning;
+    /// use rurel::strategy::terminate::FixedIterations;
+    ///
+    /// // The final learned value, and its range over the last part of training.
+    /// let fluctuation = |learning: &QLearning| {
+    ///     let mut trainer = AgentTrainer::new();
+    ///     let mut agent = CoinAgent { state: CoinState { heads: false } };
+    ///     let mut values = vec![];
+    ///     for _ in 0..100 {
+    ///         trainer.train(
+    ///             &mut agent,
+    ///             learning,
+    ///             &mut FixedIterations::new(100),
+    ///             &RandomExploration::new(),
+    ///         );
+    ///         values.push(trainer.expected_value(&CoinState { heads: false }, &()).unwrap());
+    ///     }
+    ///     let last = &values[50..];
+    ///     let max = last.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    ///     let min = last.iter().cloned().fold(f64::INFINITY, f64::min);
+    ///     (last[last.len() - 1], max - min)
+    /// };
+    ///
+    /// let (_, fixed) = fluctuation(&QLearning::new(0.2, 0., 0.));
+    /// let (value, decaying) =
+    ///     fluctuation(&QLearning::with_alpha_schedule(|n| 1. / (n as f64 + 1.), 0., 0.));
+    /// assert!((value - 0.5).abs() < 0.05);
+    /// assert!(decaying < 0.05);
+    /// assert!(decaying < fixed);
+    /// ```
+    pub fn with_alpha_schedule(
+        schedule: impl Fn(u64) -> f64 + Send + Sync + 'static,
+        gamma: f64,
+        initial_value: f64,
+    ) -> QLearning {
+        QLearning {
+            alpha: Box::new(schedule),
             gamma,
             initial_value,
+            aggregator: NextValueAggregator::Max,
+            updates: AtomicU64::new(0),
+            scheduled: true,
         }
     }
+
+    /// Sets the aggregator of the values of the new `State` that is bootstrapped from. Defaults to
+    /// [Max](NextValueAggregator::Max).
+    pub fn with_aggregator(mut self, aggregator: NextValueAggregator) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+
+    /// Returns the aggregator of the values of the new `State` that is bootstrapped from.
+    pub fn aggregator(&self) -> NextValueAggregator {
+        self.aggregator
+    }
+}
+
+impl QLearning {
+    /// Calculates the temporal difference error of an action: the difference between the received
+    /// reward plus the discounted aggregated value of the new state, and the current value.
+    pub(crate) fn td_error<A>(
+        &self,
+        new_action_values: &Option<&HashMap<A, f64>>,
+        old_value: &Option<&f64>,
+        reward_after_action: f64,
+        terminal: bool,
+    ) -> f64 {
+        let next = if terminal {
+            0.
+        } else {
+            new_action_values
+                .and_then(|m| match self.aggregator {
+                    // The best value is found without collecting the values.
+                    NextValueAggregator::Max => {
+                        m.values().cloned().max_by(|&a, &b| compare_values(a, b))
+                    }
+                    aggregator => aggregator.aggregate(m.values().cloned()),
+                })
+                .unwrap_or(self.initial_value)
+        };
+        reward_after_action + self.gamma * next - old_value.unwrap_or(&self.initial_value)
+    }
 }
 
 impl<S: State> LearningStrategy<S> for QLearning {
     fn value(
         &self,
+        _state: &S,
+        _action: &S::A,
         new_action_values: &Option<&HashMap<S::A, f64>>,
         old_value: &Option<&f64>,
         reward_after_action: f64,
+        terminal: bool,
     ) -> f64 {
-        let max_next = new_action_values
-            .and_then(|m| m.values().max_by(|a, b| a.partial_cmp(b).unwrap()))
-            .unwrap_or(&self.initial_value);
+        let alpha = (self.alpha)(self.updates.fetch_add(1, Ordering::Relaxed));
+        let td_error = self.td_error(new_action_values, old_value, reward_after_action, terminal);
         old_value.map_or(self.initial_value, |x| {
-            x + self.alpha * (reward_after_action + self.gamma * max_next - x)
+            (x + alpha * td_error).clamp(f64::MIN, f64::MAX)
         })
     }
 }
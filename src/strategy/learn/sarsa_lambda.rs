@@ -0,0 +1,135 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the SARSA(λ) learning strategy.
+
+/// Decides how the eligibility trace of an action in a `State` changes when the action is taken
+/// again by [SarsaLambda] learning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceMode {
+    /// Add 1 to the trace, so actions that are taken repeatedly get a trace larger than 1.
+    Accumulating,
+    /// Reset the trace to 1.
+    Replacing,
+}
+
+/// The SARSA(λ) learning strategy, used by
+/// [AgentTrainer::train_sarsa_lambda()](../../../struct.AgentTrainer.html#method.train_sarsa_lambda).
+/// SARSA learns on-policy: the learned value of an action moves towards the reward plus the
+/// discounted value of the action that is actually taken next, instead of the best action like
+/// Q Learning. With eligibility traces, every step also updates the actions taken earlier in the
+/// episode, weighted by a trace that decays by `gamma * lambda` every step. A `lambda` of 0 only
+/// updates the last action like one-step SARSA, and a `lambda` of 1 approaches Monte Carlo
+/// learning.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A corridor of 4 states which is walked to the right, with a reward at the end.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Corridor(i32);
+/// # impl State for Corridor {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { if self.0 == 3 { 1.0 } else { 0.0 } }
+/// #     fn actions(&self) -> Vec<()> { if self.0 == 3 { vec![] } else { vec![()] } }
+/// # }
+/// # struct Walker { state: Corridor }
+/// # impl Agent<Corridor> for Walker {
+/// #     fn current_state(&self) -> &Corridor { &self.state }
+/// #     fn take_action(&mut self, _: &()) { self.state = Corridor(self.state.0 + 1) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::{SarsaLambda, TraceMode};
+/// use rurel::strategy::terminate::SinkStates;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = Walker { state: Corridor(0) };
+/// trainer.train_sarsa_lambda(
+///     &mut agent,
+///     &SarsaLambda::new(1., 0.9, 1., 0., TraceMode::Replacing),
+///     &mut SinkStates {},
+///     &RandomExploration::new(),
+/// );
+///
+/// // A single episode teaches the discounted reward to every step of the corridor.
+/// for (x, value) in [(0, 0.81), (1, 0.9), (2, 1.)] {
+///     assert!((trainer.expected_value(&Corridor(x), &()).unwrap() - value).abs() < 1e-9);
+/// }
+/// ```
+pub struct SarsaLambda {
+    alpha: f64,
+    gamma: f64,
+    lambda: f64,
+    initial_value: f64,
+    trace_mode: TraceMode,
+}
+
+impl SarsaLambda {
+    /// Constructs the SARSA(λ) learning strategy, with learning rate `alpha`, discount factor
+    /// `gamma`, trace decay `lambda`, the initial value for Q `initial_value`, and how traces
+    /// change when an action is taken again.
+    pub fn new(
+        alpha: f64,
+        gamma: f64,
+        lambda: f64,
+        initial_value: f64,
+        trace_mode: TraceMode,
+    ) -> SarsaLambda {
+        SarsaLambda {
+            alpha,
+            gamma,
+            lambda,
+            initial_value,
+            trace_mode,
+        }
+    }
+
+    /// Returns how traces change when an action is taken again.
+    pub fn trace_mode(&self) -> TraceMode {
+        self.trace_mode
+    }
+
+    /// Returns the factor by which all traces decay after every step, `gamma * lambda`.
+    pub fn decay(&self) -> f64 {
+        self.gamma * self.lambda
+    }
+
+    /// Calculates the temporal difference error of a step, from the current value of the action
+    /// that was taken, the reward that was received after taking it, and the current value of the
+    /// action taken next. Values that were not learned yet are the initial value. When the next
+    /// state is `terminal`, no future rewards follow and the next value is ignored.
+    pub fn td_error(
+        &self,
+        current_value: Option<f64>,
+        reward: f64,
+        next_value: Option<f64>,
+        terminal: bool,
+    ) -> f64 {
+        let current_value = current_value.unwrap_or(self.initial_value);
+        let next_value = if terminal {
+            0.
+        } else {
+            next_value.unwrap_or(self.initial_value)
+        };
+        reward + self.gamma * next_value - current_value
+    }
+
+    /// Calculates the trace of an action that is taken, from its trace before.
+    pub fn visit(&self, trace: f64) -> f64 {
+        match self.trace_mode {
+            TraceMode::Accumulating => trace + 1.,
+            TraceMode::Replacing => 1.,
+        }
+    }
+
+    /// Calculates the learned value for an action from its current value, or the initial value if
+    /// no value was learned yet, the temporal difference error of the last step and the trace of
+    /// the action.
+    pub fn value(&self, current_value: Option<f64>, td_error: f64, trace: f64) -> f64 {
+        let current_value = current_value.unwrap_or(self.initial_value);
+        (current_value + self.alpha * td_error * trace).clamp(f64::MIN, f64::MAX)
+    }
+}
@@ -0,0 +1,112 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the n-step Tree Backup learning strategy.
+
+/// The n-step Tree Backup learning strategy, used by
+/// [AgentTrainer::train_tree_backup()](../../../struct.AgentTrainer.html#method.train_tree_backup).
+/// Like Q Learning, it learns the values of the greedy policy from the actions taken by any
+/// exploration strategy, but it backs up the rewards of up to `n` steps at once instead of a
+/// single step, which propagates rewards faster.
+///
+/// The return of a step is its reward, plus the discounted expected value of the next state under
+/// the greedy policy, where the value of the action that was actually taken next is replaced by
+/// the return that followed it. Under the greedy policy, only the best actions have a
+/// probability, split evenly between them when several actions have the same value. So the
+/// backup continues through the following steps as long as the exploration strategy takes a best
+/// action, and is cut at the first step that explores, where it bootstraps from the best value
+/// instead. No importance sampling is needed, and the probabilities with which the exploration
+/// strategy picks its actions do not have to be known. The more the exploration strategy
+/// explores, the shorter the backups are on average, down to the single step of Q Learning.
+///
+/// # Examples
+///
+/// The return of a step with a reward of 1, for different values of the two actions of the next
+/// state, and the action that was taken next and the return that followed it:
+///
+/// ```
+/// use rurel::strategy::learn::TreeBackup;
+///
+/// let learning = TreeBackup::new(1., 0.5, 0., 2);
+///
+/// // The best action was taken next, so the backup continues with the return that followed it.
+/// assert_eq!(learning.backup(1., &[Some(2.), Some(4.)], Some((1, 10.))), 1. + 0.5 * 10.);
+/// // Another action was taken next, so the backup is cut and the best value is bootstrapped.
+/// assert_eq!(learning.backup(1., &[Some(2.), Some(4.)], Some((0, 10.))), 1. + 0.5 * 4.);
+/// // Both actions are best, so both have a probability of 0.5.
+/// assert_eq!(
+///     learning.backup(1., &[Some(4.), Some(4.)], Some((0, 10.))),
+///     1. + 0.5 * (0.5 * 4. + 0.5 * 10.)
+/// );
+/// // Without a next action, the best value is bootstrapped, where an unlearned value is the
+/// // initial value.
+/// assert_eq!(learning.backup(1., &[None, Some(-1.)], None), 1.);
+/// // A terminal next state has no value.
+/// assert_eq!(learning.backup(1., &[], None), 1.);
+/// ```
+pub struct TreeBackup {
+    alpha: f64,
+    gamma: f64,
+    initial_value: f64,
+    n: usize,
+}
+
+impl TreeBackup {
+    /// Constructs the n-step Tree Backup learning strategy, with learning rate `alpha`, discount
+    /// factor `gamma`, the initial value for Q `initial_value`, and the number of steps `n` that
+    /// are backed up at most.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn new(alpha: f64, gamma: f64, initial_value: f64, n: usize) -> TreeBackup {
+        assert!(n > 0, "at least one step must be backed up");
+        TreeBackup {
+            alpha,
+            gamma,
+            initial_value,
+            n,
+        }
+    }
+
+    /// Returns the number of steps that are backed up at most.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Calculates the return of a step that received `reward`, given the learned values of all
+    /// actions of the next state, which are empty if the next state is terminal. `continuation`
+    /// holds the index of the action that was taken next and the return that followed it, or is
+    /// `None` when the backup ends at the next state.
+    pub fn backup(
+        &self,
+        reward: f64,
+        next_values: &[Option<f64>],
+        continuation: Option<(usize, f64)>,
+    ) -> f64 {
+        let values: Vec<f64> = next_values
+            .iter()
+            .map(|value| value.unwrap_or(self.initial_value))
+            .collect();
+        if values.is_empty() {
+            return reward;
+        }
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let best = values.iter().filter(|&&value| value == max).count();
+        let expected = match continuation {
+            Some((next, ret)) if values[next] == max => {
+                // The other best actions keep their share of the probability.
+                ((best - 1) as f64 * max + ret) / best as f64
+            }
+            _ => max,
+        };
+        reward + self.gamma * expected
+    }
+
+    /// Calculates the newly learned value from the current value and the return of the step.
+    pub fn value(&self, current_value: Option<f64>, ret: f64) -> f64 {
+        let current_value = current_value.unwrap_or(self.initial_value);
+        (current_value + self.alpha * (ret - current_value)).clamp(f64::MIN, f64::MAX)
+    }
+}
@@ -9,6 +9,7 @@ use crate::strategy::terminate::TerminationStrategy;
 
 /// The termination strategy that ends after a certain number of iterations, regardless of the
 /// `State`.
+#[derive(Clone)]
 pub struct FixedIterations {
     i: u32,
     iters: u32,
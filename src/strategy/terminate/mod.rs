@@ -5,14 +5,36 @@
 //! Module containing termination strategies.
 
 pub use self::fixed_iterations::FixedIterations;
+pub use self::reward_floor::RewardFloor;
+pub use self::reward_threshold::RewardThreshold;
 pub use self::sink_states::SinkStates;
+pub use self::validation_plateau::ValidationPlateau;
 use crate::mdp::State;
 
 pub mod fixed_iterations;
+pub mod reward_floor;
+pub mod reward_threshold;
 pub mod sink_states;
+pub mod validation_plateau;
 
 /// A termination strategy decides when to end training.
 pub trait TerminationStrategy<S: State> {
     /// If `should_stop` returns `true`, training will end.
     fn should_stop(&mut self, state: &S) -> bool;
+
+    /// Decides whether to stop after a step that arrived in `state`, given the action that was
+    /// taken in the step and the reward that was received for it. This lets a strategy stop on
+    /// the reward that was actually received, such as a reward observed by the `Agent`, without
+    /// recomputing it from the `State`. The
+    /// [AgentTrainer](../../struct.AgentTrainer.html) decides whether to stop through this method.
+    /// By default, the action and the reward are ignored and
+    /// [should_stop](TerminationStrategy::should_stop) is called.
+    fn should_stop_full(
+        &mut self,
+        state: &S,
+        _last_action: Option<&S::A>,
+        _last_reward: f64,
+    ) -> bool {
+        self.should_stop(state)
+    }
 }
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the reward floor strategy.
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The termination strategy that ends when the cumulative reward of the current episode falls
+/// below a minimum, for example to cap episodes in which costs accumulate without bound. An
+/// episode ends at a sink state (a `State` without actions), after which the cumulative reward
+/// starts again at 0. During training, the rewards passed to
+/// [should_stop_full](TerminationStrategy::should_stop_full) are accumulated, and otherwise the
+/// rewards of the `State`s.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A walk to the goal at 3, which costs 1 for every step. There are no actions at the goal.
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Walk(i32);
+/// # impl State for Walk {
+/// #     type A = ();
+/// #     fn reward(&self) -> f64 { -1. }
+/// #     fn actions(&self) -> Vec<()> { if self.0 == 3 { vec![] } else { vec![()] } }
+/// # }
+/// # struct Walker { state: Walk }
+/// # impl Agent<Walk> for Walker {
+/// #     fn current_state(&self) -> &Walk { &self.state }
+/// #     fn take_action(&mut self, _: &()) { self.state = Walk(self.state.0 + 1) }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::episode::EpisodeBoundary;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::RewardFloor;
+///
+/// // Episodes end at the goal.
+/// struct AtGoal;
+/// impl EpisodeBoundary<Walk> for AtGoal {
+///     fn should_end_episode(&mut self, state: &Walk) -> bool { state.actions().is_empty() }
+/// }
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = Walker { state: Walk(0) };
+/// let mut episodes = 0;
+/// trainer.train_episodes(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut RewardFloor::new(-5.),
+///     &RandomExploration::new(),
+///     &mut AtGoal,
+///     // The third episode starts far away from the goal.
+///     &mut |agent| {
+///         episodes += 1;
+///         agent.state = Walk(if episodes == 2 { -10 } else { 0 });
+///     },
+/// );
+///
+/// // The first two episodes cost 3 each, and the third is aborted after 6 steps.
+/// assert_eq!(agent.state, Walk(-4));
+/// assert_eq!(trainer.last_stats().unwrap().steps, 12);
+/// ```
+#[derive(Clone)]
+pub struct RewardFloor {
+    min_reward: f64,
+    episode_reward: f64,
+}
+
+impl RewardFloor {
+    /// Constructs a new termination strategy which ends when the cumulative reward of an episode
+    /// falls below `min_reward`.
+    pub fn new(min_reward: f64) -> RewardFloor {
+        RewardFloor {
+            min_reward,
+            episode_reward: 0.,
+        }
+    }
+
+    /// Returns the cumulative reward of the current episode.
+    pub fn episode_reward(&self) -> f64 {
+        self.episode_reward
+    }
+
+    fn record<S: State>(&mut self, state: &S, reward: f64) -> bool {
+        self.episode_reward += reward;
+        let stop = self.episode_reward < self.min_reward;
+        // a new episode starts after a sink state, or when training is continued after stopping
+        if stop || state.actions().is_empty() {
+            self.episode_reward = 0.;
+        }
+        stop
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for RewardFloor {
+    fn should_stop(&mut self, state: &S) -> bool {
+        self.record(state, state.reward())
+    }
+
+    fn should_stop_full(&mut self, state: &S, _: Option<&S::A>, last_reward: f64) -> bool {
+        self.record(state, last_reward)
+    }
+}
@@ -0,0 +1,70 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the reward threshold strategy.
+
+use crate::mdp::State;
+use crate::strategy::terminate::TerminationStrategy;
+
+/// The termination strategy that ends as soon as a reward of at least a threshold is received,
+/// for example when a goal is reached. During training, the received reward is passed to
+/// [should_stop_full](TerminationStrategy::should_stop_full), so it also stops on rewards that
+/// the `Agent` observes. Otherwise, the reward of the `State` is compared to the threshold.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # use rurel::fixtures::line::MyState;
+/// // An agent that finds a treasure in state 3, which the reward of the state does not include.
+/// struct MyAgent { state: MyState }
+///
+/// impl Agent<MyState> for MyAgent {
+///     fn current_state(&self) -> &MyState { &self.state }
+///     fn take_action(&mut self, action: &i32) {
+///         self.state = MyState((self.state.0 + action).clamp(-5, 5))
+///     }
+///     fn observe_reward(&self) -> Option<f64> {
+///         Some(if self.state.0 == 3 { 10. } else { 0. })
+///     }
+/// }
+///
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::RewardThreshold;
+///
+/// let mut trainer = AgentTrainer::new();
+/// let mut agent = MyAgent { state: MyState(0) };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut RewardThreshold::new(10.),
+///     &RandomExploration::new(),
+/// );
+/// // Training stopped when the treasure was found, although the reward of state 3 is -3.
+/// assert_eq!(agent.state, MyState(3));
+/// ```
+#[derive(Clone)]
+pub struct RewardThreshold {
+    threshold: f64,
+}
+
+impl RewardThreshold {
+    /// Constructs a new termination strategy which ends when a reward of at least `threshold` is
+    /// received.
+    pub fn new(threshold: f64) -> RewardThreshold {
+        RewardThreshold { threshold }
+    }
+}
+
+impl<S: State> TerminationStrategy<S> for RewardThreshold {
+    fn should_stop(&mut self, state: &S) -> bool {
+        state.reward() >= self.threshold
+    }
+
+    fn should_stop_full(&mut self, _: &S, _: Option<&S::A>, last_reward: f64) -> bool {
+        last_reward >= self.threshold
+    }
+}
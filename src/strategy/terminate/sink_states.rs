@@ -8,6 +8,7 @@ use crate::mdp::State;
 use crate::strategy::terminate::TerminationStrategy;
 
 /// The termination strategy that ends if it's at a terminal state (no actions)
+#[derive(Clone)]
 pub struct SinkStates {}
 
 impl<S: State> TerminationStrategy<S> for SinkStates {
@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the validation plateau strategy.
+
+use crate::mdp::{Agent, State};
+use crate::table::QTable;
+use crate::AgentTrainer;
+
+/// Ends training when greedy validation rollouts stop improving. Every `eval_interval` steps, a
+/// greedy [rollout](../../../struct.AgentTrainer.html#method.rollout) is done from each of the
+/// start states, using an agent created by the agent factory. Training ends when the sum of the
+/// rewards of these rollouts has not improved by more than `min_delta` for `patience` evaluations
+/// in a row.
+///
+/// Because evaluating requires access to the trainer, this strategy is not a
+/// [TerminationStrategy](trait.TerminationStrategy.html) but is passed to
+/// [AgentTrainer::train_with_validation](../../../struct.AgentTrainer.html#method.train_with_validation).
+pub struct ValidationPlateau<S, F>
+where
+    S: State,
+    F: Fn(&S) -> Box<dyn Agent<S>>,
+{
+    start_states: Vec<S>,
+    agent_factory: F,
+    eval_interval: u64,
+    max_steps: usize,
+    min_delta: f64,
+    patience: u32,
+    best_reward: f64,
+    evaluations_without_improvement: u32,
+}
+
+impl<S, F> ValidationPlateau<S, F>
+where
+    S: State,
+    F: Fn(&S) -> Box<dyn Agent<S>>,
+{
+    /// Constructs a new validation plateau strategy. Every `eval_interval` steps, it does a
+    /// greedy rollout of at most `max_steps` steps from each of the `start_states`, with an agent
+    /// created by `agent_factory`.
+    pub fn new(
+        start_states: Vec<S>,
+        agent_factory: F,
+        eval_interval: u64,
+        max_steps: usize,
+        min_delta: f64,
+        patience: u32,
+    ) -> ValidationPlateau<S, F> {
+        ValidationPlateau {
+            start_states,
+            agent_factory,
+            eval_interval,
+            max_steps,
+            min_delta,
+            patience,
+            best_reward: f64::NEG_INFINITY,
+            evaluations_without_improvement: 0,
+        }
+    }
+
+    /// The number of training steps between evaluations.
+    pub fn eval_interval(&self) -> u64 {
+        self.eval_interval
+    }
+
+    /// The highest total reward of the validation rollouts so far.
+    pub fn best_reward(&self) -> f64 {
+        self.best_reward
+    }
+
+    /// Runs the validation rollouts using the given trainer, and returns whether training should
+    /// end.
+    pub fn evaluate<T: QTable<S>>(&mut self, trainer: &AgentTrainer<S, T>) -> bool {
+        let reward: f64 = self
+            .start_states
+            .iter()
+            .map(|state| {
+                let mut agent = (self.agent_factory)(state);
+                trainer.rollout(&mut *agent, self.max_steps).total_reward
+            })
+            .sum();
+        if reward > self.best_reward + self.min_delta {
+            self.best_reward = reward;
+            self.evaluations_without_improvement = 0;
+        } else {
+            self.evaluations_without_improvement += 1;
+        }
+        self.evaluations_without_improvement >= self.patience
+    }
+}
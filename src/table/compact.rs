@@ -0,0 +1,194 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the compact table.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::compare_values;
+use crate::mdp::State;
+use crate::table::QTable;
+
+/// A type in which a [CompactTable] stores learned values. Learning strategies always compute in
+/// `f64`, the values are converted when they are stored and fetched.
+pub trait TableValue: Copy + PartialEq {
+    /// Converts a learned value to this type, rounding it if needed.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts a stored value back to `f64`.
+    fn to_f64(self) -> f64;
+
+    /// Converts the stored values of the actions of a `State` to `f64`, borrowing them if no
+    /// conversion is needed.
+    fn to_f64_values<A: Clone + Eq + Hash>(values: &HashMap<A, Self>) -> Cow<'_, HashMap<A, f64>>;
+}
+
+impl TableValue for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn to_f64_values<A: Clone + Eq + Hash>(values: &HashMap<A, Self>) -> Cow<'_, HashMap<A, f64>> {
+        Cow::Owned(
+            values
+                .iter()
+                .map(|(action, &value)| (action.clone(), value as f64))
+                .collect(),
+        )
+    }
+}
+
+impl TableValue for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn to_f64_values<A: Clone + Eq + Hash>(values: &HashMap<A, Self>) -> Cow<'_, HashMap<A, f64>> {
+        Cow::Borrowed(values)
+    }
+}
+
+/// A table storing all learned values as a [TableValue], `f32` by default. Values are converted
+/// to and from `f64` at the boundary, so the learning strategies compute in full precision but
+/// the stored values are rounded.
+///
+/// Every learned value is stored next to its action, and the pair is padded to the alignment of
+/// the action type. Storing `f32` values therefore only saves memory when actions are aligned to
+/// less than 8 bytes: an `i32` action takes 8 instead of 16 bytes per value, but a `usize` action
+/// takes 16 bytes either way. Since learning strategies receive the values of the actions of a
+/// `State` as `f64`, fetching them allocates a converted copy on every training step, unless the
+/// values are stored as `f64`.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::table::CompactTable;
+///
+/// let mut trainer = AgentTrainer::with_table(CompactTable::new());
+/// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(100000),
+///     &RandomExploration::new(),
+/// );
+///
+/// let test_state = MyState { x: 10, y: 9 };
+/// let go_up = MyAction { dx: 0, dy: -1 };
+/// let go_down = MyAction { dx: 0, dy: 1 };
+/// assert!(trainer.expected_value(&test_state, &go_down)
+///     > trainer.expected_value(&test_state, &go_up));
+///
+/// // The same, storing the values as `f64`.
+/// let mut trainer = AgentTrainer::with_table(CompactTable::<MyState, f64>::default());
+/// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(100000),
+///     &RandomExploration::new(),
+/// );
+/// assert!(trainer.expected_value(&test_state, &go_down)
+///     > trainer.expected_value(&test_state, &go_up));
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct CompactTable<S, V = f32>
+where
+    S: State,
+    V: TableValue,
+{
+    values: HashMap<S, HashMap<S::A, V>>,
+}
+
+impl<S> CompactTable<S>
+where
+    S: State,
+{
+    /// Creates an empty table storing values as `f32`. Use
+    /// [default()](#method.default) to store values as another [TableValue].
+    pub fn new() -> CompactTable<S> {
+        Self::default()
+    }
+}
+
+impl<S: State, V: TableValue> Default for CompactTable<S, V> {
+    fn default() -> Self {
+        CompactTable {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<S: State, V: TableValue> QTable<S> for CompactTable<S, V> {
+    fn get(&self, state: &S, action: &S::A) -> Option<f64> {
+        self.values
+            .get(state)
+            .and_then(|m| m.get(action))
+            .map(|&value| value.to_f64())
+    }
+
+    fn insert(&mut self, state: S, action: S::A, value: f64) {
+        self.values
+            .entry(state)
+            .or_default()
+            .insert(action, V::from_f64(value));
+    }
+
+    fn best(&self, state: &S) -> Option<(S::A, f64)> {
+        self.values
+            .get(state)
+            .and_then(|m| {
+                m.iter()
+                    .max_by(|&(_, &v1), &(_, &v2)| compare_values(v1.to_f64(), v2.to_f64()))
+            })
+            .map(|(action, &value)| (action.clone(), value.to_f64()))
+    }
+
+    fn action_values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>> {
+        self.values.get(state).map(V::to_f64_values)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&S, &S::A, f64)> + '_> {
+        Box::new(self.values.iter().flat_map(|(state, actions)| {
+            actions
+                .iter()
+                .map(move |(action, &value)| (state, action, value.to_f64()))
+        }))
+    }
+
+    fn num_states(&self) -> usize {
+        self.values.len()
+    }
+
+    fn num_state_actions(&self) -> usize {
+        self.values.values().map(|actions| actions.len()).sum()
+    }
+
+    fn contains_state(&self, state: &S) -> bool {
+        self.values.contains_key(state)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    fn remove_state(&mut self, state: &S) {
+        self.values.remove(state);
+    }
+}
@@ -0,0 +1,129 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the flat table.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::compare_values;
+use crate::mdp::State;
+use crate::table::QTable;
+
+/// A table storing the learned values of each `State` in a flat list of (action, value) pairs,
+/// instead of a `HashMap` per `State`. This avoids the overhead of the nested maps when only few
+/// actions are taken from each `State`.
+///
+/// Looking up the value of an action searches the list of its `State` linearly, so this table is
+/// slower than the default table for `State`s with many actions.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::fixtures::grid::{MyAction, MyAgent, MyState};
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::table::FlatTable;
+///
+/// let mut trainer = AgentTrainer::with_table(FlatTable::new());
+/// let mut agent = MyAgent { state: MyState { x: 0, y: 0 } };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(100000),
+///     &RandomExploration::new(),
+/// );
+///
+/// let test_state = MyState { x: 10, y: 9 };
+/// let go_up = MyAction { dx: 0, dy: -1 };
+/// let go_down = MyAction { dx: 0, dy: 1 };
+/// assert!(trainer.expected_value(&test_state, &go_down)
+///     > trainer.expected_value(&test_state, &go_up));
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct FlatTable<S>
+where
+    S: State,
+{
+    values: HashMap<S, Vec<(S::A, f64)>>,
+}
+
+impl<S> FlatTable<S>
+where
+    S: State,
+{
+    pub fn new() -> FlatTable<S> {
+        FlatTable {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<S: State> Default for FlatTable<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: State> QTable<S> for FlatTable<S> {
+    fn get(&self, state: &S, action: &S::A) -> Option<f64> {
+        self.values
+            .get(state)?
+            .iter()
+            .find(|(a, _)| a == action)
+            .map(|&(_, value)| value)
+    }
+
+    fn insert(&mut self, state: S, action: S::A, value: f64) {
+        let values = self.values.entry(state).or_default();
+        match values.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, v)) => *v = value,
+            None => values.push((action, value)),
+        }
+    }
+
+    fn best(&self, state: &S) -> Option<(S::A, f64)> {
+        self.values
+            .get(state)?
+            .iter()
+            .max_by(|(_, v1), (_, v2)| compare_values(*v1, *v2))
+            .cloned()
+    }
+
+    fn action_values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>> {
+        self.values
+            .get(state)
+            .map(|values| Cow::Owned(values.iter().cloned().collect()))
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&S, &S::A, f64)> + '_> {
+        Box::new(self.values.iter().flat_map(|(state, values)| {
+            values
+                .iter()
+                .map(move |(action, value)| (state, action, *value))
+        }))
+    }
+
+    fn num_states(&self) -> usize {
+        self.values.len()
+    }
+
+    fn num_state_actions(&self) -> usize {
+        self.values.values().map(|values| values.len()).sum()
+    }
+
+    fn contains_state(&self, state: &S) -> bool {
+        self.values.contains_key(state)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    fn remove_state(&mut self, state: &S) {
+        self.values.remove(state);
+    }
+}
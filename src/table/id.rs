@@ -0,0 +1,223 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module for the id table.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::compare_values;
+use crate::mdp::State;
+use crate::table::QTable;
+
+/// A table storing the learned values of every `State` with an [id](State::id) keyed on that id
+/// instead of on the full `State`. Looking up a value then hashes a single `u64`, which is much
+/// faster for `State`s that are expensive to hash, such as `State`s holding a `Vec`. Every
+/// `State` is still kept once, next to its values, so that the learned values can be iterated
+/// and exported as usual. `State`s without an id are keyed on the full `State`.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// # #[derive(PartialEq, Eq, Hash, Clone)]
+/// # struct MyAction { dx: i32, dy: i32 }
+/// // A position on the grid, stored as a `Vec` that is slow to hash.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct MyState { position: Vec<i32> }
+///
+/// impl State for MyState {
+///     type A = MyAction;
+/// #     fn reward(&self) -> f64 {
+/// #         -((((10 - self.position[0]).pow(2) + (10 - self.position[1]).pow(2)) as f64).sqrt())
+/// #     }
+/// #     fn actions(&self) -> Vec<MyAction> {
+/// #         vec![MyAction { dx: 0, dy: -1 }, MyAction { dx: 0, dy: 1 },
+/// #              MyAction { dx: -1, dy: 0 }, MyAction { dx: 1, dy: 0 }]
+/// #     }
+///     // ...
+///     fn id(&self) -> Option<u64> {
+///         Some((self.position[0] * 21 + self.position[1]) as u64)
+///     }
+/// }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, action: &MyAction) {
+/// #         let (x, y) = (self.state.position[0], self.state.position[1]);
+/// #         self.state = MyState {
+/// #             position: vec![(((x + action.dx) % 21) + 21) % 21, (((y + action.dy) % 21) + 21) % 21],
+/// #         }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::table::IdTable;
+///
+/// let mut trainer = AgentTrainer::with_table(IdTable::new());
+/// let mut agent = MyAgent { state: MyState { position: vec![0, 0] } };
+/// trainer.train(
+///     &mut agent,
+///     &QLearning::new(0.2, 0.01, 2.),
+///     &mut FixedIterations::new(100000),
+///     &RandomExploration::new(),
+/// );
+///
+/// let test_state = MyState { position: vec![10, 9] };
+/// let go_up = MyAction { dx: 0, dy: -1 };
+/// let go_down = MyAction { dx: 0, dy: 1 };
+/// assert!(trainer.expected_value(&test_state, &go_down)
+///     > trainer.expected_value(&test_state, &go_up));
+/// // All 21 by 21 positions were visited, and are exported with their best actions.
+/// assert_eq!(trainer.num_states(), 21 * 21);
+/// assert_eq!(trainer.export_policy().len(), 21 * 21);
+/// ```
+#[derive(Clone)]
+pub struct IdTable<S>
+where
+    S: State,
+{
+    values: IdMap<(S, HashMap<S::A, f64>)>,
+    unkeyed: HashMap<S, HashMap<S::A, f64>>,
+}
+
+/// A `HashMap` keyed on ids, hashed with an [IdHasher].
+type IdMap<V> = HashMap<u64, V, BuildHasherDefault<IdHasher>>;
+
+/// A hasher for ids, which mixes every word into the hash by a rotation and a multiplication with a
+/// large odd constant. This is much cheaper than the default hasher, and still spreads
+/// consecutive ids well.
+#[derive(Default)]
+struct IdHasher(u64);
+
+impl IdHasher {
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+}
+
+impl Hasher for IdHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.add(byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, id: u64) {
+        self.add(id);
+    }
+}
+
+impl<S> IdTable<S>
+where
+    S: State,
+{
+    pub fn new() -> IdTable<S> {
+        IdTable {
+            values: HashMap::default(),
+            unkeyed: HashMap::new(),
+        }
+    }
+
+    /// Returns the learned values for all actions in the given `State`.
+    fn state_values(&self, state: &S) -> Option<&HashMap<S::A, f64>> {
+        match state.id() {
+            Some(id) => self.values.get(&id).map(|(_, values)| values),
+            None => self.unkeyed.get(state),
+        }
+    }
+}
+
+impl<S: State> Default for IdTable<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: State> QTable<S> for IdTable<S> {
+    fn get(&self, state: &S, action: &S::A) -> Option<f64> {
+        self.state_values(state)
+            .and_then(|m| m.get(action))
+            .copied()
+    }
+
+    fn insert(&mut self, state: S, action: S::A, value: f64) {
+        let values = match state.id() {
+            Some(id) => {
+                &mut self
+                    .values
+                    .entry(id)
+                    .or_insert_with(|| (state, HashMap::new()))
+                    .1
+            }
+            None => self.unkeyed.entry(state).or_default(),
+        };
+        values.insert(action, value);
+    }
+
+    fn best(&self, state: &S) -> Option<(S::A, f64)> {
+        self.state_values(state)
+            .and_then(|m| {
+                m.iter()
+                    .max_by(|&(_, &v1), &(_, &v2)| compare_values(v1, v2))
+            })
+            .map(|(action, value)| (action.clone(), *value))
+    }
+
+    fn action_values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>> {
+        self.state_values(state).map(Cow::Borrowed)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&S, &S::A, f64)> + '_> {
+        let keyed = self
+            .values
+            .values()
+            .map(|(state, actions)| (state, actions));
+        Box::new(keyed.chain(&self.unkeyed).flat_map(|(state, actions)| {
+            actions
+                .iter()
+                .map(move |(action, value)| (state, action, *value))
+        }))
+    }
+
+    fn num_states(&self) -> usize {
+        self.values.len() + self.unkeyed.len()
+    }
+
+    fn num_state_actions(&self) -> usize {
+        self.values
+            .values()
+            .map(|(_, actions)| actions)
+            .chain(self.unkeyed.values())
+            .map(|actions| actions.len())
+            .sum()
+    }
+
+    fn contains_state(&self, state: &S) -> bool {
+        self.state_values(state).is_some()
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.unkeyed.clear();
+    }
+
+    fn remove_state(&mut self, state: &S) {
+        match state.id() {
+            Some(id) => {
+                self.values.remove(&id);
+            }
+            None => {
+                self.unkeyed.remove(state);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing tables to store learned values in.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub use self::compact::{CompactTable, TableValue};
+pub use self::flat::FlatTable;
+pub use self::id::IdTable;
+use crate::compare_values;
+use crate::mdp::State;
+
+pub mod compact;
+pub mod flat;
+pub mod id;
+
+/// A `QTable` stores the learned value of every action taken from every `State`. The default table
+/// of an [AgentTrainer](../struct.AgentTrainer.html) is a nested `HashMap`, other implementations
+/// can be supplied using
+/// [AgentTrainer::with_table()](../struct.AgentTrainer.html#method.with_table), such as the
+/// [CompactTable] which stores values as `f32` by default, or the [IdTable] which stores values keyed on
+/// compact ids of the `State`s.
+///
+/// A `QTable` does not have to store every value it is given. A function approximator can be used
+/// as a `QTable` by moving its parameters towards the value passed to
+/// [insert()](#tymethod.insert) and estimating the value in [get()](#tymethod.get), which allows
+/// learning in continuous state spaces. See the `linear_approx` example.
+pub trait QTable<S: State> {
+    /// Fetches the learned value for the given action in the given `State`, or `None` if no value
+    /// was learned.
+    fn get(&self, state: &S, action: &S::A) -> Option<f64>;
+    /// Stores the learned value for the given action in the given `State`.
+    fn insert(&mut self, state: S, action: S::A, value: f64);
+    /// Returns the action with the highest learned value in the given `State` together with its
+    /// value, or `None` if no values were learned.
+    fn best(&self, state: &S) -> Option<(S::A, f64)>;
+    /// Returns the learned values for all actions in the given `State`, or `None` if no values
+    /// were learned.
+    fn action_values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>>;
+    /// Iterates over all learned (state, action, value) triples.
+    fn iter(&self) -> Box<dyn Iterator<Item = (&S, &S::A, f64)> + '_>;
+    /// Returns the number of `State`s with at least one learned value.
+    fn num_states(&self) -> usize;
+    /// Returns the number of (state, action) pairs with a learned value.
+    fn num_state_actions(&self) -> usize;
+    /// Returns whether a value was learned for at least one action in the given `State`.
+    fn contains_state(&self, state: &S) -> bool;
+    /// Removes all learned values.
+    fn clear(&mut self);
+    /// Removes the learned values for all actions in the given `State`.
+    fn remove_state(&mut self, state: &S);
+}
+
+impl<S: State> QTable<S> for HashMap<S, HashMap<S::A, f64>> {
+    fn get(&self, state: &S, action: &S::A) -> Option<f64> {
+        HashMap::get(self, state).and_then(|m| m.get(action).copied())
+    }
+
+    fn insert(&mut self, state: S, action: S::A, value: f64) {
+        self.entry(state).or_default().insert(action, value);
+    }
+
+    fn best(&self, state: &S) -> Option<(S::A, f64)> {
+        HashMap::get(self, state)
+            .and_then(|m| {
+                m.iter()
+                    .max_by(|&(_, &v1), &(_, &v2)| compare_values(v1, v2))
+            })
+            .map(|(action, value)| (action.clone(), *value))
+    }
+
+    fn action_values(&self, state: &S) -> Option<Cow<'_, HashMap<S::A, f64>>> {
+        HashMap::get(self, state).map(Cow::Borrowed)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&S, &S::A, f64)> + '_> {
+        Box::new(HashMap::iter(self).flat_map(|(state, actions)| {
+            actions
+                .iter()
+                .map(move |(action, value)| (state, action, *value))
+        }))
+    }
+
+    fn num_states(&self) -> usize {
+        self.len()
+    }
+
+    fn num_state_actions(&self) -> usize {
+        self.values().map(|actions| actions.len()).sum()
+    }
+
+    fn contains_state(&self, state: &S) -> bool {
+        self.contains_key(state)
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+
+    fn remove_state(&mut self, state: &S) {
+        HashMap::remove(self, state);
+    }
+}
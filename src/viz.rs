@@ -0,0 +1,97 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Module containing helpers to visualize learned policies.
+
+use crate::mdp::State;
+use crate::table::QTable;
+use crate::AgentTrainer;
+
+/// Renders the best action of states on a 2D grid as text, with one line per row. `states` gives
+/// the column `x` and the row `y` of each `State` as `(x, y, state)`, and `to_char` gives the
+/// glyph of an action, such as an arrow for a move. Cells without a `State`, or with a `State`
+/// without a best action, are rendered as a space.
+///
+/// # Examples
+///
+/// ```
+/// # use rurel::mdp::{Agent, State};
+/// // A grid of 5 columns and 3 rows, in which only horizontal moves can be made.
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct MyState { x: i32, y: i32 }
+/// # impl State for MyState {
+/// #     type A = i32;
+/// #     fn reward(&self) -> f64 { -((self.x - 2).abs() as f64) }
+/// #     fn actions(&self) -> Vec<i32> { vec![-1, 1] }
+/// # }
+/// # struct MyAgent { state: MyState }
+/// # impl Agent<MyState> for MyAgent {
+/// #     fn current_state(&self) -> &MyState { &self.state }
+/// #     fn take_action(&mut self, dx: &i32) {
+/// #         self.state = MyState { x: (self.state.x + dx).clamp(0, 4), ..self.state }
+/// #     }
+/// # }
+/// use rurel::AgentTrainer;
+/// use rurel::strategy::explore::RandomExploration;
+/// use rurel::strategy::learn::QLearning;
+/// use rurel::strategy::terminate::FixedIterations;
+/// use rurel::viz::render_grid;
+///
+/// // The reward is highest in the middle column.
+/// let mut trainer = AgentTrainer::new();
+/// for y in 0..3 {
+///     let mut agent = MyAgent { state: MyState { x: 0, y } };
+///     trainer.train(
+///         &mut agent,
+///         &QLearning::new(0.2, 0.01, 2.),
+///         &mut FixedIterations::new(10000),
+///         &RandomExploration::new(),
+///     );
+/// }
+///
+/// // Leave out the middle column, where both moves are equally good.
+/// let states = (0..3).flat_map(|y| [0, 1, 3, 4].map(|x| (x, y, MyState { x, y })));
+/// let grid = render_grid(
+///     &trainer,
+///     states.map(|(x, y, state)| (x as usize, y as usize, state)),
+///     |dx| if *dx < 0 { '<' } else { '>' },
+/// );
+/// assert_eq!(grid, ">> <<\n>> <<\n>> <<\n");
+/// ```
+pub fn render_grid<S, T>(
+    trainer: &AgentTrainer<S, T>,
+    states: impl Iterator<Item = (usize, usize, S)>,
+    to_char: impl Fn(&S::A) -> char,
+) -> String
+where
+    S: State,
+    T: QTable<S>,
+{
+    render_grid_with(|state| trainer.best_action(state), states, to_char)
+}
+
+/// Renders the best action of states on a 2D grid as text, like [render_grid], for any way of
+/// finding the best action of a `State`, such as
+/// [DQNAgentTrainer::best_action](../dqn/struct.DQNAgentTrainer.html#method.best_action).
+pub fn render_grid_with<S>(
+    best_action: impl Fn(&S) -> Option<S::A>,
+    states: impl Iterator<Item = (usize, usize, S)>,
+    to_char: impl Fn(&S::A) -> char,
+) -> String
+where
+    S: State,
+{
+    let cells: Vec<(usize, usize, char)> = states
+        .map(|(x, y, state)| (x, y, best_action(&state).map_or(' ', |a| to_char(&a))))
+        .collect();
+    let width = cells.iter().map(|&(x, _, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|&(_, y, _)| y + 1).max().unwrap_or(0);
+    let mut grid = vec![vec![' '; width]; height];
+    for (x, y, c) in cells {
+        grid[y][x] = c;
+    }
+    grid.into_iter()
+        .flat_map(|row| row.into_iter().chain(std::iter::once('\n')))
+        .collect()
+}